@@ -0,0 +1,55 @@
+//! Timing gate for `StaticBst::contains_batch()` against `contains()`
+//! called once per probe.
+//!
+//! Run with `cargo bench --bench batch`. Both search the same random
+//! probes in a table far larger than CPU cache, best of several rounds
+//! is kept. Exits with failure when `contains_batch()` is slower than
+//! probing one by one.
+use std::hint::black_box;
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+
+use ds_bst::StaticBst;
+
+const ELEMENTS: u64 = 1 << 22;
+const PROBES: usize = 1 << 20;
+const ROUNDS: usize = 5;
+
+/// Xorshift probes, half of them stored in table.
+fn probes() -> Vec<u64> {
+    let mut x: u64 = 0x9E37_79B9_7F4A_7C15;
+    (0..PROBES).map(|_| {
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        x % (2 * ELEMENTS)
+    }).collect()
+}
+
+fn best_of<F: FnMut() -> usize>(mut f: F) -> Duration {
+    (0..ROUNDS).map(|_| {
+        let start = Instant::now();
+        black_box(f());
+        start.elapsed()
+    }).min().unwrap()
+}
+
+fn main() -> ExitCode {
+    let data: Vec<u64> = (0..ELEMENTS).map(|v| v * 2).collect();
+    let table = StaticBst::from_sorted(&data);
+    let probes = probes();
+
+    let single = best_of(|| probes.iter().filter(|&&p| table.contains(black_box(p))).count());
+    let batch = best_of(|| table.contains_batch(black_box(&probes)).into_iter().filter(|&v| v).count());
+
+    let per_probe = |d: Duration| d.as_nanos() as f64 / PROBES as f64;
+    let ratio = batch.as_secs_f64() / single.as_secs_f64();
+    println!("contains        {:8.1} ns/probe", per_probe(single));
+    println!("contains_batch  {:8.1} ns/probe ({:.2}x)", per_probe(batch), ratio);
+
+    if ratio > 1.0 {
+        eprintln!("contains_batch is slower than contains per probe");
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,54 @@
+//! Timing gate for `fast_lookup()` against plain `exists()`.
+//!
+//! Run with `cargo bench --features prefetch`. Both lookups search the
+//! same random keys in a tree far larger than CPU cache, best of several
+//! rounds is kept. Exits with failure when `fast_lookup()` is slower
+//! than `exists()` by more than `MAX_SLOWDOWN`.
+use std::hint::black_box;
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+
+use ds_bst::BinarySearchTree;
+
+const ELEMENTS: u64 = 1 << 21;
+const LOOKUPS: usize = 1 << 20;
+const ROUNDS: usize = 5;
+const MAX_SLOWDOWN: f64 = 1.05;
+
+/// Xorshift keys, half of them stored in tree.
+fn keys() -> Vec<u64> {
+    let mut x: u64 = 0x9E37_79B9_7F4A_7C15;
+    (0..LOOKUPS).map(|_| {
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        x % (2 * ELEMENTS)
+    }).collect()
+}
+
+fn best_of<F: FnMut() -> usize>(mut f: F) -> Duration {
+    (0..ROUNDS).map(|_| {
+        let start = Instant::now();
+        black_box(f());
+        start.elapsed()
+    }).min().unwrap()
+}
+
+fn main() -> ExitCode {
+    let root = BinarySearchTree::from((0..ELEMENTS).map(|v| v * 2).collect());
+    let keys = keys();
+
+    let plain = best_of(|| keys.iter().filter(|&&k| root.exists(black_box(k))).count());
+    let fast = best_of(|| keys.iter().filter(|&&k| root.fast_lookup(black_box(k))).count());
+
+    let per_lookup = |d: Duration| d.as_nanos() as f64 / LOOKUPS as f64;
+    let ratio = fast.as_secs_f64() / plain.as_secs_f64();
+    println!("exists       {:8.1} ns/lookup", per_lookup(plain));
+    println!("fast_lookup  {:8.1} ns/lookup ({:.2}x)", per_lookup(fast), ratio);
+
+    if ratio > MAX_SLOWDOWN {
+        eprintln!("fast_lookup is slower than exists by more than {}x", MAX_SLOWDOWN);
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,306 @@
+use crate::avl::AvlTree;
+use crate::bst::Tree;
+
+/// Shortest window of operations between two decisions.
+const MIN_WINDOW: usize = 64;
+/// Windows in a row a new strategy has to win before it is taken.
+const HYSTERESIS: usize = 2;
+/// Height over balanced height above which a plain tree is rebuilt.
+const MAX_SKEW: f64 = 3.0;
+
+/// How `AdaptiveTree` keeps its elements balanced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BalanceStrategy {
+    /// Plain tree, cheapest inserts while they arrive in random order.
+    Unbalanced,
+    /// Plain tree rebuilt balanced once it gets too skewed, for phases
+    /// of mostly lookups after a bulk load.
+    PeriodicRebuild,
+    /// `AvlTree` rotations, for inserts and lookups mixed together.
+    Avl
+}
+
+/// Workload seen by `AdaptiveTree` and decision it made from it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AdaptiveStats {
+    /// Strategy in use.
+    pub strategy: BalanceStrategy,
+    /// Inserts and removes in last completed window.
+    pub writes: usize,
+    /// Lookups in last completed window.
+    pub lookups: usize,
+    /// Height over height of a balanced tree of same size, measured
+    /// at end of last window.
+    pub skew: f64,
+    /// Number of strategy switches so far.
+    pub switches: usize,
+    /// Number of rebuilds so far.
+    pub rebuilds: usize
+}
+
+enum Storage<T> {
+    Plain(Tree<T>),
+    Avl(AvlTree<T>)
+}
+
+/// Tree which picks its balancing strategy from the workload.
+///
+/// Operations are counted in windows of `max(64, n / 4)` operations. At
+/// the end of each window, ratio of writes to lookups and skew of tree
+/// pick a strategy: mixed workloads use AVL rotations, phases of mostly
+/// lookups use a plain tree which is rebuilt once it is skewed, and so
+/// do phases of mostly inserts unless they keep tree balanced anyway.
+/// A new strategy is taken only after it wins two windows in a row and
+/// the mixed band is wider for leaving AVL than for entering it, so a
+/// workload near a threshold does not flip back and forth.
+/// Measuring skew walks the tree once per window, which stays amortized
+/// `O(1)` per operation.
+///
+/// # Example
+///
+/// ```rust
+/// use ds_bst::{AdaptiveTree, BalanceStrategy};
+///
+/// let mut tree = AdaptiveTree::new();
+/// // sorted bulk load, then a query phase
+/// for v in 0..1000 {
+///     tree.insert(v);
+/// }
+/// for v in 0..1000 {
+///     assert!(tree.contains(v));
+/// }
+/// assert_eq!(tree.stats().strategy, BalanceStrategy::PeriodicRebuild);
+/// assert!(tree.height() < 20);
+/// ```
+pub struct AdaptiveTree<T> {
+    storage: Storage<T>,
+    strategy: BalanceStrategy,
+    /// Strategy last windows voted for and how many in a row did.
+    candidate: (BalanceStrategy, usize),
+    writes: usize,
+    lookups: usize,
+    stats: AdaptiveStats
+}
+
+impl<T: PartialOrd + Copy> Default for AdaptiveTree<T> {
+    fn default() -> Self {
+        AdaptiveTree::new()
+    }
+}
+
+impl<T: PartialOrd + Copy> AdaptiveTree<T> {
+    /// Contructor creates an empty tree, starting without balancing.
+    pub fn new() -> AdaptiveTree<T> {
+        let strategy = BalanceStrategy::Unbalanced;
+        AdaptiveTree {
+            storage: Storage::Plain(Tree::new()),
+            strategy,
+            candidate: (strategy, 0),
+            writes: 0,
+            lookups: 0,
+            stats: AdaptiveStats { strategy, writes: 0, lookups: 0, skew: 1.0, switches: 0, rebuilds: 0 }
+        }
+    }
+
+    /// Number of elements in a tree.
+    /// Uses `O(1)` time.
+    pub fn len(&self) -> usize {
+        match self.storage {
+            Storage::Plain(ref tree) => tree.len(),
+            Storage::Avl(ref tree) => tree.len()
+        }
+    }
+
+    /// Checks if tree has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Workload of last window and strategy chosen from it.
+    pub fn stats(&self) -> AdaptiveStats {
+        self.stats
+    }
+
+    /// Inserts an element in a tree.
+    pub fn insert(&mut self, val: T) {
+        match self.storage {
+            Storage::Plain(ref mut tree) => tree.insert(val),
+            Storage::Avl(ref mut tree) => tree.insert(val)
+        }
+        self.writes += 1;
+        self.end_of_op();
+    }
+
+    /// Removes one copy of `val`, returns `false` if it does not exist.
+    pub fn remove(&mut self, val: T) -> bool {
+        let removed = match self.storage {
+            Storage::Plain(ref mut tree) => tree.remove(&val),
+            Storage::Avl(ref mut tree) => tree.remove(val)
+        };
+        self.writes += 1;
+        self.end_of_op();
+        removed
+    }
+
+    /// Checks if element exists in a tree.
+    pub fn contains(&mut self, val: T) -> bool {
+        let found = match self.storage {
+            Storage::Plain(ref tree) => tree.contains(&val),
+            Storage::Avl(ref tree) => tree.exists(val)
+        };
+        self.lookups += 1;
+        self.end_of_op();
+        found
+    }
+
+    /// Inorder traverse tree which yields elements in sorted order.
+    /// Uses `O(n)` time.
+    pub fn inorder(&self) -> Vec<T> {
+        match self.storage {
+            Storage::Plain(ref tree) => tree.inorder(),
+            Storage::Avl(ref tree) => tree.inorder()
+        }
+    }
+
+    /// Calculates tree maximum height.
+    /// Uses `O(n)` time.
+    pub fn height(&self) -> usize {
+        match self.storage {
+            Storage::Plain(ref tree) => tree.root().map_or(0, |r| r.height()),
+            Storage::Avl(ref tree) => tree.height()
+        }
+    }
+
+    fn end_of_op(&mut self) {
+        if self.writes + self.lookups >= MIN_WINDOW.max(self.len() / 4) {
+            self.decide();
+        }
+    }
+
+    /// Closes a window, votes for a strategy and applies it.
+    fn decide(&mut self) {
+        let (writes, lookups) = (std::mem::take(&mut self.writes), std::mem::take(&mut self.lookups));
+        let share = writes as f64 / (writes + lookups) as f64;
+        let ideal = (self.len() as f64 + 1.0).log2().ceil().max(1.0);
+        let skew = self.height() as f64 / ideal;
+
+        let (low, high) = if self.strategy == BalanceStrategy::Avl { (0.1, 0.9) } else { (0.2, 0.8) };
+        let vote = if share > low && share < high {
+            BalanceStrategy::Avl
+        } else if share <= low || skew > MAX_SKEW {
+            BalanceStrategy::PeriodicRebuild
+        } else {
+            BalanceStrategy::Unbalanced
+        };
+        self.candidate = if self.candidate.0 == vote { (vote, self.candidate.1 + 1) } else { (vote, 1) };
+        if vote != self.strategy && self.candidate.1 >= HYSTERESIS {
+            self.switch_to(vote);
+        }
+        if self.strategy == BalanceStrategy::PeriodicRebuild && skew > MAX_SKEW {
+            self.rebuild();
+        }
+
+        self.stats = AdaptiveStats {
+            strategy: self.strategy,
+            writes,
+            lookups,
+            skew,
+            switches: self.stats.switches,
+            rebuilds: self.stats.rebuilds
+        };
+    }
+
+    fn switch_to(&mut self, strategy: BalanceStrategy) {
+        let storage = std::mem::replace(&mut self.storage, Storage::Plain(Tree::new()));
+        self.storage = match (strategy, storage) {
+            (BalanceStrategy::Avl, Storage::Plain(plain)) => {
+                let mut tree = AvlTree::new();
+                for v in plain {
+                    tree.insert(v);
+                }
+                Storage::Avl(tree)
+            },
+            (BalanceStrategy::Avl, avl) => avl,
+            (_, Storage::Avl(avl)) => Storage::Plain(Tree::from(avl.inorder())),
+            (_, plain) => plain
+        };
+        self.strategy = strategy;
+        self.stats.switches += 1;
+    }
+
+    fn rebuild(&mut self) {
+        if let Storage::Plain(ref mut tree) = self.storage {
+            *tree = Tree::from(tree.take().into_iter().collect::<Vec<T>>());
+            self.stats.rebuilds += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AdaptiveTree, BalanceStrategy};
+
+    /// Pseudo random keys below `1000`.
+    fn keys(n: usize) -> Vec<u32> {
+        let mut x: u64 = 42;
+        (0..n).map(|_| {
+            x = x.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (x >> 33) as u32 % 1000
+        }).collect()
+    }
+
+    #[test]
+    fn phases() {
+        let mut tree = AdaptiveTree::new();
+        for v in keys(200) {
+            tree.insert(v);
+        }
+        assert_eq!(tree.stats().strategy, BalanceStrategy::Unbalanced);
+
+        // mixed phase moves to rotations
+        for v in 1000..1400 {
+            tree.insert(v);
+            tree.contains(v / 2);
+        }
+        assert_eq!(tree.stats().strategy, BalanceStrategy::Avl);
+        assert_eq!(tree.stats().switches, 1);
+        assert!(tree.height() <= 14);
+
+        // query phase leaves rotations
+        for v in 0..2000 {
+            tree.contains(v);
+        }
+        let stats = tree.stats();
+        assert_eq!(stats.strategy, BalanceStrategy::PeriodicRebuild);
+        assert_eq!((stats.writes, stats.switches), (0, 2));
+        assert_eq!(tree.len(), 600);
+
+        // sorted bulk load is rebuilt as it goes
+        for v in 2000..5000 {
+            tree.insert(v);
+        }
+        assert!(tree.stats().rebuilds > 0);
+        assert_eq!(tree.stats().strategy, BalanceStrategy::PeriodicRebuild);
+        assert!(tree.remove(4999));
+        assert!(tree.inorder().windows(2).all(|w| w[0] <= w[1]));
+        assert_eq!(tree.len(), 3599);
+    }
+    #[test]
+    fn hysteresis() {
+        let mut tree = AdaptiveTree::new();
+        for v in keys(64) {
+            tree.insert(v);
+        }
+        // a single mixed window does not switch
+        for v in 0..32 {
+            tree.insert(v);
+            tree.contains(v);
+        }
+        assert_eq!(tree.stats().strategy, BalanceStrategy::Unbalanced);
+        for v in 0..32 {
+            tree.insert(v);
+            tree.contains(v);
+        }
+        assert_eq!(tree.stats().strategy, BalanceStrategy::Avl);
+    }
+}
@@ -0,0 +1,234 @@
+use std::ops::RangeBounds;
+
+use crate::avl::Height;
+use crate::balanced::{self, metric, Link, Node};
+use crate::bst::{before_end, before_start};
+use crate::fold::Fold;
+use crate::search::{self, Nodes};
+
+/// AVL balanced binary search tree keeping fold `F` of every sub tree
+/// in its nodes.
+///
+/// Folds are recomputed bottom up after each insert, remove or rotation
+/// below a node, so aggregates such as sums or maxima stay current and
+/// `range_fold()` or algorithms reading them through `root()` handles
+/// get them from at most two paths of `O(log n)` nodes. With the default
+/// `F = ()` it keeps no fold.
+///
+/// # Example
+///
+/// ```rust
+/// use ds_bst::{AugmentedTree, Sum};
+///
+/// // every node keeps sum of its sub tree
+/// let mut tree: AugmentedTree<u32, Sum> = AugmentedTree::new();
+/// for v in [5, 3, 8] {
+///     tree.insert(v);
+/// }
+/// assert_eq!(tree.root().map(|n| *n.meta()), Some(16));
+/// assert_eq!(tree.range_fold(4..), 13);
+/// ```
+pub struct AugmentedTree<T, F: Fold<T> = ()> {
+    root: Link<T, F>
+}
+
+impl<T: PartialOrd + Copy, F: Fold<T>> Default for AugmentedTree<T, F> {
+    fn default() -> Self {
+        AugmentedTree::new()
+    }
+}
+
+impl<T: PartialOrd + Copy, F: Fold<T>> AugmentedTree<T, F> {
+    /// Contructor creates an empty tree
+    pub fn new() -> AugmentedTree<T, F> {
+        AugmentedTree { root: None }
+    }
+
+    /// Checks if tree has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    /// Handle to the root node, `None` for an empty tree.
+    pub fn root(&self) -> Option<AugmentedNode<'_, T, F>> {
+        self.root.as_deref().map(|node| AugmentedNode { node })
+    }
+
+    /// Inserts an element in a tree, folds are updated on the way up.
+    /// Uses `O(log n)` time.
+    pub fn insert(&mut self, val: T) {
+        balanced::insert::<Height, _, _>(&mut self.root, val);
+    }
+
+    /// Removes one copy of `val`, returns `false` if it does not exist.
+    /// Uses `O(log n)` time.
+    pub fn remove(&mut self, val: T) -> bool {
+        balanced::remove::<Height, _, _>(&mut self.root, &val)
+    }
+
+    /// Checks if element exists in a tree.
+    /// Uses `O(log n)` time.
+    pub fn exists(&self, val: T) -> bool {
+        search::exists(self.root.as_deref(), &val)
+    }
+
+    /// Aggregates elements within `range` from folds of sub trees which
+    /// lie entirely inside it, so only nodes on paths to both ends of
+    /// range are visited.
+    /// Uses `O(log n)` time.
+    pub fn range_fold<R>(&self, range: R) -> F::Acc
+        where
+            R: RangeBounds<T>
+    {
+        fn fold<T: PartialOrd, F: Fold<T>, R: RangeBounds<T>>(
+            link: &Link<T, F>, range: &R, above_start: bool, below_end: bool) -> F::Acc {
+            let node = match link {
+                None => return F::identity(),
+                Some(n) if above_start && below_end => return n.acc.clone(),
+                Some(n) => n
+            };
+            if !above_start && before_start(range.start_bound(), &node.val) {
+                return fold(&node.right, range, above_start, below_end);
+            }
+            if !below_end && !before_end(range.end_bound(), &node.val) {
+                return fold(&node.left, range, above_start, below_end);
+            }
+            let left = fold(&node.left, range, above_start, true);
+            let right = fold(&node.right, range, true, below_end);
+            F::combine(F::combine(left, F::from_value(&node.val)), right)
+        }
+
+        fold(&self.root, &range, false, false)
+    }
+
+    /// Calculates tree maximum height, kept in root node.
+    /// Uses `O(1)` time.
+    pub fn height(&self) -> usize {
+        metric(&self.root)
+    }
+
+    /// Inorder traverse tree which yields elements in sorted order.
+    /// Uses `O(n)` time.
+    pub fn inorder(&self) -> Vec<T> {
+        Nodes::new(self.root.as_deref()).map(|node| node.val).collect()
+    }
+}
+
+/// Read-only handle to a node of `AugmentedTree`.
+pub struct AugmentedNode<'a, T, F: Fold<T>> {
+    node: &'a Node<T, F>
+}
+
+impl<'a, T, F: Fold<T>> AugmentedNode<'a, T, F> {
+    /// Value stored in this node.
+    pub fn value(&self) -> &'a T {
+        &self.node.val
+    }
+
+    /// Fold of the sub tree rooted at this node.
+    pub fn meta(&self) -> &'a F::Acc {
+        &self.node.acc
+    }
+
+    /// Left child of this node.
+    pub fn left(&self) -> Option<AugmentedNode<'a, T, F>> {
+        self.node.left.as_deref().map(|node| AugmentedNode { node })
+    }
+
+    /// Right child of this node.
+    pub fn right(&self) -> Option<AugmentedNode<'a, T, F>> {
+        self.node.right.as_deref().map(|node| AugmentedNode { node })
+    }
+}
+
+impl<'a, T, F: Fold<T>> Clone for AugmentedNode<'a, T, F> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T, F: Fold<T>> Copy for AugmentedNode<'a, T, F> {}
+
+#[cfg(test)]
+mod tests {
+    use super::{AugmentedNode, AugmentedTree};
+    use crate::fold::Fold;
+
+    /// Maximum and number of elements of a sub tree.
+    struct MaxCount;
+
+    impl Fold<i32> for MaxCount {
+        type Acc = (i32, usize);
+
+        fn identity() -> (i32, usize) {
+            (i32::MIN, 0)
+        }
+
+        fn from_value(v: &i32) -> (i32, usize) {
+            (*v, 1)
+        }
+
+        fn combine(a: (i32, usize), b: (i32, usize)) -> (i32, usize) {
+            (a.0.max(b.0), a.1 + b.1)
+        }
+    }
+
+    fn check(node: Option<AugmentedNode<'_, i32, MaxCount>>) -> usize {
+        match node {
+            None => 0,
+            Some(n) => {
+                let mut expected = MaxCount::from_value(n.value());
+                for child in [n.left(), n.right()].into_iter().flatten() {
+                    expected = MaxCount::combine(expected, *child.meta());
+                }
+                assert_eq!(*n.meta(), expected);
+                let (hl, hr) = (check(n.left()), check(n.right()));
+                assert!(hl.abs_diff(hr) <= 1);
+                hl.max(hr) + 1
+            }
+        }
+    }
+
+    #[test]
+    fn metadata() {
+        let mut tree: AugmentedTree<i32, MaxCount> = AugmentedTree::new();
+        for v in [50, 30, 70, 20, 40, 60, 80, 35, 45, 65] {
+            tree.insert(v);
+        }
+        assert_eq!(*tree.root().unwrap().meta(), (80, 10));
+        check(tree.root());
+
+        assert!(tree.remove(30));
+        assert!(tree.remove(80));
+        assert!(tree.remove(50));
+        assert!(!tree.remove(99));
+        check(tree.root());
+        assert_eq!(*tree.root().unwrap().meta(), (70, 7));
+        assert_eq!(tree.inorder(), vec![20, 35, 40, 45, 60, 65, 70]);
+    }
+    #[test]
+    fn sorted_inserts_stay_balanced() {
+        let mut tree: AugmentedTree<i32, MaxCount> = AugmentedTree::new();
+        for v in 0..1000 {
+            tree.insert(v);
+        }
+        assert_eq!(check(tree.root()), 10);
+        assert_eq!(tree.height(), 10);
+        assert_eq!(tree.range_fold(100..200), (199, 100));
+        for v in (0..1000).step_by(3) {
+            assert!(tree.remove(v));
+        }
+        check(tree.root());
+        assert_eq!(tree.range_fold(..), (998, 666));
+    }
+    #[test]
+    fn plain() {
+        let mut tree = AugmentedTree::<i32>::new();
+        tree.insert(2);
+        tree.insert(1);
+        assert!(tree.exists(1));
+        assert!(tree.remove(2));
+        assert!(tree.remove(1));
+        assert!(tree.is_empty());
+    }
+}
@@ -0,0 +1,243 @@
+use std::cmp::max;
+use std::ops::RangeBounds;
+
+use crate::balanced::{self, metric, Link, Metric};
+use crate::search::{self, Nodes};
+
+/// Nodes keep height of their sub tree.
+pub(crate) enum Height {}
+
+impl Metric for Height {
+    fn of(left: usize, right: usize) -> usize {
+        max(left, right) + 1
+    }
+
+    fn heavier(a: usize, b: usize) -> bool {
+        a > b + 1
+    }
+
+    fn single_rotation(inner: usize, outer: usize) -> bool {
+        inner <= outer
+    }
+}
+
+/// Implements an AVL height-balanced binary search tree.
+///
+/// Every node keeps the height of its sub tree, heights of the two sub
+/// trees of any node differ by at most one. Insert and remove restore it
+/// with rotations on the way back up, so even sorted input keeps height
+/// below `1.45 log n` and every operation runs in `O(log n)` time.
+///
+/// # Example
+///
+/// ```rust
+/// use ds_bst::AvlTree;
+///
+/// let mut tree = AvlTree::new();
+/// for v in 1..=1000 {
+///     tree.insert(v);
+/// }
+/// assert_eq!(tree.height(), 10);
+/// assert!(tree.remove(500));
+/// assert_eq!(tree.len(), 999);
+/// ```
+pub struct AvlTree<T> {
+    root: Link<T>,
+    len: usize
+}
+
+impl<T> Default for AvlTree<T> {
+    fn default() -> Self {
+        AvlTree { root: None, len: 0 }
+    }
+}
+
+impl<T: PartialOrd + Copy> AvlTree<T> {
+    /// Contructor creates an empty tree
+    pub fn new() -> AvlTree<T> {
+        AvlTree::default()
+    }
+
+    /// Number of elements in a tree.
+    /// Uses `O(1)` time.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Checks if tree has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    /// Inserts an element in a tree.
+    /// Uses `O(log n)` time.
+    pub fn insert(&mut self, val: T) {
+        balanced::insert::<Height, _, _>(&mut self.root, val);
+        self.len += 1;
+    }
+
+    /// Removes one copy of `val`, returns `false` if it does not exist.
+    /// Uses `O(log n)` time.
+    pub fn remove(&mut self, val: T) -> bool {
+        let removed = balanced::remove::<Height, _, _>(&mut self.root, &val);
+        if removed {
+            self.len -= 1;
+        }
+        removed
+    }
+
+    /// Checks if element exists in a tree.
+    /// Uses `O(log n)` time.
+    pub fn exists(&self, val: T) -> bool {
+        search::exists(self.root.as_deref(), &val)
+    }
+
+    /// Finds minimum element in a tree.
+    /// Uses `O(log n)` time.
+    pub fn find_min(&self) -> Option<T> {
+        let mut node = self.root.as_ref()?;
+        while let Some(ref left) = node.left {
+            node = left;
+        }
+        Some(node.val)
+    }
+
+    /// Finds maximum element in a tree.
+    /// Uses `O(log n)` time.
+    pub fn find_max(&self) -> Option<T> {
+        let mut node = self.root.as_ref()?;
+        while let Some(ref right) = node.right {
+            node = right;
+        }
+        Some(node.val)
+    }
+
+    /// Inorder traverse tree which yields elements in sorted order.
+    /// Uses `O(n)` time.
+    pub fn inorder(&self) -> Vec<T> {
+        self.iter().copied().collect()
+    }
+
+    /// Iterates elements in sorted order.
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        Nodes::new(self.root.as_deref()).map(|node| &node.val)
+    }
+
+    /// Iterates elements within `range` in sorted order, sub trees
+    /// before its start are skipped.
+    /// Uses `O(log n + k)` time for `k` elements in range.
+    pub fn range<'a, R>(&'a self, range: R) -> impl Iterator<Item = &'a T> + 'a
+        where
+            R: RangeBounds<T> + 'a
+    {
+        Nodes::range(self.root.as_deref(), range).map(|node| &node.val)
+    }
+
+    /// Calculates tree maximum height, kept in root node.
+    /// Uses `O(1)` time.
+    pub fn height(&self) -> usize {
+        metric(&self.root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{metric, AvlTree, Link};
+
+    /// Checks stored heights and AVL invariant, returns real height.
+    fn assert_balanced<T>(link: &Link<T>) -> usize {
+        match link {
+            None => 0,
+            Some(node) => {
+                let hl = assert_balanced(&node.left);
+                let hr = assert_balanced(&node.right);
+                assert!(hl.abs_diff(hr) <= 1);
+                assert_eq!(node.metric, hl.max(hr) + 1);
+                node.metric
+            }
+        }
+    }
+
+    #[test]
+    fn sorted_inserts_stay_balanced() {
+        let mut tree = AvlTree::new();
+        for v in 0..1000 {
+            tree.insert(v);
+        }
+        assert_balanced(&tree.root);
+        assert_eq!(tree.len(), 1000);
+        assert_eq!(tree.height(), 10);
+        assert_eq!(tree.inorder(), (0..1000).collect::<Vec<_>>());
+        assert!(tree.exists(999));
+        assert!(!tree.exists(1000));
+
+        let mut tree = AvlTree::new();
+        for v in (0..1000).rev() {
+            tree.insert(v);
+        }
+        assert_balanced(&tree.root);
+        assert_eq!(tree.height(), 10);
+    }
+    #[test]
+    fn removals_stay_balanced() {
+        let mut tree = AvlTree::new();
+        for v in 0..300 {
+            tree.insert((v * 37) % 100);
+        }
+        assert_balanced(&tree.root);
+        assert_eq!(tree.find_min(), Some(0));
+        assert_eq!(tree.find_max(), Some(99));
+
+        for v in 0..100 {
+            assert!(tree.remove(v));
+            assert_balanced(&tree.root);
+        }
+        assert_eq!(tree.len(), 200);
+        assert!(!tree.remove(100));
+        for v in (0..100).rev() {
+            assert!(tree.remove(v));
+            assert!(tree.remove(v));
+            assert_balanced(&tree.root);
+        }
+        assert!(tree.is_empty());
+        assert_eq!(metric(&tree.root), 0);
+        assert_eq!(tree.find_min(), None);
+    }
+    #[test]
+    fn rotations_reuse_nodes() {
+        fn addresses<T: Copy>(link: &Link<T>, out: &mut Vec<(T, usize)>) {
+            if let Some(node) = link {
+                addresses(&node.left, out);
+                out.push((node.val, &**node as *const _ as usize));
+                addresses(&node.right, out);
+            }
+        }
+
+        let mut tree = AvlTree::new();
+        let mut before = Vec::new();
+        for v in 0..64 {
+            tree.insert(v);
+            addresses(&tree.root, &mut before);
+        }
+        let mut after = Vec::new();
+        addresses(&tree.root, &mut after);
+        assert!(after.iter().all(|node| before.contains(node)));
+
+        assert!(tree.remove(31));
+        let mut removed = Vec::new();
+        addresses(&tree.root, &mut removed);
+        assert!(removed.iter().all(|&(v, a)| after.iter().any(|&(_, b)| a == b) && v != 31));
+    }
+    #[test]
+    fn iter_range() {
+        let mut tree = AvlTree::new();
+        for v in (0..100).rev() {
+            tree.insert(v * 2);
+        }
+        assert!(tree.iter().copied().eq((0..100).map(|v| v * 2)));
+        assert_eq!(tree.range(9..=16).copied().collect::<Vec<_>>(), vec![10, 12, 14, 16]);
+        assert_eq!(tree.range(190..).count(), 5);
+        assert_eq!(tree.range(..0).count(), 0);
+        assert!(AvlTree::<i32>::new().iter().next().is_none());
+    }
+}
@@ -0,0 +1,175 @@
+//! Rotations and descent shared by self-balancing trees. Trees differ
+//! only in what every node keeps about its sub tree, its `Metric`, and
+//! when that makes a node out of balance. Nodes may also keep a `Fold`
+//! of their sub tree, which is updated along with the metric.
+use crate::fold::Fold;
+use crate::search::SearchNode;
+
+pub(crate) type Link<T, F = ()> = Option<Box<Node<T, F>>>;
+
+pub(crate) struct Node<T, F: Fold<T> = ()> {
+    pub(crate) val: T,
+    /// Height or size of sub tree, whichever `Metric` keeps.
+    pub(crate) metric: usize,
+    /// Fold of sub tree, `()` for trees which keep none.
+    pub(crate) acc: F::Acc,
+    pub(crate) left: Link<T, F>,
+    pub(crate) right: Link<T, F>
+}
+
+impl<T: PartialOrd, F: Fold<T>> SearchNode for Node<T, F> {
+    type Val = T;
+
+    fn val(&self) -> &T {
+        &self.val
+    }
+
+    fn left(&self) -> Option<&Self> {
+        self.left.as_deref()
+    }
+
+    fn right(&self) -> Option<&Self> {
+        self.right.as_deref()
+    }
+}
+
+/// Balance condition of a tree, given as metrics of sub trees.
+pub(crate) trait Metric {
+    /// Metric of a node whose sub trees have metrics `left` and `right`.
+    fn of(left: usize, right: usize) -> usize;
+
+    /// Checks if sub tree with metric `a` is too heavy next to its
+    /// sibling with metric `b`.
+    fn heavier(a: usize, b: usize) -> bool;
+
+    /// Checks if a heavy sub tree is fixed by a single rotation, given
+    /// metrics of its children nearer to and further from its sibling.
+    fn single_rotation(inner: usize, outer: usize) -> bool;
+}
+
+pub(crate) fn metric<T, F: Fold<T>>(link: &Link<T, F>) -> usize {
+    match link {
+        None => 0,
+        Some(node) => node.metric
+    }
+}
+
+fn acc<T, F: Fold<T>>(link: &Link<T, F>) -> F::Acc {
+    match link {
+        None => F::identity(),
+        Some(node) => node.acc.clone()
+    }
+}
+
+pub(crate) fn node<M: Metric, T, F: Fold<T>>(val: T, left: Link<T, F>, right: Link<T, F>) -> Box<Node<T, F>> {
+    let mut node = Box::new(Node { metric: 0, acc: F::identity(), val, left, right });
+    update::<M, _, _>(&mut node);
+    node
+}
+
+/// Recomputes metric and fold of `node` from its sub trees.
+pub(crate) fn update<M: Metric, T, F: Fold<T>>(node: &mut Node<T, F>) {
+    node.metric = M::of(metric(&node.left), metric(&node.right));
+    node.acc = F::combine(F::combine(acc(&node.left), F::from_value(&node.val)), acc(&node.right));
+}
+
+/// Makes right child of `root` the new root of sub tree, swapping links
+/// so both nodes keep their allocations.
+fn rotate_left<M: Metric, T, F: Fold<T>>(root: &mut Box<Node<T, F>>) {
+    let mut right = root.right.take().expect("rotated node has a right child");
+    root.right = right.left.take();
+    update::<M, _, _>(root);
+    std::mem::swap(root, &mut right);
+    root.left = Some(right);
+    update::<M, _, _>(root);
+}
+
+/// Makes left child of `root` the new root of sub tree, swapping links
+/// so both nodes keep their allocations.
+fn rotate_right<M: Metric, T, F: Fold<T>>(root: &mut Box<Node<T, F>>) {
+    let mut left = root.left.take().expect("rotated node has a left child");
+    root.left = left.right.take();
+    update::<M, _, _>(root);
+    std::mem::swap(root, &mut left);
+    root.right = Some(left);
+    update::<M, _, _>(root);
+}
+
+/// Restores balance of `node` whose sub trees are at most one step out
+/// of balance, rotating in place, and updates its metric and fold.
+pub(crate) fn balance<M: Metric, T, F: Fold<T>>(node: &mut Box<Node<T, F>>) {
+    let (ml, mr) = (metric(&node.left), metric(&node.right));
+    if M::heavier(mr, ml) {
+        let right = node.right.as_mut().expect("heavier side is not empty");
+        if !M::single_rotation(metric(&right.left), metric(&right.right)) {
+            rotate_right::<M, _, _>(right);
+        }
+        rotate_left::<M, _, _>(node);
+    } else if M::heavier(ml, mr) {
+        let left = node.left.as_mut().expect("heavier side is not empty");
+        if !M::single_rotation(metric(&left.right), metric(&left.left)) {
+            rotate_left::<M, _, _>(left);
+        }
+        rotate_right::<M, _, _>(node);
+    } else {
+        update::<M, _, _>(node);
+    }
+}
+
+/// Unlinks node with minimum element of sub tree, its right sub tree
+/// takes its place.
+pub(crate) fn pop_min<M: Metric, T, F: Fold<T>>(link: &mut Link<T, F>) -> Option<Box<Node<T, F>>> {
+    let node = link.as_mut()?;
+    if node.left.is_some() {
+        let min = pop_min::<M, _, _>(&mut node.left);
+        balance::<M, _, _>(node);
+        min
+    } else {
+        let mut min = link.take()?;
+        *link = min.right.take();
+        Some(min)
+    }
+}
+
+pub(crate) fn insert<M: Metric, T: PartialOrd, F: Fold<T>>(link: &mut Link<T, F>, val: T) {
+    match link {
+        None => *link = Some(node::<M, _, _>(val, None, None)),
+        Some(node) => {
+            if node.val > val {
+                insert::<M, _, _>(&mut node.left, val);
+            } else {
+                insert::<M, _, _>(&mut node.right, val);
+            }
+            balance::<M, _, _>(node);
+        }
+    }
+}
+
+/// Removes one copy of `val`, its in-order successor takes its place.
+/// Returns `false` if it does not exist.
+pub(crate) fn remove<M: Metric, T: PartialOrd, F: Fold<T>>(link: &mut Link<T, F>, val: &T) -> bool {
+    let node = match link {
+        None => return false,
+        Some(n) => n
+    };
+    let removed = if node.val == *val {
+        match pop_min::<M, _, _>(&mut node.right) {
+            None => {
+                *link = node.left.take();
+                return true;
+            },
+            Some(min) => node.val = min.val
+        }
+        true
+    } else if node.val > *val {
+        remove::<M, _, _>(&mut node.left, val)
+    } else if node.val < *val {
+        remove::<M, _, _>(&mut node.right, val)
+    } else {
+        false
+    };
+    if removed {
+        balance::<M, _, _>(node);
+    }
+    removed
+}
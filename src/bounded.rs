@@ -0,0 +1,138 @@
+use crate::bst::{BinarySearchTree, Tree};
+
+/// Which element `BoundedBst` drops once it is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvictionPolicy {
+    /// Drop the smallest element, keeps the `k` largest ones.
+    EvictSmallest,
+    /// Drop the largest element, keeps the `k` smallest ones.
+    EvictLargest
+}
+
+/// Binary search tree holding at most `capacity` elements.
+///
+/// Once full every insert evicts one element according to its policy,
+/// so top-k or bottom-k of a stream is tracked without popping by hand.
+/// Unlike `BinarySearchTree` it can be empty.
+///
+/// # Example
+///
+/// ```rust
+/// use ds_bst::{BoundedBst, EvictionPolicy};
+///
+/// let mut top = BoundedBst::new(3, EvictionPolicy::EvictSmallest);
+/// for v in [5, 1, 9, 7, 3] {
+///     top.insert(v);
+/// }
+/// assert_eq!(top.inorder(), vec![5, 7, 9]);
+/// ```
+pub struct BoundedBst<T> {
+    tree: Tree<T>,
+    capacity: usize,
+    policy: EvictionPolicy
+}
+
+impl<T: PartialOrd + Copy> BoundedBst<T> {
+    /// Contructor creates an empty tree
+    pub fn new(capacity: usize, policy: EvictionPolicy) -> BoundedBst<T> {
+        BoundedBst { tree: Tree::new(), capacity, policy }
+    }
+
+    /// Number of elements in a tree.
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Checks if tree has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// Maximum number of elements.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Underlying tree, `None` when empty.
+    pub fn tree(&self) -> Option<&BinarySearchTree<T>> {
+        self.tree.root()
+    }
+
+    /// Inserts an element and returns evicted one when tree was full,
+    /// which is `val` itself when it would be evicted right away.
+    /// Uses `O(n)` time.
+    pub fn insert(&mut self, val: T) -> Option<T> {
+        if self.len() < self.capacity {
+            self.tree.insert(val);
+            return None;
+        }
+
+        let evicted = match self.policy {
+            EvictionPolicy::EvictSmallest => self.tree.first().filter(|&&min| min < val),
+            EvictionPolicy::EvictLargest => self.tree.last().filter(|&&max| max > val)
+        };
+        match evicted.copied() {
+            None => Some(val),
+            Some(e) => {
+                self.tree.remove(&e);
+                self.tree.insert(val);
+                Some(e)
+            }
+        }
+    }
+
+    /// Checks if element exists in a tree.
+    /// Uses `O(n)` time.
+    pub fn exists(&self, val: T) -> bool {
+        self.tree.contains(&val)
+    }
+
+    /// Finds minimum element in a tree.
+    pub fn find_min(&self) -> Option<T> {
+        self.tree.first().copied()
+    }
+
+    /// Finds maximum element in a tree.
+    pub fn find_max(&self) -> Option<T> {
+        self.tree.last().copied()
+    }
+
+    /// Inorder traverse tree which yields elements in sorted order.
+    /// Uses `O(n)` time.
+    pub fn inorder(&self) -> Vec<T> {
+        self.tree.inorder()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BoundedBst, EvictionPolicy};
+
+    #[test]
+    fn top_k() {
+        let mut top = BoundedBst::new(3, EvictionPolicy::EvictSmallest);
+        assert_eq!(top.find_max(), None);
+        assert_eq!(top.insert(5), None);
+        assert_eq!(top.insert(1), None);
+        assert_eq!(top.insert(9), None);
+        assert_eq!(top.insert(7), Some(1));
+        assert_eq!(top.insert(2), Some(2));
+        assert_eq!(top.insert(8), Some(5));
+        assert_eq!(top.len(), 3);
+        assert_eq!(top.inorder(), vec![7, 8, 9]);
+    }
+    #[test]
+    fn bottom_k() {
+        let mut bottom = BoundedBst::new(4, EvictionPolicy::EvictLargest);
+        for v in (0..100).rev() {
+            bottom.insert(v);
+        }
+        assert_eq!(bottom.inorder(), vec![0, 1, 2, 3]);
+        assert!(bottom.exists(3));
+        assert!(!bottom.exists(4));
+
+        let mut none = BoundedBst::new(0, EvictionPolicy::EvictLargest);
+        assert_eq!(none.insert(1), Some(1));
+        assert!(none.is_empty());
+    }
+}
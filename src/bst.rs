@@ -31,15 +31,74 @@
 ///     println!("{}", *value);
 /// }
 /// ```
-use std::cmp::{max};
+///
+/// Building, inserting, lookups and iteration work for any ordered
+/// type, including owned ones such as `String`. Most other queries copy
+/// elements out and need `T: Copy`, large records can be indexed by
+/// reference without cloning them.
+///
+/// ```rust
+/// use ds_bst::BinarySearchTree;
+/// let mut root = BinarySearchTree::new(String::from("b"));
+/// root.insert(String::from("a"));
+/// assert!(root.contains(&String::from("a")));
+/// assert_eq!(root.first(), "a");
+/// ```
+///
+/// ```rust
+/// use ds_bst::BinarySearchTree;
+/// let names = vec![String::from("b"), String::from("a")];
+/// let root: BinarySearchTree<&String> = names.iter().collect();
+/// assert_eq!(root.find_min(), "a");
+/// ```
+///
+/// Nodes are owned through `Box`, so a tree is `Send` and `Sync`
+/// whenever `T` is. It can be moved into a thread, or shared for
+/// reading behind an `Arc`.
+///
+/// ```rust
+/// use std::sync::Arc;
+/// use std::thread;
+/// use ds_bst::BinarySearchTree;
+///
+/// let root = Arc::new(BinarySearchTree::from(vec![1, 2, 3]));
+/// let readers: Vec<_> = (1..=3).map(|v| {
+///     let root = Arc::clone(&root);
+///     thread::spawn(move || root.exists(v))
+/// }).collect();
+/// assert!(readers.into_iter().all(|r| r.join().unwrap()));
+/// ```
+use std::cmp::{max, Ordering};
+use std::fmt;
+use std::ops::{Bound, RangeBounds, Sub};
 
-pub struct BinarySearchTree<T> {
-    val: T,
-    left: Option<Box<BinarySearchTree<T>>>,
-    right: Option<Box<BinarySearchTree<T>>>
-}
+use crate::frozen::FrozenBst;
+use crate::map::BstMap;
+use crate::observe::{OpKind, Timer};
+use crate::static_bst::SortedIndex;
 
-impl<T: PartialOrd + Copy> BinarySearchTree<T> {
+mod node;
+mod ops;
+mod range;
+mod serial;
+mod set;
+mod storage;
+mod traversal;
+mod tree;
+
+pub use self::node::{BinarySearchTree, CursorMut, Direction, NodeRef, Shape};
+pub use self::ops::{
+    DuplicatePolicy, Edit, ImportOutcome, ImportReport, Incomparable, InsertOutcome, OpResult,
+    Operation, OutOfRange, RebalanceTask, RebuiltSubtree
+};
+pub use self::traversal::{BinarySearchTreeIter, Chunks, IntoSorted, Subtrees, Unordered, WithPath};
+pub use self::tree::Tree;
+use self::ops::merge_edits;
+pub(crate) use self::range::{before_end, before_start};
+pub(crate) use self::storage::Link;
+use self::storage::{remove_link, retain_link, take_k};
+
+impl<T: PartialOrd> BinarySearchTree<T> {
     /// Contructor creates BinarySearchTree root node
     pub fn new(v: T) -> BinarySearchTree<T> {
         BinarySearchTree {
@@ -48,21 +107,381 @@ impl<T: PartialOrd + Copy> BinarySearchTree<T> {
             right: None
         }
     }
-    /// Delegates tree building to `BinarySearchTree::build_recursive()`
-    /// This sorts vector input and pass splice to tree builder.
+
+    /// Delegates tree building to `BinarySearchTree::from_sorted_iter()`
+    /// This sorts vector input and pass it to tree builder.
     pub fn from(mut data: Vec<T>) -> BinarySearchTree<T> {
         data.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
-        let n = data.len() as isize;
-        let root = BinarySearchTree::build_recursive(&data[0..], 0, n-1);
+        let root = BinarySearchTree::from_sorted_iter(data);
 
         match root {
             None => { panic!("Empty node"); },
-            Some(r) => { *r }
+            Some(r) => { r }
+        }
+    }
+
+    /// Builds a balanced tree from iterator which yields elements in
+    /// sorted order, returns `None` for empty input. Nodes are built
+    /// with an explicit stack instead of recursion and values are
+    /// consumed one at a time, so input is never collected.
+    /// Uses `O(n)` time.
+    pub fn from_sorted_iter<I>(data: I) -> Option<BinarySearchTree<T>>
+        where
+            I: IntoIterator<Item = T>,
+            I::IntoIter: ExactSizeIterator
+    {
+        enum Stage { Left, Right }
+
+        struct Frame<T> {
+            mid: usize,
+            end: usize,
+            stage: Stage,
+            val: Option<T>,
+            left: Option<Box<BinarySearchTree<T>>>
+        }
+
+        let mut data = data.into_iter();
+        let mut stack: Vec<Frame<T>> = Vec::new();
+        let mut built: Option<Box<BinarySearchTree<T>>> = None;
+        let mut pending = Some((0, data.len()));
+
+        loop {
+            if let Some((start, end)) = pending.take() {
+                if start < end {
+                    let mid = start + (end - start - 1) / 2;
+                    stack.push(Frame { mid, end, stage: Stage::Left, val: None, left: None });
+                    pending = Some((start, mid));
+                    continue;
+                }
+                built = None;
+            }
+
+            let frame = match stack.last_mut() {
+                None => break,
+                Some(f) => f
+            };
+            match frame.stage {
+                Stage::Left => {
+                    frame.left = built.take();
+                    frame.val = data.next();
+                    frame.stage = Stage::Right;
+                    pending = Some((frame.mid + 1, frame.end));
+                }
+                Stage::Right => {
+                    let frame = stack.pop().unwrap();
+                    built = Some(Box::new(BinarySearchTree {
+                        val: frame.val.expect("iterator yielded fewer elements than its len()"),
+                        left: frame.left,
+                        right: built.take()
+                    }));
+                }
+            }
+        }
+
+        built.map(|r| *r)
+    }
+
+    /// Builds a balanced tree of `n` elements generated by index, element
+    /// at position `i` in sorted order being `f(i)`, so large synthetic
+    /// inputs never have to be collected first. Returns `None` for `n = 0`.
+    /// Uses `O(n)` time.
+    ///
+    /// # Panics
+    /// In debug builds panics if `f` is not monotone non-decreasing.
+    pub fn from_fn<F>(n: usize, f: F) -> Option<BinarySearchTree<T>>
+        where
+            F: FnMut(usize) -> T
+    {
+        let root = BinarySearchTree::from_sorted_iter((0..n).map(f))?;
+        debug_assert!(root.iter().is_sorted(), "Generator is not monotone");
+        Some(root)
+    }
+
+    /// Calculates tree maximum height
+    /// Worst case O(n)
+    pub fn height(&self) -> usize {
+        let hl: usize = match self.left {
+            None => { 0 },
+            Some(ref node) => {
+                node.height()
+            }
+        };
+
+        let hr: usize = match self.right{
+            None => { 0 },
+            Some(ref node) => {
+                node.height()
+            }
+        };
+
+        max(hl, hr) + 1
+    }
+
+    /// Inserts an element in a tree.
+    /// Uses `O(n)` time.
+    pub fn insert(&mut self, val: T) {
+        let timer = Timer::start();
+        let depth = self.insert_node(val);
+        timer.finish(OpKind::Insert, depth);
+    }
+
+    /// Inserts an element and returns number of nodes visited.
+    fn insert_node(&mut self, val: T) -> usize {
+        if self.val > val {
+            match self.left {
+                None => { self.left = Some(Box::new(BinarySearchTree::new(val))); 1 },
+                Some(ref mut n) => n.insert_node(val) + 1
+            }
+        } else {
+            match self.right {
+                None => { self.right = Some(Box::new(BinarySearchTree::new(val))); 1 },
+                Some(ref mut n) => n.insert_node(val) + 1
+            }
+        }
+    }
+
+    /// Inserts an element in a tree and reports what happened, `policy`
+    /// decides what to do when an equal element is already stored.
+    /// Uses `O(n)` time.
+    pub fn insert_full(&mut self, val: T, policy: DuplicatePolicy) -> InsertOutcome<T> {
+        match self.val.partial_cmp(&val) {
+            Some(Ordering::Equal) => match policy {
+                DuplicatePolicy::Keep => {
+                    self.insert(val);
+                    InsertOutcome::InsertedDuplicate
+                },
+                DuplicatePolicy::Replace => {
+                    InsertOutcome::ReplacedPrevious(std::mem::replace(&mut self.val, val))
+                },
+                DuplicatePolicy::Ignore => InsertOutcome::IgnoredDuplicate
+            },
+            Some(Ordering::Greater) => match self.left {
+                None => {
+                    self.left = Some(Box::new(BinarySearchTree::new(val)));
+                    InsertOutcome::Inserted
+                },
+                Some(ref mut n) => n.insert_full(val, policy)
+            },
+            _ => match self.right {
+                None => {
+                    self.right = Some(Box::new(BinarySearchTree::new(val)));
+                    InsertOutcome::Inserted
+                },
+                Some(ref mut n) => n.insert_full(val, policy)
+            }
+        }
+    }
+
+    /// Checks if element exists in a tree.
+    /// Uses `O(n)` time.
+    pub fn exists(&self, val: T) -> bool {
+        let timer = Timer::start();
+        let (found, depth) = self.find_node(&val);
+        timer.finish(OpKind::Exists, depth);
+        found
+    }
+
+    /// Searches an element and returns number of nodes visited.
+    fn find_node(&self, val: &T) -> (bool, usize) {
+        if self.val == *val {
+            return (true, 1);
+        }
+        let found = if self.val > *val {
+            match self.left {
+                None => (false, 0),
+                Some(ref n) => n.find_node(val)
+            }
+        } else if self.val < *val {
+            match self.right {
+                None => (false, 0),
+                Some(ref n) => n.find_node(val)
+            }
+        } else {
+            (false, 0)
+        };
+        (found.0, found.1 + 1)
+    }
+
+    /// Returns non-consumable iterator which yields references inorder,
+    /// so elements always come out in ascending order.
+    pub fn iter(&self) -> BinarySearchTreeIter<'_, T> {
+        BinarySearchTreeIter::new(self)
+    }
+
+    /// Consumes tree into an iterator which moves elements out in sorted
+    /// order from either end, so nothing is flattened up front and memory
+    /// is freed as it goes. Stopping early skips walking the rest of the tree.
+    /// Uses amortized `O(1)` time per element.
+    pub fn into_sorted_iter(self) -> IntoSorted<T> {
+        IntoSorted::new(Some(Box::new(self)))
+    }
+
+    /// Checks if element exists in a tree, looking it up by reference
+    /// so owned values such as `String` need not be built for a lookup.
+    /// Uses `O(n)` time.
+    pub fn contains(&self, val: &T) -> bool {
+        let timer = Timer::start();
+        let (found, depth) = self.get_node(val);
+        timer.finish(OpKind::Contains, depth);
+        found.is_some()
+    }
+
+    /// Returns stored element equal to `val`.
+    /// Uses `O(n)` time.
+    pub fn get(&self, val: &T) -> Option<&T> {
+        let timer = Timer::start();
+        let (found, depth) = self.get_node(val);
+        timer.finish(OpKind::Get, depth);
+        found
+    }
+
+    /// Returns stored element equal to `val` and number of nodes visited.
+    fn get_node(&self, val: &T) -> (Option<&T>, usize) {
+        let mut current = Some(self);
+        let mut depth = 0;
+        while let Some(node) = current {
+            depth += 1;
+            current = match node.val.partial_cmp(val) {
+                Some(Ordering::Equal) => return (Some(&node.val), depth),
+                Some(Ordering::Greater) => node.left.as_deref(),
+                Some(Ordering::Less) => node.right.as_deref(),
+                None => None
+            };
+        }
+        (None, depth)
+    }
+
+    /// Returns reference to smallest element.
+    /// Uses `O(n)` time.
+    pub fn first(&self) -> &T {
+        let mut node = self;
+        while let Some(ref left) = node.left {
+            node = left;
+        }
+        &node.val
+    }
+
+    /// Returns reference to largest element.
+    /// Uses `O(n)` time.
+    pub fn last(&self) -> &T {
+        let mut node = self;
+        while let Some(ref right) = node.right {
+            node = right;
+        }
+        &node.val
+    }
+
+    /// Rotates element equal to `val` up to root, one single rotation
+    /// per level, so a key known to be hot next is found at once. Sub
+    /// trees off the search path keep their shape. Returns `false` if
+    /// element does not exist.
+    /// Uses `O(n)` time.
+    pub fn rotate_root_to(&mut self, val: &T) -> bool {
+        let side = match self.val.partial_cmp(val) {
+            Some(Ordering::Equal) => return true,
+            Some(Ordering::Greater) => Direction::Left,
+            Some(Ordering::Less) => Direction::Right,
+            None => return false
+        };
+        let child = match side {
+            Direction::Left => &mut self.left,
+            Direction::Right => &mut self.right
+        };
+        if !child.as_mut().is_some_and(|c| c.rotate_root_to(val)) {
+            return false;
+        }
+        let mut child = child.take().unwrap();
+        match side {
+            Direction::Left => {
+                self.left = child.right.take();
+                std::mem::swap(self, &mut child);
+                self.right = Some(child);
+            },
+            Direction::Right => {
+                self.right = child.left.take();
+                std::mem::swap(self, &mut child);
+                self.left = Some(child);
+            }
+        }
+        true
+    }
+
+    /// Consumes set into a `BstMap` keyed by its elements, value of each
+    /// key being `f(key)`. Keys keep their nodes and shape, duplicate
+    /// elements collapse into one key.
+    /// Uses `O(n)` time.
+    pub fn into_map<V, F: FnMut(&T) -> V>(self, f: F) -> BstMap<T, V> {
+        BstMap::from_tree(self, f)
+    }
+
+    /// Consumes tree into an immutable `FrozenBst` laid out in one
+    /// cache friendly block.
+    /// Uses `O(n)` time.
+    pub fn freeze(self) -> FrozenBst<T> {
+        FrozenBst::from_sorted(self.into_sorted_vec())
+    }
+
+    /// Consumes tree into a vector of elements in sorted order, values
+    /// are moved out without copying.
+    /// Uses `O(n)` time.
+    pub fn into_sorted_vec(self) -> Vec<T> {
+        fn drain<T>(node: BinarySearchTree<T>, ret: &mut Vec<T>) {
+            let BinarySearchTree { val, left, right } = node;
+            if let Some(l) = left {
+                drain(*l, ret);
+            }
+            ret.push(val);
+            if let Some(r) = right {
+                drain(*r, ret);
+            }
+        }
+
+        let mut ret = Vec::new();
+        drain(self, &mut ret);
+        ret
+    }
+}
+
+impl<T: PartialOrd + Copy> BinarySearchTree<T> {
+    /// Builds a balanced tree from an already sorted slice, elements
+    /// are copied so one buffer can back many trees.
+    /// Uses `O(n)` time.
+    ///
+    /// # Panics
+    /// Panics if slice is empty.
+    pub fn from_sorted_slice(data: &[T]) -> BinarySearchTree<T> {
+        match BinarySearchTree::from_sorted_iter(data.iter().copied()) {
+            None => { panic!("Empty node"); },
+            Some(r) => { r }
+        }
+    }
+
+    /// Builds tree with exactly given `shape`, sorted `values` are
+    /// placed inorder. Returns `None` if shape is empty, its number of
+    /// nodes differs from number of values or values are not sorted.
+    /// Uses `O(n)` time.
+    pub fn from_shape(shape: &Shape, values: &[T]) -> Option<BinarySearchTree<T>> {
+        fn build<T: Copy>(shape: &Shape, values: &mut std::slice::Iter<'_, T>) -> Link<T> {
+            match shape {
+                Shape::Empty => None,
+                Shape::Node(l, r) => {
+                    let left = build(l, values);
+                    let val = *values.next()?;
+                    Some(Box::new(BinarySearchTree { val, left, right: build(r, values) }))
+                }
+            }
+        }
+
+        let sorted = values.windows(2).all(|w| w[0] <= w[1]);
+        if !sorted || shape.size() != values.len() {
+            return None;
         }
+        build(shape, &mut values.iter()).map(|r| *r)
     }
 
     /// Recursively builds tree maintaining BST properties.
     /// Uses `O(n)` time.
+    #[allow(clippy::needless_borrow)]
     pub fn build_recursive(data: &[T], start: isize, end: isize) -> Option<Box<BinarySearchTree<T>>> {
 
         if start > end {
@@ -102,6 +521,21 @@ impl<T: PartialOrd + Copy> BinarySearchTree<T> {
         ret
     }
 
+    /// Inorder traverse tree which yields each distinct element once
+    /// together with number of its copies.
+    /// Uses `O(n)` time.
+    pub fn inorder_with_counts(&self) -> Vec<(T, usize)> {
+        let mut ret: Vec<(T, usize)> = Vec::new();
+
+        for &val in self.iter() {
+            match ret.last_mut() {
+                Some((last, count)) if *last == val => *count += 1,
+                _ => ret.push((val, 1))
+            }
+        }
+        ret
+    }
+
     /// Traverse tree in preorder.
     /// Uses `O(n)` time.
     pub fn preorder(&self) -> Vec<T> {
@@ -125,162 +559,833 @@ impl<T: PartialOrd + Copy> BinarySearchTree<T> {
         ret
     }
 
-    /// Calculates tree maximum height
+    /// Returns values at exactly depth `d` from left to right,
+    /// root node is at depth `0`. It never descends below depth `d`.
     /// Worst case O(n)
-    pub fn height(&self) -> usize {
-        let hl: usize = match self.left {
-            None => { 0 },
-            Some(ref node) => {
-                node.height()
+    pub fn nodes_at_depth(&self, d: usize) -> Vec<T> {
+        let mut ret: Vec<T> = Vec::new();
+        self.collect_at_depth(d, &mut ret);
+        ret
+    }
+
+    fn collect_at_depth(&self, d: usize, ret: &mut Vec<T>) {
+        if d == 0 {
+            ret.push(self.val);
+            return;
+        }
+        if let Some(ref node) = self.left {
+            node.collect_at_depth(d - 1, ret);
+        }
+        if let Some(ref node) = self.right {
+            node.collect_at_depth(d - 1, ret);
+        }
+    }
+
+    /// Inserts an element so that no node ends up deeper than
+    /// `max_depth`, root being at depth `0`. If plain insert would go
+    /// deeper, the lowest sub tree on the way which fits the bound when
+    /// balanced is rebuilt together with the new element. Returns value
+    /// back if even whole tree can not fit, tree is untouched then.
+    /// Uses `O(n)` time.
+    pub fn insert_bounded(&mut self, val: T, max_depth: usize) -> Result<(), T> {
+        let mut path = Vec::new();
+        let mut node = &*self;
+        loop {
+            let (side, next) = if node.val > val {
+                (Direction::Left, &node.left)
+            } else {
+                (Direction::Right, &node.right)
+            };
+            path.push(side);
+            match next {
+                Some(n) => node = n,
+                None => break
             }
-        };
+        }
+        if path.len() <= max_depth {
+            self.insert(val);
+            return Ok(());
+        }
 
-        let hr: usize = match self.right{
-            None => { 0 },
-            Some(ref node) => {
-                node.height()
+        let levels = |m: usize| (usize::BITS - m.leading_zeros()) as usize;
+        let mut size = 1;
+        for depth in (0..path.len()).rev() {
+            let ancestor = self.subtree_at(&path[..depth]).unwrap();
+            let other = match path[depth] {
+                Direction::Left => &ancestor.right,
+                Direction::Right => &ancestor.left
+            };
+            size += 1 + other.as_ref().map_or(0, |n| n.iter().count());
+            if depth + levels(size) - 1 <= max_depth {
+                let subtree = self.subtree_at_mut(&path[..depth]).unwrap();
+                let mut values = subtree.inorder();
+                values.insert(values.partition_point(|x| *x <= val), val);
+                *subtree = BinarySearchTree::from_sorted_iter(values).unwrap();
+                return Ok(());
             }
-        };
+        }
+        Err(val)
+    }
 
-        max(hl, hr) + 1
+    /// Inserts an element only if it lies within `range`, e.g. to keep
+    /// a shard to the keys it owns. Tree is left untouched on error.
+    /// Uses `O(n)` time.
+    pub fn try_insert_within<R: RangeBounds<T>>(&mut self, range: R, val: T) -> Result<(), OutOfRange<T>> {
+        if !range.contains(&val) {
+            return Err(OutOfRange {
+                value: val,
+                start: range.start_bound().cloned(),
+                end: range.end_bound().cloned()
+            });
+        }
+        self.insert(val);
+        Ok(())
     }
 
-    /// Inserts an element in a tree.
+    /// Inserts an element unless it is not comparable with an element
+    /// on its way down, such as `NaN`, which `insert()` would place on an
+    /// arbitrary side. Tree is left untouched on error. Returns `Ok(false)`
+    /// when an equal element existed and another copy was inserted.
     /// Uses `O(n)` time.
-    pub fn insert(&mut self, val: T) {
-        if self.val > val {
-            match self.left {
-                None => self.left = Some(Box::new(BinarySearchTree::new(val))),
-                Some(ref mut n) => n.insert(val)
+    pub fn insert_checked(&mut self, val: T) -> Result<bool, Incomparable<T>> {
+        self.insert_checked_in(val, true)
+    }
+
+    /// Single descent behind `insert_checked()`, which stops at first
+    /// equal element without inserting unless `keep_duplicate` is set.
+    fn insert_checked_in(&mut self, val: T, keep_duplicate: bool) -> Result<bool, Incomparable<T>> {
+        let mut fresh = true;
+        let mut node = self;
+        loop {
+            let next = match node.val.partial_cmp(&val) {
+                None => return Err(Incomparable { value: val, existing: node.val }),
+                Some(Ordering::Greater) => &mut node.left,
+                Some(Ordering::Equal) if !keep_duplicate => return Ok(false),
+                Some(Ordering::Equal) => {
+                    fresh = false;
+                    &mut node.right
+                },
+                Some(Ordering::Less) => &mut node.right
+            };
+            match next {
+                None => {
+                    *next = Some(Box::new(BinarySearchTree::new(val)));
+                    return Ok(fresh);
+                },
+                Some(n) => node = n
             }
-        } else {
-            match self.right {
-                None => self.right = Some(Box::new(BinarySearchTree::new(val))),
-                Some(ref mut n) => n.insert(val)
+        }
+    }
+
+    /// Inserts every item which is neither a duplicate nor incomparable
+    /// with stored elements, bad items are skipped instead of aborting
+    /// the whole load. Returns outcome of each item, every item descends
+    /// once.
+    /// Uses `O(n)` time per item.
+    pub fn try_extend<I: IntoIterator<Item = T>>(&mut self, iter: I) -> ImportReport {
+        let mut report = ImportReport::default();
+        for val in iter {
+            let outcome = match self.insert_checked_in(val, false) {
+                Ok(true) => ImportOutcome::Inserted,
+                Ok(false) => ImportOutcome::Duplicate,
+                Err(_) => ImportOutcome::Incomparable
+            };
+            report.outcomes.push(outcome);
+        }
+        report
+    }
+
+    /// Returns number of leading elements in sorted order for which `pred`
+    /// holds, like `slice::partition_point()`. `pred` has to be true for
+    /// a prefix of elements and false for the rest, sub trees right of
+    /// a failing node are skipped. Nodes keep no sub tree sizes, so every
+    /// passed left sub tree is counted by walking it, see
+    /// `WeightBalancedTree::partition_point()` for an `O(log n)` one.
+    /// Uses `O(h + k)` time for `k` elements before the partition point.
+    pub fn partition_point<P>(&self, mut pred: P) -> usize
+        where
+            P: FnMut(&T) -> bool
+    {
+        let mut count = 0;
+        let mut current = Some(self);
+        while let Some(node) = current {
+            if pred(&node.val) {
+                count += 1 + node.left.as_ref().map_or(0, |n| n.iter().count());
+                current = node.right.as_deref();
+            } else {
+                current = node.left.as_deref();
             }
         }
+        count
     }
 
+    /// Finds element using a comparator, like `slice::binary_search_by()`.
+    /// `f` tells how probed element is ordered relative to the target,
+    /// so elements can be searched by a projection such as a key field.
+    /// Uses `O(n)` time.
+    pub fn find_by<F>(&self, mut f: F) -> Option<T>
+        where
+            F: FnMut(&T) -> Ordering
+    {
+        let mut current = Some(self);
+        while let Some(node) = current {
+            current = match f(&node.val) {
+                Ordering::Equal => return Some(node.val),
+                Ordering::Greater => node.left.as_deref(),
+                Ordering::Less => node.right.as_deref()
+            };
+        }
+        None
+    }
 
-    /// Checks if element exists in a tree.
+    /// Counts how many copies of `val` are stored in a tree,
+    /// `0` when it does not exist.
     /// Uses `O(n)` time.
-    pub fn exists(&self, val: T) -> bool {
-        if self.val == val {
-            return true;
+    pub fn count(&self, val: &T) -> usize {
+        let timer = Timer::start();
+        let mut depth = 0;
+        let count = self.count_copies(val, &mut depth);
+        timer.finish(OpKind::Count, depth);
+        count
+    }
+
+    fn count_copies(&self, val: &T, visited: &mut usize) -> usize {
+        *visited += 1;
+        let mut count_in = |node: &Link<T>| match node {
+            None => 0,
+            Some(ref n) => n.count_copies(val, visited)
+        };
+
+        match self.val.partial_cmp(val) {
+            Some(Ordering::Equal) => 1 + count_in(&self.left) + count_in(&self.right),
+            Some(Ordering::Greater) => count_in(&self.left),
+            Some(Ordering::Less) => count_in(&self.right),
+            None => 0
         }
-        if self.val > val {
-            return match self.left {
-                None => false,
-                Some(ref n) => n.exists(val)
+    }
+
+    /// Returns element `n` positions after `val` in sorted order, or
+    /// before it when `n` is negative. Position of `val` is its rank,
+    /// so `nth_from(val, 0)` is the first element not less than `val`.
+    /// Returns `None` when offset runs past either end. Iteration starts
+    /// at `val` instead of counting its rank, see
+    /// `WeightBalancedTree::nth_from()` for an `O(log n)` one.
+    /// Uses `O(h + |n|)` time.
+    pub fn nth_from(&self, val: T, n: isize) -> Option<T> {
+        let timer = Timer::start();
+        let (found, depth) = match usize::try_from(n) {
+            Ok(n) => {
+                let (iter, depth) = BinarySearchTreeIter::range(self, &(val..));
+                (iter.copied().nth(n), depth)
+            },
+            Err(_) => {
+                let (iter, depth) = BinarySearchTreeIter::range(self, &(..val));
+                (iter.copied().nth_back(n.unsigned_abs() - 1), depth)
+            }
+        };
+        timer.finish(OpKind::NthFrom, depth);
+        found
+    }
+
+    /// Finds smallest sub tree holding both `a` and `b` and returns its
+    /// minimum and maximum, `None` if either of them does not exist.
+    /// Root of that sub tree is the lowest common ancestor, found without
+    /// building a path to it.
+    /// Uses `O(n)` time.
+    pub fn latest_common_range(&self, a: T, b: T) -> Option<(T, T)> {
+        let (lo, hi) = if a <= b { (a, b) } else { (b, a) };
+        let mut node = self;
+        loop {
+            let next = if node.val > hi {
+                &node.left
+            } else if node.val < lo {
+                &node.right
+            } else {
+                break;
             };
+            node = next.as_deref()?;
         }
-        if self.val < val {
-            return match self.right {
-                None => false,
-                Some(ref n) => n.exists(val)
-            };
+
+        if node.find_node(&lo).0 && node.find_node(&hi).0 {
+            Some((*node.first(), *node.last()))
+        } else {
+            None
         }
-        false
     }
 
     /// Finds minimum element in a tree.
     /// Uses `O(n)` time.
     pub fn find_min(&self) -> T {
-        match self.left {
-            None => self.val,
-            Some(ref n) => n.find_min()
+        let timer = Timer::start();
+        let mut node = self;
+        let mut depth = 1;
+        while let Some(ref n) = node.left {
+            node = n;
+            depth += 1;
         }
+        timer.finish(OpKind::FindMin, depth);
+        node.val
     }
 
     /// Finds maximum element in a tree.
     /// Uses `O(n)` time.
     pub fn find_max(&self) -> T {
-        match self.right {
-            None => self.val,
-            Some(ref n) => n.find_max()
+        let timer = Timer::start();
+        let mut node = self;
+        let mut depth = 1;
+        while let Some(ref n) = node.right {
+            node = n;
+            depth += 1;
         }
+        timer.finish(OpKind::FindMax, depth);
+        node.val
     }
-}
 
-/// BinarySearchTreeIterator
-pub struct BinarySearchTreeIter<'a, T> {
-    nodes: Vec<&'a T>
-}
-
-impl<'a, T> BinarySearchTreeIter<'a, T>
-    where
-        T: PartialOrd + Copy
-{
-    /// Construct nodes based on input tree. By default
-    /// it uses in-order traversal for iterator.
-    fn new(root: &'a BinarySearchTree<T>) -> Self {
-        let mut iter = BinarySearchTreeIter {
-            nodes: Vec::new()
-        };
+    /// Finds largest element less than or equal to `val`.
+    /// Uses `O(n)` time.
+    pub fn floor(&self, val: T) -> Option<T> {
+        let timer = Timer::start();
+        let (found, depth) = self.floor_node(val);
+        timer.finish(OpKind::Floor, depth);
+        found
+    }
 
-        iter.inorder(root);
+    /// Finds floor of `val` and returns number of nodes visited.
+    fn floor_node(&self, val: T) -> (Option<T>, usize) {
+        let mut found = None;
+        let mut current = Some(self);
+        let mut depth = 0;
+        while let Some(node) = current {
+            depth += 1;
+            current = match node.val.partial_cmp(&val) {
+                Some(Ordering::Equal) => return (Some(node.val), depth),
+                Some(Ordering::Greater) => node.left.as_deref(),
+                Some(Ordering::Less) => {
+                    found = Some(node.val);
+                    node.right.as_deref()
+                },
+                None => None
+            };
+        }
+        (found, depth)
+    }
 
-        iter
+    /// Finds smallest element greater than or equal to `val`.
+    /// Uses `O(n)` time.
+    pub fn ceiling(&self, val: T) -> Option<T> {
+        let timer = Timer::start();
+        let (found, depth) = self.ceiling_node(val);
+        timer.finish(OpKind::Ceiling, depth);
+        found
     }
 
-    /// In-order tree traversal
-    fn inorder(&mut self, tree: &'a BinarySearchTree<T>) {
-        match tree.right {
-            None => {},
-            Some(ref node) => {
-                self.inorder(node);
-            }
-        };
-        self.nodes.push(&tree.val);
-        match tree.left {
-            None => {},
-            Some(ref node) => {
-                self.inorder(node);
-            }
+    /// Finds ceiling of `val` and returns number of nodes visited.
+    fn ceiling_node(&self, val: T) -> (Option<T>, usize) {
+        let mut found = None;
+        let mut current = Some(self);
+        let mut depth = 0;
+        while let Some(node) = current {
+            depth += 1;
+            current = match node.val.partial_cmp(&val) {
+                Some(Ordering::Equal) => return (Some(node.val), depth),
+                Some(Ordering::Less) => node.right.as_deref(),
+                Some(Ordering::Greater) => {
+                    found = Some(node.val);
+                    node.left.as_deref()
+                },
+                None => None
+            };
         }
+        (found, depth)
     }
-}
-
-/// Implement iterator for BinarySearchTreeIter
-/// nodes are stored in flat array. It just pop outs node
-impl<'a, T> Iterator for BinarySearchTreeIter<'a, T>
-    where
-        T: PartialOrd + Copy,
-{
-    type Item = &'a T;
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.nodes.pop()
+    /// Returns iterator over every node in a tree in preorder.
+    /// Each yielded `NodeRef` is the root of a sub tree.
+    pub fn subtrees(&self) -> Subtrees<'_, T> {
+        Subtrees {
+            stack: vec![self]
+        }
     }
-}
 
-/// implement consumable IntoIterator for BinarySearchTree
-impl<T> IntoIterator for BinarySearchTree<T>
-    where
-        T: PartialOrd + Copy,
-{
-    type Item = T;
-    type IntoIter = std::vec::IntoIter<Self::Item>;
+    /// Returns iterator which visits every element once in no particular
+    /// order. It walks nodes lazily in preorder without any up front
+    /// work, use it when order does not matter.
+    pub fn iter_unordered(&self) -> Unordered<'_, T> {
+        Unordered { nodes: self.subtrees() }
+    }
 
-    fn into_iter(self) -> Self::IntoIter {
-        self.inorder().into_iter()
+    /// Returns iterator which yields references inorder together with
+    /// steps from root to each node, handy to pin down exact node
+    /// positions in bug reports.
+    pub fn iter_with_path(&self) -> WithPath<'_, T> {
+        let mut iter = WithPath { stack: Vec::new() };
+        iter.push_left(self, Vec::new());
+        iter
     }
-}
 
-/// Implement non-consumable IntoIterator for BinarySearchTree
-impl<'a, T> IntoIterator for &'a BinarySearchTree<T>
-    where
-        T: PartialOrd + Copy {
-    type Item = &'a T;
-    type IntoIter = BinarySearchTreeIter<'a, T>;
+    /// Returns iterator which yields consecutive sorted chunks of `n`
+    /// elements, last chunk may be shorter, for writing contents out in
+    /// batches.
+    ///
+    /// # Panics
+    /// Panics if `n` is `0`.
+    pub fn iter_chunks(&self, n: usize) -> Chunks<'_, T> {
+        assert!(n > 0, "Chunk size must be non-zero");
+        Chunks { iter: self.iter(), size: n }
+    }
 
-    fn into_iter(self) -> Self::IntoIter {
-        BinarySearchTreeIter::new(self)
+    /// Returns iterator which yields owned values inorder. Values are
+    /// cloned one at a time as the iterator advances, so it never
+    /// copies whole tree up front.
+    pub fn iter_owned(&self) -> std::iter::Cloned<BinarySearchTreeIter<'_, T>> {
+        self.iter().cloned()
     }
-}
 
+    /// Copies elements into an owned sorted snapshot supporting `rank()`
+    /// and `select()`, which stays valid while tree keeps changing.
+    /// Uses `O(n)` time.
+    pub fn export_index(&self) -> SortedIndex<T> {
+        SortedIndex::from(self.iter().copied().collect::<Box<[T]>>())
+    }
+
+    /// Rebuilds a balanced tree when height exceeds `threshold` times
+    /// the height of a perfectly balanced tree of same size. Returns
+    /// `true` if tree was rebuilt.
+    /// Uses `O(n)` time.
+    pub fn rebuild_if_unbalanced(&mut self, threshold: f64) -> bool {
+        let values = self.inorder();
+        let ideal = (values.len() as f64 + 1.0).log2().ceil();
+
+        if (self.height() as f64) / ideal <= threshold {
+            return false;
+        }
+        if let Some(root) = BinarySearchTree::from_sorted_iter(values) {
+            *self = root;
+        }
+        true
+    }
+
+    /// Walks elements in sorted order and replaces each run of neighbours
+    /// for which `f(prev, next)` gives `Some(merged)` by merged value, e.g.
+    /// joining touching intervals kept as keys. Tree is rebuilt balanced
+    /// from coalesced elements, which are sorted again in case merging
+    /// moved one past its neighbours.
+    /// Uses `O(n log n)` time.
+    pub fn merge_adjacent<F>(&mut self, mut f: F)
+        where
+            F: FnMut(&T, &T) -> Option<T>
+    {
+        let mut current = *self.first();
+        let mut merged = Vec::new();
+        for v in self.iter().skip(1).copied() {
+            match f(&current, &v) {
+                Some(m) => current = m,
+                None => merged.push(std::mem::replace(&mut current, v))
+            }
+        }
+        merged.push(current);
+        *self = BinarySearchTree::from(merged);
+    }
+
+    /// Splits in-order sequence into at most `n` contiguous chunks of
+    /// nearly equal length, so each worker thread can consume a disjoint
+    /// sorted part of a tree. Chunks are range iterators between split
+    /// keys, so nothing is copied and copies of one value always end up
+    /// in one chunk.
+    /// Uses `O(n)` time to find split keys.
+    pub fn split_iter(&self, n: usize) -> Vec<BinarySearchTreeIter<'_, T>> {
+        let len = self.iter().count();
+        let n = max(1, n).min(len);
+        let mut starts = (1..n).map(|i| (i * len).div_ceil(n)).peekable();
+        let mut keys: Vec<T> = Vec::with_capacity(n - 1);
+
+        for (pos, v) in self.iter().enumerate() {
+            if starts.peek().is_none() {
+                break;
+            }
+            if starts.next_if_eq(&pos).is_some() && keys.last().unwrap_or(self.first()) != v {
+                keys.push(*v);
+            }
+        }
+
+        let mut lower = Bound::Unbounded;
+        let mut chunks = Vec::with_capacity(keys.len() + 1);
+        for k in keys {
+            chunks.push(BinarySearchTreeIter::range(self, &(lower, Bound::Excluded(k))).0);
+            lower = Bound::Included(k);
+        }
+        chunks.push(BinarySearchTreeIter::range(self, &(lower, Bound::Unbounded)).0);
+        chunks
+    }
+
+    /// Keeps only elements within `range` for which `f` returns `true`,
+    /// elements outside of `range` are kept untouched and sub trees
+    /// entirely outside of it are never visited. Returns `false` if `f`
+    /// rejects every element, last rejected one is kept then since a
+    /// tree cannot be empty, see `Tree::retain_range()` which can empty.
+    /// Uses `O(n)` time.
+    pub fn retain_range<R, F>(&mut self, range: R, mut f: F) -> bool
+        where
+            R: RangeBounds<T>,
+            F: FnMut(&T) -> bool
+    {
+        let mut last_rejected = self.val;
+        let mut keep = |v: &T| {
+            let kept = f(v);
+            if !kept {
+                last_rejected = *v;
+            }
+            kept
+        };
+        let placeholder = BinarySearchTree::new(self.val);
+        let root = Some(Box::new(std::mem::replace(self, placeholder)));
+
+        match retain_link(root, &range, &mut keep) {
+            None => {
+                *self = BinarySearchTree::new(last_rejected);
+                false
+            },
+            Some(r) => {
+                *self = *r;
+                true
+            }
+        }
+    }
+
+    /// Removes `k` smallest elements and returns them smallest first.
+    /// Only the left spine and removed nodes are visited. At most all
+    /// but one element are removed, largest one stays behind since a
+    /// tree cannot be empty.
+    /// Uses `O(k + h)` time for tree of height `h`.
+    pub fn remove_min_k(&mut self, k: usize) -> Vec<T> {
+        self.remove_k(k, Direction::Left)
+    }
+
+    /// Removes `k` largest elements and returns them largest first.
+    /// At most all but one element are removed, smallest one stays behind.
+    /// Uses `O(k + h)` time for tree of height `h`.
+    pub fn remove_max_k(&mut self, k: usize) -> Vec<T> {
+        self.remove_k(k, Direction::Right)
+    }
+
+    fn remove_k(&mut self, mut k: usize, side: Direction) -> Vec<T> {
+        let mut taken = Vec::with_capacity(k);
+        if k == 0 {
+            return taken;
+        }
+        let placeholder = BinarySearchTree::new(self.val);
+        let root = Some(Box::new(std::mem::replace(self, placeholder)));
+
+        match take_k(root, &mut k, side, &mut taken) {
+            Some(rest) => *self = *rest,
+            None => {
+                // last taken element is put back as root
+                if let Some(last) = taken.pop() {
+                    *self = BinarySearchTree::new(last);
+                }
+            }
+        }
+        taken
+    }
+
+    /// Exchanges contents of two trees without moving any nodes.
+    /// Uses `O(1)` time.
+    pub fn swap_contents(&mut self, other: &mut BinarySearchTree<T>) {
+        std::mem::swap(self, other);
+    }
+
+    /// Finds sub trees whose height exceeds `threshold` times the height
+    /// of a perfectly balanced tree of same size, the same test as
+    /// `rebuild_if_unbalanced()`. Each one comes as a task owning a sorted
+    /// copy of its elements, so rebuilding can run later or on another
+    /// thread and be put back with `apply_rebuilt()`. Unbalanced sub trees
+    /// nested in another one are covered by the outermost task.
+    /// Uses `O(n)` time.
+    pub fn rebalance_tasks(&self, threshold: f64) -> Vec<RebalanceTask<T>> {
+        fn walk<T>(node: &BinarySearchTree<T>, threshold: f64, path: &mut Vec<Direction>,
+                   found: &mut Vec<Vec<Direction>>) -> (usize, usize) {
+            let before = found.len();
+            let mut height = 0;
+            let mut size = 1;
+            for (side, child) in [(Direction::Left, &node.left), (Direction::Right, &node.right)] {
+                if let Some(c) = child {
+                    path.push(side);
+                    let (h, s) = walk(c, threshold, path, found);
+                    path.pop();
+                    height = max(height, h);
+                    size += s;
+                }
+            }
+            height += 1;
+
+            let ideal = (size as f64 + 1.0).log2().ceil();
+            if (height as f64) / ideal > threshold {
+                found.truncate(before);
+                found.push(path.clone());
+            }
+            (height, size)
+        }
+
+        let mut found = Vec::new();
+        walk(self, threshold, &mut Vec::new(), &mut found);
+        found.into_iter()
+            .map(|path| {
+                let values = self.subtree_at(&path).map_or_else(Vec::new, |n| n.inorder());
+                RebalanceTask { path, values }
+            })
+            .collect()
+    }
+
+    /// Puts sub tree built by `RebalanceTask::run()` back in place.
+    /// Returns `false` and leaves tree untouched when sub tree at task
+    /// path no longer holds the same elements, e.g. because tree was
+    /// modified after the task was taken.
+    /// Uses `O(k)` time for a sub tree of `k` elements.
+    pub fn apply_rebuilt(&mut self, rebuilt: RebuiltSubtree<T>) -> bool {
+        match self.subtree_at_mut(&rebuilt.path) {
+            Some(node) if node.iter().eq(rebuilt.root.iter()) => {
+                *node = rebuilt.root;
+                true
+            },
+            _ => false
+        }
+    }
+
+    fn subtree_at(&self, path: &[Direction]) -> Option<&BinarySearchTree<T>> {
+        let mut node = self;
+        for side in path {
+            node = match side {
+                Direction::Left => node.left.as_deref()?,
+                Direction::Right => node.right.as_deref()?
+            };
+        }
+        Some(node)
+    }
+
+    fn subtree_at_mut(&mut self, path: &[Direction]) -> Option<&mut BinarySearchTree<T>> {
+        let mut node = self;
+        for side in path {
+            node = match side {
+                Direction::Left => node.left.as_deref_mut()?,
+                Direction::Right => node.right.as_deref_mut()?
+            };
+        }
+        Some(node)
+    }
+
+    /// Lists inserts and removes which turn contents of this tree into
+    /// contents of `other`, every copy of a duplicate counts on its own.
+    /// Both trees are walked once in sorted order side by side, so
+    /// the script is minimal and comes out in ascending order.
+    /// Uses `O(n + m)` time.
+    pub fn edit_script(&self, other: &BinarySearchTree<T>) -> Vec<Edit<T>> {
+        merge_edits(self.iter(), other.iter())
+    }
+
+    /// Checks that every element of sorted `values` is stored, walking
+    /// both in order and stopping at the first miss, which is returned.
+    /// Each copy of a duplicate needs a copy of its own in tree.
+    /// Uses `O(n + m)` time.
+    pub fn contains_sorted(&self, values: &[T]) -> Result<(), T> {
+        let mut current = self.iter().peekable();
+        for &v in values {
+            while current.next_if(|x| **x < v).is_some() {}
+            if current.next_if(|x| **x == v).is_none() {
+                return Err(v);
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies a batch of edits sorted by value, e.g. output of
+    /// `edit_script()`, by merging it with elements in sorted order and
+    /// rebuilding a balanced tree once. `Insert` adds a copy and `Remove`
+    /// drops one copy if there is any. Much faster than point updates
+    /// when batch is large. Returns `false` if edits would remove every
+    /// element, tree is left untouched then.
+    /// Uses `O(n + m)` time.
+    ///
+    /// # Panics
+    /// Panics if edits are not sorted, tree is left untouched then.
+    pub fn apply_sorted_updates<I: IntoIterator<Item = Edit<T>>>(&mut self, updates: I) -> bool {
+        let mut merged = Vec::new();
+        let mut current = self.iter().copied().peekable();
+        let mut last = None;
+
+        for edit in updates {
+            let (Edit::Insert(v) | Edit::Remove(v)) = edit;
+            assert!(last.is_none_or(|l| l <= v), "Updates are not sorted");
+            last = Some(v);
+            while let Some(x) = current.next_if(|x| *x < v) {
+                merged.push(x);
+            }
+            match edit {
+                Edit::Insert(v) => merged.push(v),
+                Edit::Remove(v) => {
+                    current.next_if(|x| *x == v);
+                }
+            }
+        }
+        merged.extend(current);
+        match BinarySearchTree::from_sorted_iter(merged) {
+            Some(root) => {
+                *self = root;
+                true
+            },
+            None => false
+        }
+    }
+
+    /// Finds first node in preorder which is out of order with one of
+    /// its ancestors, returns steps from root to it.
+    fn find_misplaced(&self, lower: Option<T>, upper: Option<T>, path: &mut Vec<Direction>) -> bool {
+        let children = [
+            (Direction::Left, &self.left, lower, Some(self.val)),
+            (Direction::Right, &self.right, Some(self.val), upper)
+        ];
+        for (side, child, lo, hi) in children {
+            if let Some(ref c) = child {
+                path.push(side);
+                let below = lo.is_some_and(|l| !matches!(l.partial_cmp(&c.val), Some(Ordering::Less | Ordering::Equal)));
+                let above = hi.is_some_and(|h| !matches!(c.val.partial_cmp(&h), Some(Ordering::Less | Ordering::Equal)));
+                if below || above || c.find_misplaced(lo, hi, path) {
+                    return true;
+                }
+                path.pop();
+            }
+        }
+        false
+    }
+
+    /// Runs a single `Operation`, giving fuzzers and property tests one
+    /// typed entry point to drive a tree and compare it with a model.
+    /// Removing the only element is refused with `OpResult::Refused`.
+    /// Uses `O(n)` time.
+    pub fn apply(&mut self, op: Operation<T>) -> OpResult<T> {
+        match op {
+            Operation::Insert(v) => {
+                self.insert(v);
+                OpResult::Inserted
+            },
+            Operation::Remove(v) => {
+                if self.left.is_none() && self.right.is_none() && self.val == v {
+                    return OpResult::Refused;
+                }
+                let placeholder = BinarySearchTree::new(self.val);
+                let mut root = Some(Box::new(std::mem::replace(self, placeholder)));
+                let removed = remove_link(&mut root, &v, &mut 0);
+                *self = *root.unwrap();
+                OpResult::Removed(removed)
+            },
+            Operation::Contains(v) => OpResult::Contains(self.exists(v)),
+            Operation::Count(v) => OpResult::Count(self.count(&v)),
+            Operation::QueryRange(start, end) => {
+                let mut ret = Vec::new();
+                self.collect_range(&(start, end), &mut ret);
+                OpResult::Range(ret)
+            },
+            Operation::Min => OpResult::Min(self.find_min()),
+            Operation::Max => OpResult::Max(self.find_max())
+        }
+    }
+}
+
+impl<T: PartialOrd + Copy + fmt::Debug> BinarySearchTree<T> {
+    /// Asserts that inorder contents equal `expected` sorted slice and
+    /// every node is ordered against all of its ancestors, a building
+    /// block for regression tests.
+    /// Uses `O(n + m)` time.
+    ///
+    /// # Panics
+    /// Panics with misplaced node or with missing and unexpected
+    /// elements if tree does not match.
+    pub fn validate_against(&self, expected: &[T]) {
+        let mut path = Vec::new();
+        if self.find_misplaced(None, None, &mut path) {
+            let node = self.subtree_at(&path).map(|n| n.val);
+            panic!("Tree order is broken at {:?}, node {:?} is out of order", path, node.unwrap());
+        }
+
+        let edits = merge_edits(self.iter(), expected.iter());
+        if !edits.is_empty() {
+            let mut missing = Vec::new();
+            let mut unexpected = Vec::new();
+            for edit in edits {
+                match edit {
+                    Edit::Insert(v) => missing.push(v),
+                    Edit::Remove(v) => unexpected.push(v)
+                }
+            }
+            panic!("Tree does not match expected elements\n  missing: {:?}\n  unexpected: {:?}", missing, unexpected);
+        }
+    }
+}
+
+impl<T: PartialOrd + Copy + Sub<Output = T>> BinarySearchTree<T> {
+    /// Finds element closest to `val` which is at most `eps` away from it,
+    /// useful for float trees where exact equality rarely matches.
+    /// Uses `O(n)` time.
+    pub fn find_approx(&self, val: T, eps: T) -> Option<T> {
+        let below = self.floor_node(val).0.filter(|&f| val - f <= eps);
+        let above = self.ceiling_node(val).0.filter(|&c| c - val <= eps);
+
+        match (below, above) {
+            (Some(f), Some(c)) => if val - f <= c - val { Some(f) } else { Some(c) },
+            (f, c) => f.or(c)
+        }
+    }
+}
+
+#[cfg(feature = "prefetch")]
+impl<T: PartialOrd + Copy> BinarySearchTree<T> {
+    /// Checks if element exists in a tree like `exists()`, but issues
+    /// software prefetches for the grandchildren of every visited node
+    /// so memory latency of the next levels overlaps with comparisons.
+    /// Only pays off on trees which are much larger than CPU cache,
+    /// `cargo bench --features prefetch` times it against `exists()`.
+    ///
+    /// Available with `prefetch` feature.
+    /// Uses `O(n)` time.
+    pub fn fast_lookup(&self, val: T) -> bool {
+        let mut current = Some(self);
+        while let Some(node) = current {
+            prefetch_children(node.left.as_deref());
+            prefetch_children(node.right.as_deref());
+            current = match node.val.partial_cmp(&val) {
+                Some(Ordering::Equal) => return true,
+                Some(Ordering::Greater) => node.left.as_deref(),
+                Some(Ordering::Less) => node.right.as_deref(),
+                None => return false
+            };
+        }
+        false
+    }
+}
+
+#[cfg(feature = "prefetch")]
+fn prefetch_children<T>(node: Option<&BinarySearchTree<T>>) {
+    if let Some(node) = node {
+        for child in [&node.left, &node.right].into_iter().flatten() {
+            prefetch(&**child);
+        }
+    }
+}
+
+#[cfg(feature = "prefetch")]
+#[inline(always)]
+fn prefetch<T>(ptr: *const T) {
+    #[cfg(target_arch = "x86_64")]
+    // SAFETY: prefetch is only a hint, it never faults or changes memory.
+    unsafe {
+        use std::arch::x86_64::{_mm_prefetch, _MM_HINT_T0};
+        _mm_prefetch::<_MM_HINT_T0>(ptr as *const i8);
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    let _ = ptr;
+}
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison, clippy::explicit_counter_loop, clippy::assign_op_pattern)]
 mod tests {
     use super::BinarySearchTree;
     #[test]
@@ -326,6 +1431,664 @@ mod tests {
         assert_eq!(root.find_max(), 1.9);
     }
     #[test]
+    fn borrowed() {
+        #[derive(PartialEq, PartialOrd, Debug)]
+        struct Record { id: u32, payload: Vec<u8> }
+
+        let arena: Vec<Record> = (0..100).rev().map(|id| Record { id, payload: vec![0; 64] }).collect();
+        let mut root: BinarySearchTree<&Record> = arena.iter().collect();
+        root.insert(&arena[0]);
+
+        assert_eq!(root.find_min().id, 0);
+        assert_eq!(root.count(&&arena[0]), 2);
+        assert_eq!(root.find_by(|r| r.id.cmp(&42)).map(|r| r.payload.len()), Some(64));
+        assert!(std::ptr::eq(root.find_max(), &arena[0]));
+    }
+    #[test]
+    fn inorder_counts() {
+        let mut root = BinarySearchTree::from(vec![3, 1, 3, 2, 3]);
+        root.insert(1);
+        assert_eq!(root.inorder_with_counts(), vec![(1, 2), (2, 1), (3, 3)]);
+    }
+    #[test]
+    fn depth() {
+        let root = BinarySearchTree::from(vec![1,2,3,4,5,6,7,8]);
+        assert_eq!(root.nodes_at_depth(0), vec![4]);
+        assert_eq!(root.nodes_at_depth(1), vec![2, 6]);
+        assert_eq!(root.nodes_at_depth(2), vec![1, 3, 5, 7]);
+        assert_eq!(root.nodes_at_depth(3), vec![8]);
+        assert!(root.nodes_at_depth(4).is_empty());
+    }
+    #[test]
+    fn partition_point() {
+        let root = BinarySearchTree::from(vec![10, 20, 20, 30, 40, 50, 60]);
+        for (x, expected) in [(0, 0), (10, 0), (20, 1), (25, 3), (60, 6), (99, 7)] {
+            assert_eq!(root.partition_point(|v| *v < x), expected);
+        }
+        assert_eq!(root.partition_point(|v| *v <= 20), 3);
+    }
+    #[test]
+    fn iter_fork() {
+        fn fused<I: std::iter::FusedIterator>(iter: I) -> I { iter }
+
+        let root = BinarySearchTree::from(vec![1, 2, 3, 4, 5]);
+        let mut iter = fused(root.iter());
+        iter.next();
+        let mut ahead = iter.clone();
+        assert_eq!(ahead.nth(2), Some(&4));
+        assert_eq!(iter.next(), Some(&2));
+        assert_eq!(ahead.collect::<Vec<_>>(), vec![&5]);
+        assert_eq!(iter.by_ref().count(), 3);
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next(), None);
+    }
+    #[test]
+    fn iter_both_ends() {
+        let mut root = BinarySearchTree::new(4);
+        for v in [2, 6, 1, 3, 5, 7, 6, 2] {
+            root.insert(v);
+        }
+        let sorted = root.inorder();
+        // every split point between front and back meets exactly once
+        for split in 0..=sorted.len() {
+            let mut iter = root.iter();
+            let mut front: Vec<i32> = iter.by_ref().take(split).copied().collect();
+            assert_eq!(iter.peek_next(), sorted.get(split));
+            let mut back: Vec<i32> = iter.by_ref().rev().copied().collect();
+            assert_eq!(iter.peek_prev(), None);
+            assert_eq!(iter.next(), None);
+            back.reverse();
+            front.extend(back);
+            assert_eq!(front, sorted);
+        }
+        let mut iter = root.iter();
+        assert_eq!((iter.next(), iter.next_back()), (Some(&1), Some(&7)));
+        assert_eq!((iter.next(), iter.next_back()), (Some(&2), Some(&6)));
+        assert_eq!(iter.count(), 5);
+    }
+    #[test]
+    fn into_sorted_iter() {
+        let root = BinarySearchTree::from(vec![5, 3, 8, 1, 4, 7, 9, 3]);
+        assert_eq!(root.into_sorted_iter().collect::<Vec<_>>(), vec![1, 3, 3, 4, 5, 7, 8, 9]);
+
+        let mut root = BinarySearchTree::new(1);
+        root.extend(2..100);
+        let mut iter = root.into_sorted_iter();
+        assert_eq!(iter.by_ref().take(3).collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(iter.next_back(), Some(99));
+        assert_eq!(iter.count(), 95);
+
+        // a degenerate chain is consumed from both ends in linear time
+        let mut chain = BinarySearchTree::new(0);
+        chain.extend(1..2000);
+        let mut iter = chain.into_sorted_iter();
+        assert_eq!((iter.next(), iter.next_back()), (Some(0), Some(1999)));
+        assert!(iter.eq(1..1999));
+    }
+    #[test]
+    fn send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+
+        assert_send_sync::<BinarySearchTree<i32>>();
+        assert_send_sync::<crate::BinarySearchTreeIter<'_, i32>>();
+        assert_send_sync::<crate::IntoSorted<i32>>();
+        assert_send_sync::<crate::AugmentedTree<i32, crate::Sum>>();
+        assert_send_sync::<crate::AvlTree<i32>>();
+        assert_send_sync::<crate::BoundedBst<i32>>();
+        assert_send_sync::<crate::BstMap<i32, String>>();
+        assert_send_sync::<crate::FrozenBst<i32>>();
+        assert_send_sync::<crate::MultiWayTree<i32>>();
+        assert_send_sync::<crate::PackedStaticBst>();
+        assert_send_sync::<crate::Sandbox<i32>>();
+        assert_send_sync::<crate::SortedIndex<i32>>();
+        assert_send_sync::<crate::StaticBst<'_, i32>>();
+        assert_send_sync::<crate::Tree<i32>>();
+        assert_send_sync::<crate::WeightBalancedTree<i32>>();
+        assert_send_sync::<crate::WeightedTree<i32>>();
+    }
+    #[test]
+    fn cursor_mut() {
+        use super::Direction::{Left, Right};
+
+        let mut root = BinarySearchTree::from((1..=7).collect());
+        let mut cursor = root.cursor_mut();
+        assert!(cursor.seek(3));
+        assert_eq!(cursor.path(), &[Left, Right]);
+        assert!(!cursor.descend(Right));
+        assert!(cursor.attach(Right, BinarySearchTree::new(5)).is_err());
+        assert!(cursor.attach(Right, BinarySearchTree::new(4)).is_ok());
+        assert!(cursor.ascend());
+        assert_eq!(cursor.value(), 2);
+
+        let mut unordered = BinarySearchTree::new(1);
+        unordered.left = Some(Box::new(BinarySearchTree::new(2)));
+        assert!(cursor.ascend());
+        let left = cursor.take(Left).unwrap();
+        assert!(cursor.attach(Left, unordered).is_err());
+        assert!(cursor.attach(Left, left).is_ok());
+        assert!(!cursor.seek(0));
+        assert_eq!(root.inorder(), vec![1, 2, 3, 4, 4, 5, 6, 7]);
+    }
+    #[test]
+    fn try_insert_within() {
+        use std::ops::Bound::{Excluded, Included};
+        use super::OutOfRange;
+
+        let mut root = BinarySearchTree::new(150);
+        assert_eq!(root.try_insert_within(100..200, 100), Ok(()));
+        let err = root.try_insert_within(100..200, 200).unwrap_err();
+        assert_eq!(err, OutOfRange { value: 200, start: Included(100), end: Excluded(200) });
+        assert_eq!(err.to_string(), "200 is outside of range (Included(100), Excluded(200))");
+        assert_eq!(root.inorder(), vec![100, 150]);
+    }
+    #[test]
+    fn from_fn() {
+        let root = BinarySearchTree::from_fn(1000, |i| i * 2).unwrap();
+        assert_eq!(root.height(), 10);
+        assert_eq!(root.get(&998), Some(&998));
+        assert!(!root.contains(&999));
+        assert!(root.iter().copied().eq((0..1000).map(|i| i * 2)));
+        assert!(BinarySearchTree::from_fn(0, |i| i).is_none());
+    }
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "Generator is not monotone")]
+    fn from_fn_not_monotone() {
+        BinarySearchTree::from_fn(10, |i| 10 - i);
+    }
+    #[test]
+    fn descending() {
+        let root = BinarySearchTree::from(vec![5, 3, 8, 1, 4, 7, 9, 3]);
+        assert_eq!(root.iter().rev().copied().collect::<Vec<_>>(), vec![9, 8, 7, 5, 4, 3, 3, 1]);
+        assert_eq!(root.iter().rev().nth(2), Some(&7));
+
+        let mut iter = root.into_iter().rev();
+        assert_eq!(iter.next(), Some(9));
+        assert_eq!(iter.next_back(), Some(1));
+        assert_eq!(iter.collect::<Vec<_>>(), vec![8, 7, 5, 4, 3, 3]);
+    }
+    #[test]
+    fn merge_adjacent() {
+        let mut root = BinarySearchTree::from(vec![(1, 3), (3, 5), (7, 8), (8, 9), (9, 12), (20, 21)]);
+        root.merge_adjacent(|a, b| if a.1 >= b.0 { Some((a.0, b.1.max(a.1))) } else { None });
+        assert_eq!(root.inorder(), vec![(1, 5), (7, 12), (20, 21)]);
+
+        let mut root = BinarySearchTree::from(vec![1, 1, 2, 2, 2, 3]);
+        root.merge_adjacent(|a, b| if a == b { Some(*a) } else { None });
+        assert_eq!(root.inorder(), vec![1, 2, 3]);
+        root.merge_adjacent(|a, b| Some(a + b));
+        assert_eq!(root.inorder(), vec![6]);
+    }
+    #[test]
+    fn into_map() {
+        let root = BinarySearchTree::from(vec![5, 3, 8, 1]);
+        let map = root.into_map(|k| k * 10);
+        assert_eq!(map.len(), 4);
+        assert_eq!(map.iter().collect::<Vec<_>>(), [(&1, &10), (&3, &30), (&5, &50), (&8, &80)]);
+
+        let mut root = BinarySearchTree::from(vec![2, 2, 1]);
+        root.insert(2);
+        let mut calls = 0;
+        let map = root.into_map(|_| { calls += 1; calls });
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get(&2), Some(&2));
+    }
+    #[test]
+    fn find_by() {
+        #[derive(Clone, Copy, PartialEq, PartialOrd, Debug)]
+        struct Order { id: u32, price: u32 }
+
+        let root = BinarySearchTree::from((1..=20).map(|id| Order { id, price: id * 10 }).collect());
+        assert_eq!(root.find_by(|o| o.id.cmp(&7)), Some(Order { id: 7, price: 70 }));
+        assert_eq!(root.find_by(|o| o.id.cmp(&21)), None);
+    }
+    #[test]
+    fn count() {
+        let mut root = BinarySearchTree::from(vec![1, 2, 2, 2, 3, 5]);
+        root.insert(2);
+        root.insert(5);
+        assert_eq!(root.count(&2), 4);
+        assert_eq!(root.count(&5), 2);
+        assert_eq!(root.count(&1), 1);
+        assert_eq!(root.count(&4), 0);
+    }
+    #[test]
+    fn nth_from() {
+        let root = BinarySearchTree::from((1..=10).map(|v| v * 10).collect());
+        assert_eq!(root.nth_from(30, 0), Some(30));
+        assert_eq!(root.nth_from(30, 5), Some(80));
+        assert_eq!(root.nth_from(30, -2), Some(10));
+        assert_eq!(root.nth_from(30, -3), None);
+        assert_eq!(root.nth_from(35, 1), Some(50));
+        assert_eq!(root.nth_from(100, 1), None);
+        assert_eq!(root.nth_from(200, -1), Some(100));
+    }
+    #[test]
+    fn latest_common_range() {
+        let root = BinarySearchTree::from((1..=15).collect());
+        assert_eq!(root.latest_common_range(1, 3), Some((1, 3)));
+        assert_eq!(root.latest_common_range(7, 5), Some((5, 7)));
+        assert_eq!(root.latest_common_range(9, 13), Some((9, 15)));
+        assert_eq!(root.latest_common_range(3, 12), Some((1, 15)));
+        assert_eq!(root.latest_common_range(6, 6), Some((5, 7)));
+        assert_eq!(root.latest_common_range(2, 16), None);
+    }
+    #[test]
+    fn floor_ceiling() {
+        let root = BinarySearchTree::from(vec![10, 20, 30, 40]);
+        assert_eq!(root.floor(25), Some(20));
+        assert_eq!(root.floor(30), Some(30));
+        assert_eq!(root.floor(5), None);
+        assert_eq!(root.ceiling(25), Some(30));
+        assert_eq!(root.ceiling(40), Some(40));
+        assert_eq!(root.ceiling(45), None);
+    }
+    #[test]
+    fn approx() {
+        let root = BinarySearchTree::from(vec![0.1, 0.5, 0.9, 1.3]);
+        assert_eq!(root.find_approx(0.52, 0.05), Some(0.5));
+        assert_eq!(root.find_approx(0.88, 0.05), Some(0.9));
+        assert_eq!(root.find_approx(0.7, 0.05), None);
+        assert_eq!(root.find_approx(f64::NAN, 0.05), None);
+    }
+    #[test]
+    fn subtrees() {
+        let root = BinarySearchTree::from(vec![1,2,3,4,5,6,7]);
+        let values: Vec<_> = root.subtrees().map(|n| *n.value()).collect();
+        assert_eq!(values, root.preorder());
+
+        let sizes: Vec<_> = root.subtrees().map(|n| n.subtree().iter().count()).collect();
+        assert_eq!(sizes, vec![7, 3, 1, 1, 3, 1, 1]);
+
+        let top = root.subtrees().next().unwrap();
+        assert_eq!(top.left().map(|n| *n.value()), Some(2));
+        assert_eq!(top.right().map(|n| *n.value()), Some(6));
+    }
+    #[cfg(feature = "prefetch")]
+    #[test]
+    fn fast_lookup() {
+        let mut root = BinarySearchTree::from((0..1000).map(|v| v * 2).collect());
+        root.insert(7);
+        for v in 0..2000 {
+            assert_eq!(root.fast_lookup(v), root.exists(v));
+        }
+    }
+    #[test]
+    fn sorted_iter() {
+        assert!(BinarySearchTree::<i32>::from_sorted_iter(vec![]).is_none());
+
+        let root = BinarySearchTree::from_sorted_iter(0..1000).unwrap();
+        assert_eq!(root.inorder(), (0..1000).collect::<Vec<_>>());
+        assert_eq!(root.height(), 10);
+
+        let data: Vec<i32> = (0..11).collect();
+        let recursive = BinarySearchTree::build_recursive(&data, 0, 10).unwrap();
+        let iterative = BinarySearchTree::from_sorted_iter(data).unwrap();
+        assert_eq!(recursive.preorder(), iterative.preorder());
+    }
+    #[test]
+    fn from_shape() {
+        use super::Shape;
+
+        let chain = BinarySearchTree::from_shape(&Shape::left_chain(4), &[1, 2, 3, 4]).unwrap();
+        assert_eq!(chain.preorder(), vec![4, 3, 2, 1]);
+        assert_eq!(chain.height(), 4);
+
+        let shape = Shape::node(Shape::right_chain(2), Shape::leaf());
+        let root = BinarySearchTree::from_shape(&shape, &[1, 2, 3, 4]).unwrap();
+        assert_eq!(root.preorder(), vec![3, 1, 2, 4]);
+
+        assert!(BinarySearchTree::from_shape(&shape, &[1, 2, 3]).is_none());
+        assert!(BinarySearchTree::from_shape(&shape, &[1, 3, 2, 4]).is_none());
+        assert!(BinarySearchTree::<i32>::from_shape(&Shape::Empty, &[]).is_none());
+    }
+    #[test]
+    fn sorted_slice() {
+        let buffer: Vec<i32> = (0..100).collect();
+        for window in buffer.windows(7) {
+            let root = BinarySearchTree::from_sorted_slice(window);
+            assert_eq!(root.inorder(), window);
+            assert_eq!(root.height(), 3);
+        }
+    }
+    #[test]
+    fn collect() {
+        let data = [5, 3, 8, 1];
+        let mut root: BinarySearchTree<i32> = data.iter().collect();
+        assert_eq!(root.inorder(), vec![1, 3, 5, 8]);
+
+        root.extend(&[4, 9]);
+        root.extend(vec![0]);
+        assert_eq!(root.inorder(), vec![0, 1, 3, 4, 5, 8, 9]);
+
+        let doubled: BinarySearchTree<i32> = root.iter().map(|v| v * 2).collect();
+        assert_eq!(doubled.find_max(), 18);
+    }
+    #[test]
+    fn insert_full() {
+        use super::{DuplicatePolicy, InsertOutcome};
+
+        let mut root = BinarySearchTree::from(vec![1, 3, 5]);
+        assert_eq!(root.insert_full(4, DuplicatePolicy::Ignore), InsertOutcome::Inserted);
+        assert_eq!(root.insert_full(3, DuplicatePolicy::Ignore), InsertOutcome::IgnoredDuplicate);
+        assert_eq!(root.insert_full(3, DuplicatePolicy::Replace), InsertOutcome::ReplacedPrevious(3));
+        assert_eq!(root.insert_full(5, DuplicatePolicy::Keep), InsertOutcome::InsertedDuplicate);
+        assert_eq!(root.inorder(), vec![1, 3, 4, 5, 5]);
+    }
+    #[test]
+    fn insert_checked() {
+        let mut root = BinarySearchTree::from(vec![1.0, 2.0, 3.0]);
+        assert_eq!(root.insert_checked(2.5), Ok(true));
+        assert_eq!(root.insert_checked(2.0), Ok(false));
+
+        let err = root.insert_checked(f64::NAN).err().unwrap();
+        assert!(err.value.is_nan());
+        assert_eq!(err.existing, 2.0);
+        assert_eq!(err.to_string(), "NaN is not comparable with 2.0");
+        assert_eq!(root.inorder(), vec![1.0, 2.0, 2.0, 2.5, 3.0]);
+    }
+    #[test]
+    fn try_extend() {
+        use super::ImportOutcome::{Duplicate, Incomparable, Inserted};
+
+        let mut root = BinarySearchTree::from(vec![1.0, 2.0]);
+        let report = root.try_extend(vec![3.0, 2.0, f64::NAN, 0.5, 3.0]);
+        assert_eq!(report.outcomes, vec![Inserted, Duplicate, Incomparable, Inserted, Duplicate]);
+        assert_eq!(report.count(Inserted), 2);
+        assert_eq!(report.skipped(), vec![1, 2, 4]);
+        assert_eq!(root.inorder(), vec![0.5, 1.0, 2.0, 3.0]);
+    }
+    #[test]
+    fn export_index() {
+        let mut root = BinarySearchTree::from(vec![10, 30, 20]);
+        let index = root.export_index();
+        root.insert(5);
+
+        let handle = std::thread::spawn(move || (index.rank(25), index.select(0), index.contains(5)));
+        assert_eq!(handle.join().unwrap(), (2, Some(10), false));
+        assert_eq!(root.find_min(), 5);
+    }
+    #[test]
+    fn rebuild() {
+        let mut root = BinarySearchTree::new(1);
+        for v in 2..=15 {
+            root.insert(v);
+        }
+        assert_eq!(root.height(), 15);
+        assert!(!root.rebuild_if_unbalanced(4.0));
+        assert!(root.rebuild_if_unbalanced(2.0));
+        assert_eq!(root.height(), 4);
+        assert_eq!(root.inorder(), (1..=15).collect::<Vec<_>>());
+        assert!(!root.rebuild_if_unbalanced(1.0));
+    }
+    #[test]
+    fn rebalance_tasks() {
+        use super::Direction;
+
+        let mut root = BinarySearchTree::from((1..=127).collect());
+        for v in 128..=140 {
+            root.insert(v);
+        }
+        assert!(root.rebalance_tasks(4.0).is_empty());
+
+        let tasks = root.rebalance_tasks(3.0);
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].path(), &[Direction::Right; 3]);
+        assert_eq!(tasks[0].size(), 28);
+
+        let rebuilt: Vec<_> = std::thread::scope(|s| {
+            let handles: Vec<_> = tasks.into_iter().map(|t| s.spawn(move || t.run())).collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        for r in rebuilt {
+            assert!(root.apply_rebuilt(r));
+        }
+        assert_eq!(root.height(), 8);
+        assert_eq!(root.inorder(), (1..=140).collect::<Vec<_>>());
+
+        for v in 141..=160 {
+            root.insert(v);
+        }
+        let task = root.rebalance_tasks(2.0).pop().unwrap().run();
+        root.insert(161);
+        assert!(!root.apply_rebuilt(task));
+    }
+    #[test]
+    fn iter_chunks() {
+        let root = BinarySearchTree::from((1..=7).collect());
+        let chunks: Vec<_> = root.iter_chunks(3).collect();
+        assert_eq!(chunks, vec![vec![1, 2, 3], vec![4, 5, 6], vec![7]]);
+        assert_eq!(root.iter_chunks(7).count(), 1);
+    }
+    #[test]
+    fn split_iter() {
+        let root = BinarySearchTree::from((1..=10).collect());
+        let chunks: Vec<Vec<i32>> = root.split_iter(3).into_iter()
+            .map(|c| c.copied().collect())
+            .collect();
+        assert_eq!(chunks, vec![vec![1, 2, 3, 4], vec![5, 6, 7], vec![8, 9, 10]]);
+
+        assert_eq!(root.split_iter(20).len(), 10);
+        assert_eq!(root.split_iter(0).len(), 1);
+        assert!(root.split_iter(2)[1].clone().rev().eq([10, 9, 8, 7, 6].iter()));
+
+        let dups = BinarySearchTree::from(vec![1, 1, 1, 1, 2, 2]);
+        let chunks: Vec<Vec<i32>> = dups.split_iter(3).into_iter()
+            .map(|c| c.copied().collect())
+            .collect();
+        assert_eq!(chunks, vec![vec![1, 1, 1, 1], vec![2, 2]]);
+
+        let sums: Vec<i32> = std::thread::scope(|s| {
+            let handles: Vec<_> = root.split_iter(4).into_iter()
+                .map(|c| s.spawn(move || c.sum::<i32>()))
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        assert_eq!(sums.iter().sum::<i32>(), 55);
+    }
+    #[test]
+    fn retain_range() {
+        let mut root = BinarySearchTree::from((1..=20).collect());
+        let mut seen = Vec::new();
+        root.retain_range(5..=10, |v| {
+            seen.push(*v);
+            v % 2 == 0
+        });
+        assert_eq!(seen, vec![5, 6, 7, 8, 9, 10]);
+        assert_eq!(root.inorder(), vec![1, 2, 3, 4, 6, 8, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20]);
+
+        assert!(root.retain_range(..5, |_| false));
+        assert_eq!(root.find_min(), 6);
+        assert!(!root.retain_range(.., |v| *v > 100));
+        assert_eq!(root.inorder(), vec![20]);
+    }
+    #[test]
+    fn remove_k() {
+        let mut root = BinarySearchTree::from((1..=20).collect());
+        assert_eq!(root.remove_min_k(3), vec![1, 2, 3]);
+        assert_eq!(root.remove_max_k(4), vec![20, 19, 18, 17]);
+        assert!(root.remove_min_k(0).is_empty());
+        assert_eq!(root.inorder(), (4..=16).collect::<Vec<_>>());
+        assert_eq!(root.remove_min_k(12).len(), 12);
+        assert_eq!(root.inorder(), vec![16]);
+    }
+    #[test]
+    fn remove_k_everything() {
+        let mut root = BinarySearchTree::from(vec![1, 2, 3]);
+        assert_eq!(root.remove_max_k(3), vec![3, 2]);
+        assert_eq!(root.inorder(), vec![1]);
+        let mut root = BinarySearchTree::from(vec![1, 2, 3]);
+        assert_eq!(root.remove_min_k(10), vec![1, 2]);
+        assert_eq!(root.inorder(), vec![3]);
+    }
+    #[test]
+    fn swap_contents() {
+        let mut front = BinarySearchTree::from(vec![1, 2, 3]);
+        let mut back = BinarySearchTree::new(10);
+        front.swap_contents(&mut back);
+        assert_eq!(front.inorder(), vec![10]);
+        assert_eq!(back.inorder(), vec![1, 2, 3]);
+    }
+    #[test]
+    fn edit_script() {
+        use super::Edit::{Insert, Remove};
+
+        let ours = BinarySearchTree::from(vec![1, 2, 2, 4, 6]);
+        let theirs = BinarySearchTree::from(vec![2, 3, 4, 6, 6, 7]);
+        let script = ours.edit_script(&theirs);
+        assert_eq!(script, vec![Remove(1), Remove(2), Insert(3), Insert(6), Insert(7)]);
+        assert!(ours.edit_script(&ours).is_empty());
+
+        let mut copy = ours.inorder();
+        for edit in script {
+            match edit {
+                Insert(v) => copy.push(v),
+                Remove(v) => { copy.remove(copy.iter().position(|&x| x == v).unwrap()); }
+            }
+        }
+        copy.sort();
+        assert_eq!(copy, theirs.inorder());
+    }
+    #[test]
+    fn apply_sorted_updates() {
+        use super::Edit::{Insert, Remove};
+
+        let mut ours = BinarySearchTree::from(vec![1, 2, 2, 4, 6]);
+        let theirs = BinarySearchTree::from(vec![2, 3, 4, 6, 6, 7]);
+        assert!(ours.apply_sorted_updates(ours.edit_script(&theirs)));
+        assert_eq!(ours.inorder(), theirs.inorder());
+
+        assert!(ours.apply_sorted_updates([Remove(0), Insert(1), Remove(3), Remove(6), Insert(8)]));
+        assert_eq!(ours.inorder(), vec![1, 2, 4, 6, 7, 8]);
+        assert_eq!(ours.height(), 3);
+
+        let mut root = BinarySearchTree::from(vec![1, 2]);
+        assert!(!root.apply_sorted_updates([Remove(1), Remove(2)]));
+        assert_eq!(root.inorder(), vec![1, 2]);
+    }
+    #[test]
+    #[should_panic(expected = "Updates are not sorted")]
+    fn apply_unsorted_updates() {
+        let mut root = BinarySearchTree::new(1);
+        root.apply_sorted_updates([super::Edit::Insert(3), super::Edit::Insert(2)]);
+    }
+    #[test]
+    fn contains_sorted() {
+        let root = BinarySearchTree::from(vec![1, 3, 3, 5, 7, 9]);
+        assert_eq!(root.contains_sorted(&[1, 3, 3, 9]), Ok(()));
+        assert_eq!(root.contains_sorted(&[]), Ok(()));
+        assert_eq!(root.contains_sorted(&[3, 4, 5]), Err(4));
+        assert_eq!(root.contains_sorted(&[3, 3, 3]), Err(3));
+        assert_eq!(root.contains_sorted(&[9, 10]), Err(10));
+    }
+    #[test]
+    fn insert_bounded() {
+        let mut root = BinarySearchTree::new(1);
+        for v in 2..=15 {
+            assert_eq!(root.insert_bounded(v, 3), Ok(()));
+            assert!(root.height() <= 4);
+        }
+        assert_eq!(root.inorder(), (1..=15).collect::<Vec<_>>());
+        assert_eq!(root.insert_bounded(16, 3), Err(16));
+        assert_eq!(root.insert_bounded(0, 4), Ok(()));
+        assert_eq!(root.height(), 5);
+
+        let mut chain = BinarySearchTree::new(1);
+        chain.extend([2, 3]);
+        assert_eq!(chain.insert_bounded(4, 2), Ok(()));
+        assert_eq!(chain.val, 1);
+        assert_eq!(chain.inorder(), vec![1, 2, 3, 4]);
+        assert_eq!(chain.height(), 3);
+    }
+    #[test]
+    fn owned_values() {
+        let mut root: BinarySearchTree<String> = ["m", "c", "x"].iter().map(|s| s.to_string()).collect();
+        root.insert(String::from("a"));
+        root.extend([String::from("z"), String::from("c")]);
+
+        assert!(root.contains(&String::from("x")));
+        assert!(root.exists(String::from("c")));
+        assert_eq!(root.get(&String::from("q")), None);
+        assert_eq!((root.first().as_str(), root.last().as_str()), ("a", "z"));
+        assert_eq!(root.height(), 3);
+        assert_eq!(root.iter().map(String::as_str).collect::<String>(), "accmxz");
+        assert_eq!(root.into_iter().collect::<Vec<_>>(), ["a", "c", "c", "m", "x", "z"]);
+    }
+    #[test]
+    fn rotate_root_to() {
+        let mut root = BinarySearchTree::from((1..=15).collect());
+        assert!(root.rotate_root_to(&11));
+        assert_eq!(root.val, 11);
+        assert_eq!(root.inorder(), (1..=15).collect::<Vec<_>>());
+        root.validate_against(&(1..=15).collect::<Vec<_>>());
+        assert_eq!(root.height(), 5);
+
+        assert!(root.rotate_root_to(&11));
+        assert!(!root.rotate_root_to(&16));
+        assert_eq!(root.val, 11);
+    }
+    #[test]
+    fn apply() {
+        use std::ops::Bound::{Excluded, Included};
+        use super::{OpResult, Operation};
+
+        let mut root = BinarySearchTree::new(50);
+        let mut model = vec![50];
+        let mut seed: u32 = 7;
+        for _ in 0..2000 {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            let v = (seed >> 16) % 100;
+            let op = match (seed >> 8) % 6 {
+                0 | 1 => Operation::Insert(v),
+                2 => Operation::Remove(v),
+                3 => Operation::Count(v),
+                4 => Operation::QueryRange(Included(v), Excluded(v + 20)),
+                _ => Operation::Min
+            };
+
+            let expected = match op {
+                Operation::Insert(v) => {
+                    model.push(v);
+                    model.sort();
+                    OpResult::Inserted
+                },
+                Operation::Remove(v) => match model.iter().position(|&x| x == v) {
+                    Some(_) if model.len() == 1 => OpResult::Refused,
+                    Some(i) => {
+                        model.remove(i);
+                        OpResult::Removed(true)
+                    },
+                    None => OpResult::Removed(false)
+                },
+                Operation::Count(v) => OpResult::Count(model.iter().filter(|&&x| x == v).count()),
+                Operation::QueryRange(..) => {
+                    OpResult::Range(model.iter().copied().filter(|x| (v..v + 20).contains(x)).collect())
+                },
+                _ => OpResult::Min(model[0])
+            };
+            assert_eq!(root.apply(op), expected);
+        }
+        assert_eq!(root.inorder(), model);
+        assert_eq!(root.apply(Operation::Max), OpResult::Max(*model.last().unwrap()));
+        assert_eq!(root.apply(Operation::Contains(100)), OpResult::Contains(false));
+    }
+    #[test]
+    fn validate_against() {
+        let mut root = BinarySearchTree::from(vec![1, 2, 2, 3]);
+        root.insert(5);
+        root.validate_against(&[1, 2, 2, 3, 5]);
+
+        let result = std::panic::catch_unwind(|| root.validate_against(&[1, 2, 3, 4, 5]));
+        let message = *result.err().unwrap().downcast::<String>().unwrap();
+        assert_eq!(message, "Tree does not match expected elements\n  missing: [4]\n  unexpected: [2]");
+
+        let mut broken = BinarySearchTree::from(vec![1, 2, 3, 4, 5]);
+        broken.left.as_mut().unwrap().right.as_mut().unwrap().val = 9;
+        let result = std::panic::catch_unwind(|| broken.validate_against(&[1, 2, 3, 4, 5]));
+        let message = *result.err().unwrap().downcast::<String>().unwrap();
+        assert_eq!(message, "Tree order is broken at [Left, Right], node 9 is out of order");
+    }
+    #[test]
     fn iterator_consumable() {
         let root = BinarySearchTree::from(vec![1,2,3]);
         let mut i = 1;
@@ -349,6 +2112,58 @@ mod tests {
         assert_eq!(root.height(), 2);
     }
     #[test]
+    fn iterator_owned() {
+        let root = BinarySearchTree::from(vec![3,1,2]);
+        let owned: Vec<i32> = root.iter_owned().collect();
+        assert_eq!(owned, vec![1, 2, 3]);
+
+        let first: Vec<i32> = root.iter().cloned().take(2).collect();
+        assert_eq!(first, vec![1, 2]);
+    }
+    #[test]
+    fn iterator_order() {
+        let mut root = BinarySearchTree::from(vec![5, 9, 1, 7, 3]);
+        root.insert(4);
+        root.insert(8);
+        let sorted: Vec<_> = root.iter().copied().collect();
+        assert_eq!(sorted, vec![1, 3, 4, 5, 7, 8, 9]);
+        assert_eq!(sorted, root.inorder());
+
+        let mut unordered: Vec<_> = root.iter_unordered().copied().collect();
+        assert_eq!(unordered, root.preorder());
+        unordered.sort();
+        assert_eq!(unordered, sorted);
+    }
+    #[test]
+    fn iterator_with_path() {
+        use super::Direction::{Left, Right};
+
+        let mut root = BinarySearchTree::from(vec![1, 2, 3]);
+        root.insert(4);
+        let paths: Vec<_> = root.iter_with_path().collect();
+        assert_eq!(paths, vec![
+            (&1, vec![Left]),
+            (&2, vec![]),
+            (&3, vec![Right]),
+            (&4, vec![Right, Right])
+        ]);
+    }
+    #[test]
+    fn iterator_peek() {
+        let root = BinarySearchTree::from(vec![1,2,3,4]);
+        let mut iter = root.iter();
+        assert_eq!(iter.peek_next(), Some(&1));
+        assert_eq!(iter.peek_prev(), Some(&4));
+        assert_eq!(iter.next(), Some(&1));
+        assert_eq!(iter.next_back(), Some(&4));
+        assert_eq!(iter.peek_next(), Some(&2));
+        assert_eq!(iter.peek_prev(), Some(&3));
+        assert_eq!(iter.next(), Some(&2));
+        assert_eq!(iter.next(), Some(&3));
+        assert_eq!(iter.peek_next(), None);
+        assert_eq!(iter.peek_prev(), None);
+    }
+    #[test]
     fn height() {
         let root = BinarySearchTree::from(vec![1]);
         assert_eq!(root.height(), 1);
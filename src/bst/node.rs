@@ -0,0 +1,236 @@
+//! Tree node and handles to it, including `CursorMut` for building
+//! custom algorithms on top of raw tree structure.
+use std::cmp::Ordering;
+
+/// Structure of a tree without values, for `BinarySearchTree::from_shape()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Shape {
+    /// No node.
+    Empty,
+    /// Node with left and right sub tree shapes.
+    Node(Box<Shape>, Box<Shape>)
+}
+
+impl Shape {
+    /// Single node without children.
+    pub fn leaf() -> Shape {
+        Shape::node(Shape::Empty, Shape::Empty)
+    }
+
+    /// Node with given children.
+    pub fn node(left: Shape, right: Shape) -> Shape {
+        Shape::Node(Box::new(left), Box::new(right))
+    }
+
+    /// Chain of `n` nodes where each node is left child of previous one.
+    pub fn left_chain(n: usize) -> Shape {
+        (0..n).fold(Shape::Empty, |s, _| Shape::node(s, Shape::Empty))
+    }
+
+    /// Chain of `n` nodes where each node is right child of previous one.
+    pub fn right_chain(n: usize) -> Shape {
+        (0..n).fold(Shape::Empty, |s, _| Shape::node(Shape::Empty, s))
+    }
+
+    /// Number of nodes.
+    pub fn size(&self) -> usize {
+        match self {
+            Shape::Empty => 0,
+            Shape::Node(l, r) => l.size() + r.size() + 1
+        }
+    }
+}
+
+/// Side of a parent node on which a child hangs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Left,
+    Right
+}
+
+pub struct BinarySearchTree<T> {
+    pub(crate) val: T,
+    pub(crate) left: Option<Box<BinarySearchTree<T>>>,
+    pub(crate) right: Option<Box<BinarySearchTree<T>>>
+}
+
+/// Read-only handle to a node in a tree.
+pub struct NodeRef<'a, T> {
+    pub(super) node: &'a BinarySearchTree<T>
+}
+
+impl<'a, T> NodeRef<'a, T> {
+    /// Value stored in this node.
+    pub fn value(&self) -> &'a T {
+        &self.node.val
+    }
+
+    /// Left child of this node.
+    pub fn left(&self) -> Option<NodeRef<'a, T>> {
+        self.node.left.as_deref().map(|node| NodeRef { node })
+    }
+
+    /// Right child of this node.
+    pub fn right(&self) -> Option<NodeRef<'a, T>> {
+        self.node.right.as_deref().map(|node| NodeRef { node })
+    }
+
+    /// Sub tree rooted at this node.
+    pub fn subtree(&self) -> &'a BinarySearchTree<T> {
+        self.node
+    }
+}
+
+impl<'a, T> Clone for NodeRef<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Copy for NodeRef<'a, T> {}
+
+/// Mutable cursor over nodes of a tree, made by
+/// `BinarySearchTree::cursor_mut()`.
+///
+/// It is a low level API for custom tree algorithms: the cursor moves
+/// one step at a time and whole sub trees can be detached and attached
+/// below it. Attaching is checked against the bounds set by every
+/// ancestor, so tree order can not be broken through a cursor.
+///
+/// # Example
+///
+/// ```rust
+/// use ds_bst::{BinarySearchTree, Direction};
+///
+/// let mut root = BinarySearchTree::from(vec![1, 2, 3, 4, 5]);
+/// let mut cursor = root.cursor_mut();
+/// let right = cursor.take(Direction::Right).unwrap();
+/// assert_eq!(right.inorder(), vec![4, 5]);
+///
+/// // 2 is smaller than root 3, so it can not go on its right
+/// assert!(cursor.attach(Direction::Right, BinarySearchTree::new(2)).is_err());
+/// assert!(cursor.attach(Direction::Right, right).is_ok());
+/// assert_eq!(root.inorder(), vec![1, 2, 3, 4, 5]);
+/// ```
+pub struct CursorMut<'a, T> {
+    root: &'a mut BinarySearchTree<T>,
+    path: Vec<Direction>
+}
+
+impl<T: PartialOrd + Copy> BinarySearchTree<T> {
+    /// Returns cursor placed at root, see `CursorMut`.
+    pub fn cursor_mut(&mut self) -> CursorMut<'_, T> {
+        CursorMut { root: self, path: Vec::new() }
+    }
+}
+
+impl<'a, T: PartialOrd + Copy> CursorMut<'a, T> {
+    fn node(&self) -> &BinarySearchTree<T> {
+        self.root.subtree_at(&self.path).expect("Cursor path is valid")
+    }
+
+    fn node_mut(&mut self) -> &mut BinarySearchTree<T> {
+        self.root.subtree_at_mut(&self.path).expect("Cursor path is valid")
+    }
+
+    fn child(&self, side: Direction) -> Option<&BinarySearchTree<T>> {
+        let node = self.node();
+        match side {
+            Direction::Left => node.left.as_deref(),
+            Direction::Right => node.right.as_deref()
+        }
+    }
+
+    /// Value of node under cursor.
+    pub fn value(&self) -> T {
+        self.node().val
+    }
+
+    /// Steps from root to node under cursor.
+    pub fn path(&self) -> &[Direction] {
+        &self.path
+    }
+
+    /// Checks if node under cursor has a child on `side`.
+    pub fn has_child(&self, side: Direction) -> bool {
+        self.child(side).is_some()
+    }
+
+    /// Moves to child on `side`, returns `false` if there is none.
+    pub fn descend(&mut self, side: Direction) -> bool {
+        let exists = self.has_child(side);
+        if exists {
+            self.path.push(side);
+        }
+        exists
+    }
+
+    /// Moves to parent, returns `false` at root.
+    pub fn ascend(&mut self) -> bool {
+        self.path.pop().is_some()
+    }
+
+    /// Descends from current node the way a search for `val` does and
+    /// stops at the first equal element or at the last node on the way.
+    /// Returns `true` if an equal element was reached.
+    /// Uses `O(n)` time.
+    pub fn seek(&mut self, val: T) -> bool {
+        loop {
+            let side = match self.value().partial_cmp(&val) {
+                Some(Ordering::Equal) => return true,
+                Some(Ordering::Greater) => Direction::Left,
+                Some(Ordering::Less) => Direction::Right,
+                None => return false
+            };
+            if !self.descend(side) {
+                return false;
+            }
+        }
+    }
+
+    /// Detaches sub tree on `side` of node under cursor.
+    pub fn take(&mut self, side: Direction) -> Option<BinarySearchTree<T>> {
+        let node = self.node_mut();
+        let link = match side {
+            Direction::Left => &mut node.left,
+            Direction::Right => &mut node.right
+        };
+        link.take().map(|sub| *sub)
+    }
+
+    /// Attaches `sub` on `side` of node under cursor. Sub tree is given
+    /// back if that spot is taken, if `sub` is not ordered itself or if
+    /// any element falls outside bounds of the spot.
+    /// Uses `O(k + h)` time for a sub tree of `k` elements.
+    pub fn attach(&mut self, side: Direction, sub: BinarySearchTree<T>) -> Result<(), BinarySearchTree<T>> {
+        if self.has_child(side) {
+            return Err(sub);
+        }
+        let mut path = self.path.clone();
+        path.push(side);
+        let (mut lower, mut upper) = (None, None);
+        let mut node = &*self.root;
+        for step in path {
+            match step {
+                Direction::Left => upper = Some(node.val),
+                Direction::Right => lower = Some(node.val)
+            }
+            node = match (step, &node.left, &node.right) {
+                (Direction::Left, Some(child), _) | (Direction::Right, _, Some(child)) => child,
+                _ => break
+            };
+        }
+
+        let fits = lower.is_none_or(|l| matches!(l.partial_cmp(&sub.val), Some(Ordering::Less | Ordering::Equal)))
+            && upper.is_none_or(|u| matches!(sub.val.partial_cmp(&u), Some(Ordering::Less | Ordering::Equal)));
+        if !fits || sub.find_misplaced(lower, upper, &mut Vec::new()) {
+            return Err(sub);
+        }
+        let node = self.node_mut();
+        match side {
+            Direction::Left => node.left = Some(Box::new(sub)),
+            Direction::Right => node.right = Some(Box::new(sub))
+        }
+        Ok(())
+    }
+}
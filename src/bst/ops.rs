@@ -0,0 +1,252 @@
+//! Operation, result and report types used by tree methods.
+use std::error::Error;
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::Bound;
+
+use super::{BinarySearchTree, Direction};
+
+/// What `BinarySearchTree::insert_full()` does when an equal
+/// element is already stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicatePolicy {
+    /// Store another copy, same as `insert()`.
+    Keep,
+    /// Overwrite stored element with the new one.
+    Replace,
+    /// Leave tree unchanged.
+    Ignore
+}
+
+/// Outcome of `BinarySearchTree::insert_full()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InsertOutcome<T> {
+    /// No equal element existed, value was inserted.
+    Inserted,
+    /// An equal element existed and another copy was inserted.
+    InsertedDuplicate,
+    /// An equal element existed and was replaced, it is returned.
+    ReplacedPrevious(T),
+    /// An equal element existed and value was dropped.
+    IgnoredDuplicate
+}
+
+/// Error of `BinarySearchTree::insert_checked()`, new value could not
+/// be ordered against an element already stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Incomparable<T> {
+    /// Value which was not inserted.
+    pub value: T,
+    /// Stored element it is not comparable with.
+    pub existing: T
+}
+
+impl<T: fmt::Debug> fmt::Display for Incomparable<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} is not comparable with {:?}", self.value, self.existing)
+    }
+}
+
+impl<T: fmt::Debug> Error for Incomparable<T> {}
+
+/// Error of `BinarySearchTree::try_insert_within()`, value falls
+/// outside of allowed range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutOfRange<T> {
+    /// Value which was not inserted.
+    pub value: T,
+    /// Start of allowed range.
+    pub start: Bound<T>,
+    /// End of allowed range.
+    pub end: Bound<T>
+}
+
+impl<T: fmt::Debug> fmt::Display for OutOfRange<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} is outside of range ({:?}, {:?})", self.value, self.start, self.end)
+    }
+}
+
+impl<T: fmt::Debug> Error for OutOfRange<T> {}
+
+/// What happened to one item of `BinarySearchTree::try_extend()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportOutcome {
+    /// Item was inserted.
+    Inserted,
+    /// An equal element existed, item was skipped.
+    Duplicate,
+    /// Item was not comparable with a stored element and was skipped.
+    Incomparable
+}
+
+/// Report of `BinarySearchTree::try_extend()`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportReport {
+    /// Outcome of each item in input order.
+    pub outcomes: Vec<ImportOutcome>
+}
+
+impl ImportReport {
+    /// Number of items with given outcome.
+    pub fn count(&self, outcome: ImportOutcome) -> usize {
+        self.outcomes.iter().filter(|&&o| o == outcome).count()
+    }
+
+    /// Input positions of items which were not inserted.
+    pub fn skipped(&self) -> Vec<usize> {
+        self.outcomes.iter().enumerate()
+            .filter(|(_, &o)| o != ImportOutcome::Inserted)
+            .map(|(i, _)| i)
+            .collect()
+    }
+}
+
+/// Single step of `BinarySearchTree::edit_script()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edit<T> {
+    /// Insert one copy of the element.
+    Insert(T),
+    /// Remove one copy of the element.
+    Remove(T)
+}
+
+/// Single operation for `BinarySearchTree::apply()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation<T> {
+    /// Insert another copy, same as `insert()`.
+    Insert(T),
+    /// Remove one copy.
+    Remove(T),
+    /// Check if element exists.
+    Contains(T),
+    /// Count copies of element.
+    Count(T),
+    /// Collect elements between the bounds in sorted order.
+    QueryRange(Bound<T>, Bound<T>),
+    /// Find minimum element.
+    Min,
+    /// Find maximum element.
+    Max
+}
+
+/// Result of `BinarySearchTree::apply()`, one variant per `Operation`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OpResult<T> {
+    /// Element was inserted.
+    Inserted,
+    /// One copy was removed, `false` if element did not exist.
+    Removed(bool),
+    /// Remove of the only element was refused, a tree cannot be empty.
+    Refused,
+    /// Whether element exists.
+    Contains(bool),
+    /// Number of copies.
+    Count(usize),
+    /// Elements within bounds in sorted order.
+    Range(Vec<T>),
+    /// Minimum element.
+    Min(T),
+    /// Maximum element.
+    Max(T)
+}
+
+/// Merges two sorted sequences into inserts and removes turning
+/// the first one into the second one.
+pub(super) fn merge_edits<'a, T, A, B>(ours: A, theirs: B) -> Vec<Edit<T>>
+    where
+        T: PartialOrd + Copy + 'a,
+        A: Iterator<Item = &'a T>,
+        B: Iterator<Item = &'a T>
+{
+    let mut script = Vec::new();
+    let mut ours = ours.peekable();
+    let mut theirs = theirs.peekable();
+
+    loop {
+        match (ours.peek(), theirs.peek()) {
+            (None, None) => return script,
+            (Some(_), None) => script.extend(ours.by_ref().map(|&v| Edit::Remove(v))),
+            (None, Some(_)) => script.extend(theirs.by_ref().map(|&v| Edit::Insert(v))),
+            (Some(&&a), Some(&&b)) => match a.partial_cmp(&b) {
+                Some(Ordering::Equal) => {
+                    ours.next();
+                    theirs.next();
+                },
+                Some(Ordering::Less) => {
+                    script.push(Edit::Remove(a));
+                    ours.next();
+                },
+                Some(Ordering::Greater) => {
+                    script.push(Edit::Insert(b));
+                    theirs.next();
+                },
+                None => {
+                    script.push(Edit::Remove(a));
+                    script.push(Edit::Insert(b));
+                    ours.next();
+                    theirs.next();
+                }
+            }
+        }
+    }
+}
+
+/// Walks two sorted sequences in step and counts elements found in
+/// both and elements found only in `ours`.
+pub(super) fn merge_sizes<'a, T, A, B>(ours: A, theirs: B) -> (usize, usize)
+    where
+        T: PartialOrd + 'a,
+        A: Iterator<Item = &'a T>,
+        B: Iterator<Item = &'a T>
+{
+    let (mut ours, mut theirs) = (ours.peekable(), theirs.peekable());
+    let (mut shared, mut only_ours) = (0, 0);
+    while let (Some(a), Some(b)) = (ours.peek(), theirs.peek()) {
+        match a.partial_cmp(b) {
+            Some(Ordering::Equal) => {
+                shared += 1;
+                ours.next();
+                theirs.next();
+            },
+            Some(Ordering::Greater) => { theirs.next(); },
+            _ => {
+                only_ours += 1;
+                ours.next();
+            }
+        }
+    }
+    (shared, only_ours + ours.count())
+}
+
+/// Sub tree rebuild found by `BinarySearchTree::rebalance_tasks()`.
+pub struct RebalanceTask<T> {
+    pub(super) path: Vec<Direction>,
+    pub(super) values: Vec<T>
+}
+
+/// Balanced sub tree built by `RebalanceTask::run()`, waiting to be
+/// put back with `BinarySearchTree::apply_rebuilt()`.
+pub struct RebuiltSubtree<T> {
+    pub(super) path: Vec<Direction>,
+    pub(super) root: BinarySearchTree<T>
+}
+
+impl<T: PartialOrd + Copy> RebalanceTask<T> {
+    /// Steps from root to the sub tree.
+    pub fn path(&self) -> &[Direction] {
+        &self.path
+    }
+
+    /// Number of elements in the sub tree.
+    pub fn size(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Builds balanced sub tree, source tree is not touched.
+    /// Uses `O(k)` time for a sub tree of `k` elements.
+    pub fn run(self) -> RebuiltSubtree<T> {
+        let root = BinarySearchTree::from_sorted_iter(self.values).expect("Empty node");
+        RebuiltSubtree { path: self.path, root }
+    }
+}
@@ -0,0 +1,327 @@
+//! Range queries, visiting only sub trees which overlap a range.
+use std::cmp::Ordering;
+use std::ops::{Bound, Range, RangeBounds};
+
+use crate::fold::Fold;
+use crate::observe::{OpKind, Timer};
+use super::traversal::MultiScan;
+use super::{BinarySearchTree, BinarySearchTreeIter, Link};
+
+impl<T: PartialOrd> BinarySearchTree<T> {
+    /// Returns iterator over elements within `range` in ascending order,
+    /// e.g. `root.range(3..=10)`. Only paths to both ends of range are
+    /// searched up front and sub trees outside of it are never visited.
+    /// Uses `O(h + k)` time for `k` elements yielded.
+    pub fn range<R: RangeBounds<T>>(&self, range: R) -> BinarySearchTreeIter<'_, T> {
+        let timer = Timer::start();
+        let (iter, depth) = BinarySearchTreeIter::range(self, &range);
+        timer.finish(OpKind::Range, depth);
+        iter
+    }
+}
+
+impl<T: PartialOrd + Copy> BinarySearchTree<T> {
+    /// Finds largest element within upper `bound`, `Bound::Excluded(x)`
+    /// gives largest element strictly less than `x`.
+    /// Uses `O(n)` time.
+    pub fn floor_bound(&self, bound: Bound<T>) -> Option<T> {
+        let mut found = None;
+        let mut current = Some(self);
+        while let Some(node) = current {
+            if before_end(bound.as_ref(), &node.val) {
+                found = Some(node.val);
+                current = node.right.as_deref();
+            } else {
+                current = node.left.as_deref();
+            }
+        }
+        found
+    }
+
+    /// Finds smallest element within lower `bound`, `Bound::Excluded(x)`
+    /// gives smallest element strictly greater than `x`.
+    /// Uses `O(n)` time.
+    pub fn ceiling_bound(&self, bound: Bound<T>) -> Option<T> {
+        let mut found = None;
+        let mut current = Some(self);
+        while let Some(node) = current {
+            if before_start(bound.as_ref(), &node.val) {
+                current = node.right.as_deref();
+            } else {
+                found = Some(node.val);
+                current = node.left.as_deref();
+            }
+        }
+        found
+    }
+
+    /// Counts elements within `range`, sub trees entirely outside
+    /// of it are skipped.
+    /// Uses `O(n)` time.
+    pub fn range_count<R: RangeBounds<T>>(&self, range: R) -> usize {
+        let timer = Timer::start();
+        let mut depth = 0;
+        let count = self.count_within(&range, &mut depth);
+        timer.finish(OpKind::RangeCount, depth);
+        count
+    }
+
+    fn count_within<R: RangeBounds<T>>(&self, range: &R, visited: &mut usize) -> usize {
+        *visited += 1;
+        let mut count_in = |node: &Link<T>| match node {
+            None => 0,
+            Some(ref n) => n.count_within(range, visited)
+        };
+
+        if before_start(range.start_bound(), &self.val) {
+            count_in(&self.right)
+        } else if !before_end(range.end_bound(), &self.val) {
+            count_in(&self.left)
+        } else {
+            1 + count_in(&self.left) + count_in(&self.right)
+        }
+    }
+
+    /// Visits elements within `range` in sorted order and returns first
+    /// result for which `f` gives `Some`. Sub trees outside of range are
+    /// skipped and search stops as soon as `f` succeeds.
+    /// Uses `O(n)` time.
+    pub fn find_map_range<R, B, F>(&self, range: R, mut f: F) -> Option<B>
+        where
+            R: RangeBounds<T>,
+            F: FnMut(T) -> Option<B>
+    {
+        self.find_map_in(&range, &mut f)
+    }
+
+    fn find_map_in<R, B, F>(&self, range: &R, f: &mut F) -> Option<B>
+        where
+            R: RangeBounds<T>,
+            F: FnMut(T) -> Option<B>
+    {
+        let starts_after = before_start(range.start_bound(), &self.val);
+        let ends_before = !before_end(range.end_bound(), &self.val);
+
+        if let (false, Some(ref node)) = (starts_after, &self.left) {
+            if let Some(found) = node.find_map_in(range, f) {
+                return Some(found);
+            }
+        }
+        if !starts_after && !ends_before {
+            if let Some(found) = f(self.val) {
+                return Some(found);
+            }
+        }
+        match (ends_before, &self.right) {
+            (false, Some(ref node)) => node.find_map_in(range, f),
+            _ => None
+        }
+    }
+
+    /// Aggregates elements within `range` in sorted order with fold `F`,
+    /// e.g. `root.range_fold::<Sum, _>(a..b)`. Sub trees outside of range
+    /// are skipped, see `AugmentedTree::range_fold()` for an `O(log n)` one.
+    /// Uses `O(h + k)` time for `k` elements in range.
+    pub fn range_fold<F: Fold<T>, R: RangeBounds<T>>(&self, range: R) -> F::Acc {
+        self.fold_in::<F, R>(&range, F::identity())
+    }
+
+    fn fold_in<F: Fold<T>, R: RangeBounds<T>>(&self, range: &R, mut acc: F::Acc) -> F::Acc {
+        let starts_after = before_start(range.start_bound(), &self.val);
+        let ends_before = !before_end(range.end_bound(), &self.val);
+
+        if let (false, Some(ref node)) = (starts_after, &self.left) {
+            acc = node.fold_in::<F, R>(range, acc);
+        }
+        if !starts_after && !ends_before {
+            acc = F::combine(acc, F::from_value(&self.val));
+        }
+        if let (false, Some(ref node)) = (ends_before, &self.right) {
+            acc = node.fold_in::<F, R>(range, acc);
+        }
+        acc
+    }
+
+    /// Builds a balanced copy of elements within `range`, sub trees
+    /// outside of it are not visited. Returns `None` when range holds
+    /// no element.
+    /// Uses `O(n)` time.
+    pub fn clone_range<R: RangeBounds<T>>(&self, range: R) -> Option<BinarySearchTree<T>> {
+        let mut values = Vec::new();
+        self.collect_range(&range, &mut values);
+        BinarySearchTree::from_sorted_iter(values)
+    }
+
+    /// Counts elements in buckets split by sorted `boundaries` in one
+    /// pass. Bucket `0` holds elements below first boundary, bucket `i`
+    /// those from boundary `i - 1` up to boundary `i` excluded and last
+    /// one everything from last boundary on, so there are
+    /// `boundaries.len() + 1` counts. Nodes keep no sub tree sizes, so
+    /// counting by rank would walk the tree for every boundary, see
+    /// `WeightBalancedTree::group_by_ranges()` for an `O(k log n)` one.
+    /// Uses `O(n + k)` time for `k` boundaries.
+    pub fn group_by_ranges(&self, boundaries: &[T]) -> Vec<usize> {
+        let mut counts = vec![0; boundaries.len() + 1];
+        let mut bucket = 0;
+        for v in self.iter() {
+            while bucket < boundaries.len() && *v >= boundaries[bucket] {
+                bucket += 1;
+            }
+            counts[bucket] += 1;
+        }
+        counts
+    }
+
+    /// Answers many range queries in one inorder sweep instead of
+    /// descending from root for each of them. Yields `(query, element)`
+    /// pairs sorted by element and then by index of query in `queries`,
+    /// which may overlap and come in any order. Parts of tree before
+    /// the next query are skipped.
+    /// Uses `O(n + q log q + k)` time for `k` results.
+    pub fn multi_range(&self, queries: &[Range<T>]) -> Vec<(usize, T)> {
+        let mut order: Vec<usize> = (0..queries.len()).collect();
+        order.sort_by(|&a, &b| queries[a].start.partial_cmp(&queries[b].start).unwrap_or(Ordering::Equal));
+
+        let mut scan = MultiScan {
+            queries,
+            pending: order.into_iter().peekable(),
+            active: Vec::new(),
+            out: Vec::new()
+        };
+        scan.walk(self);
+        scan.out
+    }
+
+    pub(super) fn collect_range<R: RangeBounds<T>>(&self, range: &R, ret: &mut Vec<T>) {
+        let starts_after = before_start(range.start_bound(), &self.val);
+        let ends_before = !before_end(range.end_bound(), &self.val);
+
+        if let (false, Some(ref node)) = (starts_after, &self.left) {
+            node.collect_range(range, ret);
+        }
+        if !starts_after && !ends_before {
+            ret.push(self.val);
+        }
+        if let (false, Some(ref node)) = (ends_before, &self.right) {
+            node.collect_range(range, ret);
+        }
+    }
+}
+
+/// Checks if `v` lies before start of a range.
+pub(crate) fn before_start<T: PartialOrd>(bound: Bound<&T>, v: &T) -> bool {
+    match bound {
+        Bound::Included(start) => v < start,
+        Bound::Excluded(start) => v <= start,
+        Bound::Unbounded => false
+    }
+}
+
+/// Checks if `v` does not lie after end of a range.
+pub(crate) fn before_end<T: PartialOrd>(bound: Bound<&T>, v: &T) -> bool {
+    match bound {
+        Bound::Included(end) => v <= end,
+        Bound::Excluded(end) => v < end,
+        Bound::Unbounded => true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BinarySearchTree;
+
+    #[test]
+    fn range() {
+        use std::ops::Bound::{Excluded, Unbounded};
+
+        let mut root = BinarySearchTree::new(50);
+        for v in [30, 70, 20, 40, 60, 80, 40, 65] {
+            root.insert(v);
+        }
+        let collect = |iter: crate::BinarySearchTreeIter<'_, i32>| iter.copied().collect::<Vec<_>>();
+        assert_eq!(collect(root.range(35..=65)), vec![40, 40, 50, 60, 65]);
+        assert_eq!(collect(root.range(40..60)), vec![40, 40, 50]);
+        assert_eq!(collect(root.range((Excluded(40), Unbounded))), vec![50, 60, 65, 70, 80]);
+        assert_eq!(collect(root.range(..)), root.inorder());
+        assert_eq!(collect(root.range(41..50)), vec![]);
+        assert_eq!(collect(root.range(90..)), vec![]);
+        assert_eq!(collect(root.range(..20)), vec![]);
+        assert_eq!(collect(root.range(20..=20)), vec![20]);
+        assert_eq!(collect(root.range((Excluded(80), Excluded(30)))), vec![]);
+        assert_eq!(root.range(35..=65).rev().copied().collect::<Vec<_>>(), vec![65, 60, 50, 40, 40]);
+        let mut iter = root.range(30..=70);
+        assert_eq!((iter.next(), iter.next_back()), (Some(&30), Some(&70)));
+        assert_eq!(iter.peek_prev(), Some(&65));
+        assert_eq!(iter.count(), 5);
+    }
+    #[test]
+    fn find_map_range() {
+        let root = BinarySearchTree::from((1..=20).collect());
+        let mut visited = Vec::new();
+        let found = root.find_map_range(5..15, |v| {
+            visited.push(v);
+            if v % 4 == 0 { Some(v * 10) } else { None }
+        });
+        assert_eq!(found, Some(80));
+        assert_eq!(visited, vec![5, 6, 7, 8]);
+        assert_eq!(root.find_map_range(9..=11, |v| (v % 4 == 0).then_some(v)), None);
+    }
+    #[test]
+    fn multi_range() {
+        let root = BinarySearchTree::from((0..100).map(|v| v * 2).collect());
+        let queries = [10..15, 0..3, 12..17, 150..150, 190..500];
+        let got = root.multi_range(&queries);
+
+        let mut expected = Vec::new();
+        for v in root.inorder() {
+            for (i, q) in queries.iter().enumerate() {
+                if q.contains(&v) {
+                    expected.push((i, v));
+                }
+            }
+        }
+        assert_eq!(got, expected);
+        assert_eq!(got[..4], [(1, 0), (1, 2), (0, 10), (0, 12)]);
+        assert!(root.multi_range(&[]).is_empty());
+    }
+    #[test]
+    fn bounds() {
+        use std::ops::Bound::{Excluded, Included, Unbounded};
+
+        let root = BinarySearchTree::from(vec![10, 20, 30, 40]);
+        assert_eq!(root.floor_bound(Included(30)), Some(30));
+        assert_eq!(root.floor_bound(Excluded(30)), Some(20));
+        assert_eq!(root.floor_bound(Excluded(10)), None);
+        assert_eq!(root.floor_bound(Unbounded), Some(40));
+        assert_eq!(root.ceiling_bound(Included(30)), Some(30));
+        assert_eq!(root.ceiling_bound(Excluded(30)), Some(40));
+        assert_eq!(root.ceiling_bound(Excluded(40)), None);
+        assert_eq!(root.ceiling_bound(Unbounded), Some(10));
+
+        assert_eq!(root.range_count(20..40), 2);
+        assert_eq!(root.range_count(20..=40), 3);
+        assert_eq!(root.range_count((Excluded(10), Excluded(40))), 2);
+        assert_eq!(root.range_count(..), 4);
+        assert_eq!(root.range_count(41..), 0);
+
+        let floats = BinarySearchTree::from(vec![0.5, 1.0, 1.5]);
+        assert_eq!(floats.range_count((Excluded(0.5), Excluded(1.5))), 1);
+    }
+    #[test]
+    fn group_by_ranges() {
+        let root = BinarySearchTree::from(vec![1, 5, 10, 10, 15, 20, 25, 99]);
+        assert_eq!(root.group_by_ranges(&[10, 20, 30]), vec![2, 3, 2, 1]);
+        assert_eq!(root.group_by_ranges(&[0, 0, 100]), vec![0, 0, 8, 0]);
+        assert_eq!(root.group_by_ranges(&[]), vec![8]);
+    }
+    #[test]
+    fn clone_range() {
+        let mut root = BinarySearchTree::new(1);
+        root.extend(2..=20);
+        let part = root.clone_range(5..12).unwrap();
+        assert_eq!(part.inorder(), (5..12).collect::<Vec<_>>());
+        assert_eq!(part.height(), 3);
+        assert_eq!(root.inorder().len(), 20);
+        assert!(root.clone_range(30..).is_none());
+    }
+}
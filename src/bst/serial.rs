@@ -0,0 +1,407 @@
+//! Rendering trees as text: layouts, Graphviz DOT, JSON and log lines.
+use std::cmp::{max, Ordering};
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::json::JsonValue;
+use super::{BinarySearchTree, Direction, Link};
+
+impl<T: PartialOrd + Copy> BinarySearchTree<T> {
+    /// Computes drawing coordinates with the Reingold–Tilford tidy tree
+    /// algorithm. Yields `(value, x, y)` inorder where `y` is depth and
+    /// `x` is a column starting at `0`. Parents are centered above their
+    /// children, siblings are at least two columns apart and mirrored
+    /// sub trees get mirrored layouts, so output is fully deterministic.
+    /// Uses `O(n * h)` time.
+    pub fn layout(&self) -> Vec<(T, usize, usize)> {
+        let mut halves = Vec::new();
+        self.layout_contours(&mut halves);
+
+        let mut ret = Vec::new();
+        let mut next = 0;
+        self.layout_place(0, 0, &halves, &mut next, &mut ret);
+        let min = ret.iter().map(|&(_, x, _)| x).min().unwrap_or(0);
+        ret.into_iter().map(|(v, x, y)| (v, (x - min) as usize, y)).collect()
+    }
+
+    /// Returns left and right contour of this sub tree relative to its
+    /// root, `halves` gets distance of children from each node in preorder.
+    fn layout_contours(&self, halves: &mut Vec<i64>) -> (Vec<i64>, Vec<i64>) {
+        let slot = halves.len();
+        halves.push(0);
+        let left = self.left.as_ref().map(|n| n.layout_contours(halves));
+        let right = self.right.as_ref().map(|n| n.layout_contours(halves));
+
+        let half = match (&left, &right) {
+            (Some((_, lr)), Some((rl, _))) => {
+                let overlap = lr.iter().zip(rl).map(|(a, b)| a - b).max().unwrap_or(0);
+                (overlap + 3) / 2
+            },
+            (None, None) => 0,
+            _ => 1
+        };
+        halves[slot] = half;
+
+        let mut contours = (vec![0], vec![0]);
+        let depth = max(left.as_ref().map_or(0, |c| c.0.len()), right.as_ref().map_or(0, |c| c.0.len()));
+        for i in 0..depth {
+            let l = left.as_ref().filter(|c| i < c.0.len()).map(|c| (c.0[i] - half, c.1[i] - half));
+            let r = right.as_ref().filter(|c| i < c.0.len()).map(|c| (c.0[i] + half, c.1[i] + half));
+            let (lo, hi) = match (l, r) {
+                (Some(a), Some(b)) => (a.0.min(b.0), a.1.max(b.1)),
+                (Some(a), None) | (None, Some(a)) => a,
+                (None, None) => unreachable!()
+            };
+            contours.0.push(lo);
+            contours.1.push(hi);
+        }
+        contours
+    }
+
+    fn layout_place(&self, x: i64, y: usize, halves: &[i64], next: &mut usize, ret: &mut Vec<(T, i64, usize)>) {
+        let half = halves[*next];
+        *next += 1;
+        if let Some(ref node) = self.left {
+            node.layout_place(x - half, y + 1, halves, next, ret);
+        }
+        ret.push((self.val, x, y));
+        if let Some(ref node) = self.right {
+            node.layout_place(x + half, y + 1, halves, next, ret);
+        }
+    }
+
+    /// Renders tree in Graphviz DOT format, `label` gives text of each
+    /// node from its value and depth, root is at depth `0`. So any `T`
+    /// can be drawn, e.g. `|v, d| format!("{:?}@{}", v, d)`.
+    /// Uses `O(n)` time.
+    pub fn to_dot_with<F>(&self, mut label: F) -> String
+        where
+            F: FnMut(&T, usize) -> String
+    {
+        let mut out = String::from("digraph {\n");
+        let mut stack = vec![(self, None, 0)];
+        let mut id = 0;
+
+        while let Some((node, parent, depth)) = stack.pop() {
+            let text = label(&node.val, depth).replace('\\', "\\\\").replace('"', "\\\"");
+            out.push_str(&format!("    n{} [label=\"{}\"];\n", id, text));
+            if let Some(p) = parent {
+                out.push_str(&format!("    n{} -> n{};\n", p, id));
+            }
+            for child in [&node.right, &node.left].into_iter().flatten() {
+                stack.push((child, Some(id), depth + 1));
+            }
+            id += 1;
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+impl<T: PartialOrd + Copy + fmt::Debug> BinarySearchTree<T> {
+    /// One line summary for logs and snapshot tests: sorted elements
+    /// followed by a hash of tree shape. Hash is 64 bit FNV-1a over
+    /// preorder child flags, so it is same on every platform and run.
+    /// Uses `O(n)` time.
+    pub fn debug_compact(&self) -> String {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        let mut stack = vec![self];
+        while let Some(node) = stack.pop() {
+            let flags = node.left.is_some() as u64 | (node.right.is_some() as u64) << 1;
+            hash = (hash ^ flags).wrapping_mul(0x0100_0000_01b3);
+            stack.extend(node.right.as_deref());
+            stack.extend(node.left.as_deref());
+        }
+        format!("{:?} #{:016x}", self.inorder(), hash)
+    }
+}
+
+impl<T: PartialOrd + Copy + fmt::Display> BinarySearchTree<T> {
+    /// Renders tree in Graphviz DOT format labelling nodes with values,
+    /// see `to_dot_with()` for custom labels.
+    /// Uses `O(n)` time.
+    pub fn to_dot(&self) -> String {
+        self.to_dot_with(|v, _| v.to_string())
+    }
+
+    /// Renders `new` in Graphviz DOT format coloured against `old`:
+    /// inserted nodes are green, nodes reached by a different path from
+    /// root than before are orange and removed nodes are drawn dashed red
+    /// next to the tree. Equal copies are paired in sorted order.
+    /// Uses `O(n * h)` time, `h` being height of the trees.
+    pub fn to_dot_diff(old: &Self, new: &Self) -> String {
+        let mut colors = HashMap::new();
+        let mut removed = Vec::new();
+        let mut ours = old.iter_with_path().peekable();
+        let mut theirs = new.iter_with_path().peekable();
+
+        loop {
+            let order = match (ours.peek(), theirs.peek()) {
+                (None, None) => break,
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (Some((a, _)), Some((b, _))) => a.partial_cmp(b).unwrap_or(Ordering::Less)
+            };
+            match order {
+                Ordering::Less => removed.extend(ours.next().map(|(v, _)| *v)),
+                Ordering::Greater => {
+                    let (_, path) = theirs.next().unwrap();
+                    colors.insert(path, "green");
+                },
+                Ordering::Equal => {
+                    let (_, before) = ours.next().unwrap();
+                    let (_, path) = theirs.next().unwrap();
+                    if before != path {
+                        colors.insert(path, "orange");
+                    }
+                }
+            }
+        }
+
+        let escape = |v: &T| v.to_string().replace('\\', "\\\\").replace('"', "\\\"");
+        let mut out = String::from("digraph {\n");
+        let mut stack = vec![(new, None, Vec::new())];
+        let mut id = 0;
+
+        while let Some((node, parent, path)) = stack.pop() {
+            let style = match colors.get(&path) {
+                Some(color) => format!(", color={}, fontcolor={}", color, color),
+                None => String::new()
+            };
+            out.push_str(&format!("    n{} [label=\"{}\"{}];\n", id, escape(&node.val), style));
+            if let Some(p) = parent {
+                out.push_str(&format!("    n{} -> n{};\n", p, id));
+            }
+            for (child, side) in [(&node.right, Direction::Right), (&node.left, Direction::Left)] {
+                if let Some(child) = child {
+                    let mut child_path = path.clone();
+                    child_path.push(side);
+                    stack.push((child, Some(id), child_path));
+                }
+            }
+            id += 1;
+        }
+        for (i, v) in removed.iter().enumerate() {
+            out.push_str(&format!("    r{} [label=\"{}\", color=red, fontcolor=red, style=dashed];\n", i, escape(v)));
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+impl<T: PartialOrd + JsonValue> BinarySearchTree<T> {
+    /// Renders tree as nested JSON objects `{"val":..,"left":..,"right":..}`
+    /// with `null` for a missing child, the recursive shape tree
+    /// visualizers consume. Values are written by `JsonValue`, so output
+    /// is valid JSON whatever they hold.
+    /// Uses `O(n)` time.
+    pub fn to_json_tree(&self) -> String {
+        fn write<T: JsonValue>(link: Option<&BinarySearchTree<T>>, out: &mut String) {
+            match link {
+                None => out.push_str("null"),
+                Some(node) => {
+                    out.push_str("{\"val\":");
+                    node.val.write_json(out);
+                    out.push_str(",\"left\":");
+                    write(node.left.as_deref(), out);
+                    out.push_str(",\"right\":");
+                    write(node.right.as_deref(), out);
+                    out.push('}');
+                }
+            }
+        }
+
+        let mut out = String::new();
+        write(Some(self), &mut out);
+        out
+    }
+
+    /// Reads a tree back from nested JSON written by `to_json_tree()`,
+    /// keeping its shape. Fields may come in any order and whitespace is
+    /// ignored. Returns `None` if input is malformed, the root is `null`
+    /// or nodes are not in search tree order.
+    /// Uses `O(n)` time.
+    pub fn from_json_tree(json: &str) -> Option<BinarySearchTree<T>> {
+        fn skip_ws(s: &mut &str) {
+            *s = s.trim_start();
+        }
+
+        fn eat(s: &mut &str, token: &str) -> bool {
+            skip_ws(s);
+            match s.strip_prefix(token) {
+                Some(rest) => { *s = rest; true },
+                None => false
+            }
+        }
+
+        fn literal<'a>(s: &mut &'a str) -> Option<&'a str> {
+            skip_ws(s);
+            let end = match s.strip_prefix('"') {
+                Some(quoted) => {
+                    let mut escaped = false;
+                    1 + quoted.find(|c| {
+                        let closes = c == '"' && !escaped;
+                        escaped = c == '\\' && !escaped;
+                        closes
+                    })? + 1
+                },
+                None => s.find([',', '}'])?
+            };
+            let (lit, rest) = s.split_at(end);
+            *s = rest;
+            Some(lit.trim_end())
+        }
+
+        fn node<T: PartialOrd + JsonValue>(s: &mut &str) -> Option<Link<T>> {
+            if eat(s, "null") {
+                return Some(None);
+            }
+            if !eat(s, "{") {
+                return None;
+            }
+            let (mut val, mut left, mut right) = (None, None, None);
+            loop {
+                if !eat(s, "\"") {
+                    return None;
+                }
+                let end = s.find('"')?;
+                let key = &s[..end];
+                *s = &s[end + 1..];
+                if !eat(s, ":") {
+                    return None;
+                }
+                match key {
+                    "val" => val = Some(T::read_json(literal(s)?)?),
+                    "left" => left = Some(node(s)?),
+                    "right" => right = Some(node(s)?),
+                    _ => return None
+                }
+                if eat(s, "}") {
+                    break;
+                }
+                if !eat(s, ",") {
+                    return None;
+                }
+            }
+            Some(Some(Box::new(BinarySearchTree {
+                val: val?,
+                left: left.unwrap_or(None),
+                right: right.unwrap_or(None)
+            })))
+        }
+
+        let mut rest = json;
+        let root = *node::<T>(&mut rest)??;
+        skip_ws(&mut rest);
+        if !rest.is_empty() || !root.iter().is_sorted() {
+            return None;
+        }
+        Some(root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BinarySearchTree;
+
+    #[test]
+    fn to_dot_diff() {
+        let old = BinarySearchTree::from(vec![2, 1, 3]);
+        let mut new = BinarySearchTree::new(3);
+        new.insert(1);
+        new.insert(4);
+
+        assert_eq!(BinarySearchTree::to_dot_diff(&old, &new), "digraph {\n\
+            \x20   n0 [label=\"3\", color=orange, fontcolor=orange];\n\
+            \x20   n1 [label=\"1\"];\n\
+            \x20   n0 -> n1;\n\
+            \x20   n2 [label=\"4\", color=green, fontcolor=green];\n\
+            \x20   n0 -> n2;\n\
+            \x20   r0 [label=\"2\", color=red, fontcolor=red, style=dashed];\n\
+            }\n");
+        assert_eq!(BinarySearchTree::to_dot_diff(&old, &old), old.to_dot());
+    }
+    #[test]
+    fn json_tree() {
+        let mut root = BinarySearchTree::new(5);
+        root.insert(3);
+        root.insert(8);
+        root.insert(4);
+        let json = root.to_json_tree();
+        assert_eq!(json, "{\"val\":5,\"left\":{\"val\":3,\"left\":null,\"right\":\
+            {\"val\":4,\"left\":null,\"right\":null}},\"right\":{\"val\":8,\"left\":null,\"right\":null}}");
+
+        let back = BinarySearchTree::<i32>::from_json_tree(&json).unwrap();
+        assert_eq!(back.to_json_tree(), json);
+        let spaced = BinarySearchTree::<f64>::from_json_tree(r#" { "left": null, "val": 1.5 ,
+            "right": { "val": 2, "right": null, "left": null } } "#).unwrap();
+        assert_eq!(spaced.inorder(), vec![1.5, 2.0]);
+
+        for bad in ["null", "{\"val\":1", "{\"val\":x}", "{\"val\":1} tail", "{\"left\":null}",
+                    "{\"val\":1,\"left\":{\"val\":2}}"] {
+            assert!(BinarySearchTree::<i32>::from_json_tree(bad).is_none(), "{}", bad);
+        }
+        assert!(BinarySearchTree::<f64>::from_json_tree("{\"val\":inf}").is_none());
+
+        // strings are quoted and escaped, so separators inside them are kept
+        let chars = BinarySearchTree::from(vec!['"', ',', '}', '\\']);
+        let json = chars.to_json_tree();
+        assert!(json.starts_with(r#"{"val":",","left":{"val":"\"","#));
+        assert!(json.contains(r#"{"val":"\\","left":null,"right":{"val":"}","#));
+        assert_eq!(BinarySearchTree::<char>::from_json_tree(&json).unwrap().inorder(), chars.inorder());
+
+        let floats = BinarySearchTree::from(vec![f64::NEG_INFINITY, 0.5, f64::INFINITY]);
+        let json = floats.to_json_tree();
+        assert!(json.contains(r#"{"val":"-inf","#) && json.contains(r#"{"val":"inf","#));
+        assert_eq!(BinarySearchTree::<f64>::from_json_tree(&json).unwrap().inorder(), floats.inorder());
+
+        let words = BinarySearchTree::from(vec![String::from("a \"b\""), String::from("c\n")]);
+        let back = BinarySearchTree::<String>::from_json_tree(&words.to_json_tree()).unwrap();
+        assert!(back.iter().eq(words.iter()));
+    }
+    #[test]
+    fn layout() {
+        let root = BinarySearchTree::from((1..=7).collect());
+        assert_eq!(root.layout(), vec![(1, 0, 2), (2, 1, 1), (3, 2, 2), (4, 3, 0), (5, 4, 2), (6, 5, 1), (7, 6, 2)]);
+
+        let mut root = BinarySearchTree::new(10);
+        for v in [5, 7, 8, 9, 20] {
+            root.insert(v);
+        }
+        let layout = root.layout();
+        assert_eq!(layout[0], (5, 0, 1));
+        assert_eq!(layout[4], (10, 1, 0));
+        assert_eq!(layout[3], (9, 3, 4));
+        assert_eq!(layout[5], (20, 2, 1));
+        assert_eq!(BinarySearchTree::new(1).layout(), vec![(1, 0, 0)]);
+    }
+    #[test]
+    fn dot() {
+        let mut root = BinarySearchTree::from(vec![1, 2, 3]);
+        root.insert(4);
+        assert_eq!(root.to_dot(), "digraph {\n\
+            \x20   n0 [label=\"2\"];\n\
+            \x20   n1 [label=\"1\"];\n\
+            \x20   n0 -> n1;\n\
+            \x20   n2 [label=\"3\"];\n\
+            \x20   n0 -> n2;\n\
+            \x20   n3 [label=\"4\"];\n\
+            \x20   n2 -> n3;\n\
+            }\n");
+
+        let dot = root.to_dot_with(|v, d| format!("{}@{}", v, d));
+        assert!(dot.contains("[label=\"4@2\"]"));
+        let quoted = root.to_dot_with(|_, _| String::from("\"x\""));
+        assert!(quoted.contains("[label=\"\\\"x\\\"\"]"));
+    }
+    #[test]
+    fn debug_compact() {
+        let root = BinarySearchTree::from(vec![1, 2, 3]);
+        assert_eq!(root.debug_compact(), "[1, 2, 3] #e1f68d1870f72e62");
+
+        let mut chain = BinarySearchTree::new(1);
+        chain.extend([2, 3]);
+        assert!(chain.debug_compact().starts_with("[1, 2, 3] #"));
+        assert_ne!(chain.debug_compact(), root.debug_compact());
+    }
+}
@@ -0,0 +1,93 @@
+//! Sizes of set operations between two trees.
+use std::ops::Bound;
+
+use super::ops::merge_sizes;
+use super::{BinarySearchTree, BinarySearchTreeIter};
+
+impl<T: PartialOrd> BinarySearchTree<T> {
+    /// Counts elements shared with `other`, a value stored several times
+    /// in both trees counts as often as in the tree holding fewer copies.
+    /// Only elements where value ranges of both trees overlap are walked
+    /// in step, sub trees outside of overlap are never entered.
+    /// Uses `O(h + k)` time for `k` elements of both trees within overlap.
+    pub fn intersection_size(&self, other: &BinarySearchTree<T>) -> usize {
+        match self.overlap(other) {
+            None => 0,
+            Some((lo, hi)) => {
+                let overlap = (Bound::Included(lo), Bound::Included(hi));
+                let (ours, _) = BinarySearchTreeIter::range(self, &overlap);
+                let (theirs, _) = BinarySearchTreeIter::range(other, &overlap);
+                merge_sizes(ours, theirs).0
+            }
+        }
+    }
+
+    /// Counts elements of this tree left after removing those shared
+    /// with `other`, see `intersection_size()`. Elements outside of value
+    /// range of `other` are only counted, paths to ends of overlap are
+    /// searched once and only overlap is walked in step with `other`.
+    /// Uses `O(h + k)` time for `k` elements counted or walked.
+    pub fn difference_size(&self, other: &BinarySearchTree<T>) -> usize {
+        match self.overlap(other) {
+            None => self.iter().count(),
+            Some((lo, hi)) => {
+                let overlap = (Bound::Included(lo), Bound::Included(hi));
+                let (below, _) = BinarySearchTreeIter::range(self, &(Bound::Unbounded, Bound::Excluded(lo)));
+                let (above, _) = BinarySearchTreeIter::range(self, &(Bound::Excluded(hi), Bound::Unbounded));
+                let (ours, _) = BinarySearchTreeIter::range(self, &overlap);
+                let (theirs, _) = BinarySearchTreeIter::range(other, &overlap);
+                below.count() + above.count() + merge_sizes(ours, theirs).1
+            }
+        }
+    }
+
+    /// Range of values within both value ranges, `None` if they are disjoint.
+    fn overlap<'a>(&'a self, other: &'a BinarySearchTree<T>) -> Option<(&'a T, &'a T)> {
+        let lo = if self.first() < other.first() { other.first() } else { self.first() };
+        let hi = if self.last() < other.last() { self.last() } else { other.last() };
+        if lo <= hi { Some((lo, hi)) } else { None }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BinarySearchTree;
+
+    #[test]
+    fn set_sizes() {
+        let a = BinarySearchTree::from(vec![1, 3, 3, 3, 5, 7, 9]);
+        let b = BinarySearchTree::from(vec![0, 3, 3, 4, 9, 10]);
+        assert_eq!(a.intersection_size(&b), 3);
+        assert_eq!(b.intersection_size(&a), 3);
+        assert_eq!(a.difference_size(&b), 4);
+        assert_eq!(b.difference_size(&a), 3);
+        assert_eq!(a.difference_size(&a), 0);
+
+        let far = BinarySearchTree::from(vec![20, 30]);
+        assert_eq!(a.intersection_size(&far), 0);
+        assert_eq!(a.difference_size(&far), 7);
+    }
+    #[test]
+    fn set_sizes_skip_outside_overlap() {
+        thread_local!(static COMPARED: std::cell::Cell<usize> = const { std::cell::Cell::new(0) });
+
+        #[derive(Clone, Copy, PartialEq)]
+        struct Probe(i32);
+        impl PartialOrd for Probe {
+            fn partial_cmp(&self, other: &Probe) -> Option<std::cmp::Ordering> {
+                COMPARED.with(|c| c.set(c.get() + 1));
+                self.0.partial_cmp(&other.0)
+            }
+        }
+
+        let a = BinarySearchTree::from((0..1024).map(Probe).collect());
+        let b = BinarySearchTree::from((1020..2048).map(Probe).collect());
+        COMPARED.with(|c| c.set(0));
+        assert_eq!(a.intersection_size(&b), 4);
+        assert_eq!(b.intersection_size(&a), 4);
+        // a full walk in step would compare over 2000 times
+        assert!(COMPARED.with(|c| c.get()) < 200);
+        assert_eq!(a.difference_size(&b), 1020);
+        assert_eq!(b.difference_size(&a), 1024);
+    }
+}
@@ -0,0 +1,143 @@
+//! Owned links between nodes and helpers which relink sub trees.
+use std::cmp::Ordering;
+use std::ops::RangeBounds;
+
+use super::{before_end, before_start, BinarySearchTree, Direction};
+
+pub(crate) type Link<T> = Option<Box<BinarySearchTree<T>>>;
+
+/// Splits sub tree into elements for which `goes_left` holds and the rest.
+/// `goes_left` has to hold for a prefix of in-order sequence.
+/// Uses `O(n)` time.
+pub(super) fn split_link<T, F>(link: Link<T>, goes_left: &F) -> (Link<T>, Link<T>)
+    where
+        F: Fn(&T) -> bool
+{
+    match link {
+        None => (None, None),
+        Some(mut node) => {
+            if goes_left(&node.val) {
+                let (left, right) = split_link(node.right.take(), goes_left);
+                node.right = left;
+                (Some(node), right)
+            } else {
+                let (left, right) = split_link(node.left.take(), goes_left);
+                node.left = right;
+                (left, Some(node))
+            }
+        }
+    }
+}
+
+/// Joins two sub trees where every element of `left` is not greater
+/// than any element of `right`. Minimum of `right` becomes root above
+/// both, so joined tree is at most one level taller than taller part.
+/// Uses `O(n)` time.
+pub(super) fn join_links<T>(left: Link<T>, mut right: Link<T>) -> Link<T> {
+    let mut pivot = match pop_min_link(&mut right) {
+        None => return left,
+        Some(p) => p
+    };
+    pivot.left = left;
+    pivot.right = right;
+    Some(pivot)
+}
+
+/// Removes elements within `range` for which `f` returns `false`.
+pub(super) fn retain_link<T, R, F>(link: Link<T>, range: &R, f: &mut F) -> Link<T>
+    where
+        T: PartialOrd,
+        R: RangeBounds<T>,
+        F: FnMut(&T) -> bool
+{
+    let mut node = link?;
+    if before_start(range.start_bound(), &node.val) {
+        node.right = retain_link(node.right.take(), range, f);
+        return Some(node);
+    }
+    if !before_end(range.end_bound(), &node.val) {
+        node.left = retain_link(node.left.take(), range, f);
+        return Some(node);
+    }
+
+    node.left = retain_link(node.left.take(), range, f);
+    let keep = f(&node.val);
+    node.right = retain_link(node.right.take(), range, f);
+    let mut link = Some(node);
+    if !keep {
+        unlink(&mut link);
+    }
+    link
+}
+
+/// Detaches first `k` nodes walking inorder from `side`, rest of
+/// each visited node is hung in its place.
+pub(super) fn take_k<T: Copy>(link: Link<T>, k: &mut usize, side: Direction, taken: &mut Vec<T>) -> Link<T> {
+    let mut node = link?;
+    if *k == 0 {
+        return Some(node);
+    }
+    let (near, far) = match side {
+        Direction::Left => (&mut node.left, &mut node.right),
+        Direction::Right => (&mut node.right, &mut node.left)
+    };
+    *near = take_k(near.take(), k, side, taken);
+    if *k == 0 {
+        return Some(node);
+    }
+    taken.push(node.val);
+    *k -= 1;
+    take_k(far.take(), k, side, taken)
+}
+
+/// Detaches minimum node of a sub tree, its right child takes its place.
+/// Uses `O(n)` time.
+fn pop_min_link<T>(link: &mut Link<T>) -> Link<T> {
+    let mut link = link;
+    while link.as_ref()?.left.is_some() {
+        link = &mut link.as_mut()?.left;
+    }
+    let mut min = link.take()?;
+    *link = min.right.take();
+    Some(min)
+}
+
+/// Unlinks node at `link` and returns its value. A node with two
+/// children is replaced by its in-order successor, so height of the
+/// tree does not grow as it would by joining children.
+/// Uses `O(n)` time.
+///
+/// # Panics
+/// Panics if `link` is empty.
+pub(super) fn unlink<T>(link: &mut Link<T>) -> T {
+    let BinarySearchTree { val, left, mut right } = *link.take().expect("Link is empty");
+    *link = match pop_min_link(&mut right) {
+        None => left,
+        Some(mut successor) => {
+            successor.left = left;
+            successor.right = right;
+            Some(successor)
+        }
+    };
+    val
+}
+
+/// Removes one copy of `val`, in-order successor of removed node
+/// takes its place. Nodes compared on the way are added to `visited`.
+/// Uses `O(n)` time.
+pub(super) fn remove_link<T: PartialOrd>(link: &mut Link<T>, val: &T, visited: &mut usize) -> bool {
+    let node = match link {
+        None => return false,
+        Some(n) => n
+    };
+    *visited += 1;
+    match node.val.partial_cmp(val) {
+        Some(Ordering::Greater) => remove_link(&mut node.left, val, visited),
+        Some(Ordering::Less) => remove_link(&mut node.right, val, visited),
+        Some(Ordering::Equal) => {
+            unlink(link);
+            true
+        },
+        None => false
+    }
+}
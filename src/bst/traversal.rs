@@ -0,0 +1,422 @@
+//! Iterators over a tree and conversions from and into iterators.
+use std::collections::VecDeque;
+use std::iter::{FusedIterator, Peekable};
+use std::ops::{Range, RangeBounds};
+use std::vec::IntoIter;
+
+use super::storage::Link;
+use super::{before_end, before_start, BinarySearchTree, Direction, NodeRef};
+
+/// Preorder iterator over all sub trees
+pub struct Subtrees<'a, T> {
+    pub(super) stack: Vec<&'a BinarySearchTree<T>>
+}
+
+impl<'a, T> Iterator for Subtrees<'a, T> {
+    type Item = NodeRef<'a, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        if let Some(ref right) = node.right {
+            self.stack.push(right);
+        }
+        if let Some(ref left) = node.left {
+            self.stack.push(left);
+        }
+        Some(NodeRef { node })
+    }
+}
+
+/// Iterator over elements in unspecified order
+pub struct Unordered<'a, T> {
+    pub(super) nodes: Subtrees<'a, T>
+}
+
+impl<'a, T> Iterator for Unordered<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.nodes.next().map(|node| node.value())
+    }
+}
+
+/// In-order iterator which also yields path to every node
+pub struct WithPath<'a, T> {
+    pub(super) stack: Vec<(&'a BinarySearchTree<T>, Vec<Direction>)>
+}
+
+impl<'a, T> WithPath<'a, T> {
+    pub(super) fn push_left(&mut self, mut node: &'a BinarySearchTree<T>, mut path: Vec<Direction>) {
+        while let Some(ref left) = node.left {
+            let mut left_path = path.clone();
+            left_path.push(Direction::Left);
+            self.stack.push((node, path));
+            node = left;
+            path = left_path;
+        }
+        self.stack.push((node, path));
+    }
+}
+
+impl<'a, T> Iterator for WithPath<'a, T> {
+    type Item = (&'a T, Vec<Direction>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (node, path) = self.stack.pop()?;
+        if let Some(ref right) = node.right {
+            let mut right_path = path.clone();
+            right_path.push(Direction::Right);
+            self.push_left(right, right_path);
+        }
+        Some((&node.val, path))
+    }
+}
+
+/// Iterator returned by `BinarySearchTree::iter_chunks()`.
+pub struct Chunks<'a, T> {
+    pub(super) iter: BinarySearchTreeIter<'a, T>,
+    pub(super) size: usize
+}
+
+impl<'a, T: Copy> Iterator for Chunks<'a, T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Vec<T>> {
+        let chunk: Vec<T> = self.iter.by_ref().take(self.size).copied().collect();
+        if chunk.is_empty() { None } else { Some(chunk) }
+    }
+}
+
+/// BinarySearchTreeIterator
+///
+/// Walks tree lazily in-order, holding only the left spine of the next
+/// node and the right spine of the previous one, so creating it takes
+/// `O(h)` time and no element is visited before it is asked for.
+pub struct BinarySearchTreeIter<'a, T> {
+    front: Vec<&'a BinarySearchTree<T>>,
+    back: Vec<&'a BinarySearchTree<T>>,
+    /// Last nodes yielded from each end, the walk is over once
+    /// one end reaches a node already yielded by the other.
+    last_front: Option<&'a BinarySearchTree<T>>,
+    last_back: Option<&'a BinarySearchTree<T>>
+}
+
+impl<'a, T> BinarySearchTreeIter<'a, T> {
+    /// Construct iterator based on input tree. By default
+    /// it uses in-order traversal for iterator.
+    pub(super) fn new(root: &'a BinarySearchTree<T>) -> Self {
+        let mut iter = BinarySearchTreeIter {
+            front: Vec::new(),
+            back: Vec::new(),
+            last_front: None,
+            last_back: None
+        };
+
+        iter.push_left(root);
+        iter.push_right(root);
+
+        iter
+    }
+
+    /// Construct iterator over elements within `range` only. Both
+    /// stacks are seeded by searching for ends of range, so sub trees
+    /// outside of it are never entered. Nodes just outside of range
+    /// count as already yielded, which stops either end there. Returns
+    /// number of nodes visited by both searches with it.
+    pub(super) fn range<R: RangeBounds<T>>(root: &'a BinarySearchTree<T>, range: &R) -> (Self, usize)
+        where
+            T: PartialOrd
+    {
+        let mut iter = BinarySearchTreeIter {
+            front: Vec::new(),
+            back: Vec::new(),
+            last_front: None,
+            last_back: None
+        };
+
+        let mut visited = 0;
+        let mut node = Some(root);
+        while let Some(n) = node {
+            visited += 1;
+            if before_start(range.start_bound(), &n.val) {
+                iter.last_front = Some(n);
+                node = n.right.as_deref();
+            } else {
+                iter.front.push(n);
+                node = n.left.as_deref();
+            }
+        }
+        let mut node = Some(root);
+        while let Some(n) = node {
+            visited += 1;
+            if before_end(range.end_bound(), &n.val) {
+                iter.back.push(n);
+                node = n.right.as_deref();
+            } else {
+                iter.last_back = Some(n);
+                node = n.left.as_deref();
+            }
+        }
+        // first element not before start lies after end when range is
+        // empty or reversed
+        if iter.front.last().is_none_or(|n| !before_end(range.end_bound(), &n.val)) {
+            iter.front.clear();
+            iter.back.clear();
+        }
+
+        (iter, visited)
+    }
+
+    fn push_left(&mut self, mut node: &'a BinarySearchTree<T>) {
+        self.front.push(node);
+        while let Some(ref left) = node.left {
+            node = left;
+            self.front.push(node);
+        }
+    }
+
+    fn push_right(&mut self, mut node: &'a BinarySearchTree<T>) {
+        self.back.push(node);
+        while let Some(ref right) = node.right {
+            node = right;
+            self.back.push(node);
+        }
+    }
+
+    /// Node which `next()` would yield.
+    fn front_node(&self) -> Option<&'a BinarySearchTree<T>> {
+        let node = *self.front.last()?;
+        match self.last_back {
+            Some(b) if std::ptr::eq(node, b) => None,
+            _ => Some(node)
+        }
+    }
+
+    /// Node which `next_back()` would yield.
+    fn back_node(&self) -> Option<&'a BinarySearchTree<T>> {
+        let node = *self.back.last()?;
+        match self.last_front {
+            Some(f) if std::ptr::eq(node, f) => None,
+            _ => Some(node)
+        }
+    }
+
+    /// Returns element which `next()` would yield without consuming it.
+    pub fn peek_next(&self) -> Option<&'a T> {
+        self.front_node().map(|node| &node.val)
+    }
+
+    /// Returns element which `next_back()` would yield without consuming it.
+    pub fn peek_prev(&self) -> Option<&'a T> {
+        self.back_node().map(|node| &node.val)
+    }
+}
+
+/// Implement iterator for BinarySearchTreeIter
+/// it pops next node and pushes left spine of its right sub tree.
+impl<'a, T> Iterator for BinarySearchTreeIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.front_node()?;
+        self.front.pop();
+        if let Some(ref right) = node.right {
+            self.push_left(right);
+        }
+        self.last_front = Some(node);
+        Some(&node.val)
+    }
+}
+
+/// Once nodes are exhausted `next()` keeps returning `None`.
+impl<'a, T> FusedIterator for BinarySearchTreeIter<'a, T> {}
+
+/// Cloning copies both stacks only, so both copies continue
+/// from same position without walking tree again.
+impl<'a, T> Clone for BinarySearchTreeIter<'a, T> {
+    fn clone(&self) -> Self {
+        BinarySearchTreeIter {
+            front: self.front.clone(),
+            back: self.back.clone(),
+            last_front: self.last_front,
+            last_back: self.last_back
+        }
+    }
+}
+
+/// Implement reverse iteration for BinarySearchTreeIter
+/// it mirrors `next()` on the right spine.
+impl<'a, T> DoubleEndedIterator for BinarySearchTreeIter<'a, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let node = self.back_node()?;
+        self.back.pop();
+        if let Some(ref left) = node.left {
+            self.push_right(left);
+        }
+        self.last_back = Some(node);
+        Some(&node.val)
+    }
+}
+
+/// Iterator returned by `BinarySearchTree::into_sorted_iter()`.
+///
+/// Holds owned sub trees whose in-order sequences follow each other.
+/// Taking from the front detaches left spine of first sub tree onto the
+/// front, taking from the back mirrors it with right spine of the last
+/// one, so every node is relinked at most twice.
+pub struct IntoSorted<T> {
+    parts: VecDeque<Box<BinarySearchTree<T>>>
+}
+
+impl<T> IntoSorted<T> {
+    pub(super) fn new(root: Link<T>) -> Self {
+        IntoSorted { parts: root.into_iter().collect() }
+    }
+}
+
+impl<T> Iterator for IntoSorted<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let mut node = self.parts.pop_front()?;
+        while let Some(left) = node.left.take() {
+            self.parts.push_front(node);
+            node = left;
+        }
+        if let Some(right) = node.right.take() {
+            self.parts.push_front(right);
+        }
+        Some(node.val)
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoSorted<T> {
+    fn next_back(&mut self) -> Option<T> {
+        let mut node = self.parts.pop_back()?;
+        while let Some(right) = node.right.take() {
+            self.parts.push_back(node);
+            node = right;
+        }
+        if let Some(left) = node.left.take() {
+            self.parts.push_back(left);
+        }
+        Some(node.val)
+    }
+}
+
+impl<T> FusedIterator for IntoSorted<T> {}
+
+/// State of `BinarySearchTree::multi_range()` sweeping over a tree.
+pub(super) struct MultiScan<'q, T> {
+    pub(super) queries: &'q [Range<T>],
+    /// Queries not started yet, in order of their start.
+    pub(super) pending: Peekable<IntoIter<usize>>,
+    /// Queries containing last visited element, in order of index.
+    pub(super) active: Vec<usize>,
+    pub(super) out: Vec<(usize, T)>
+}
+
+impl<'q, T: PartialOrd + Copy> MultiScan<'q, T> {
+    /// Walks sub tree inorder, left sub trees which end before
+    /// the next query starts are skipped.
+    pub(super) fn walk(&mut self, node: &BinarySearchTree<T>) {
+        let idle = self.active.is_empty();
+        if idle && self.pending.peek().is_none() {
+            return;
+        }
+        let queries = self.queries;
+        let skip_left = idle && self.pending.peek().is_some_and(|&i| queries[i].start > node.val);
+        if let (false, Some(ref left)) = (skip_left, &node.left) {
+            self.walk(left);
+        }
+        self.visit(node.val);
+        if let Some(ref right) = node.right {
+            self.walk(right);
+        }
+    }
+
+    fn visit(&mut self, v: T) {
+        let queries = self.queries;
+        while let Some(i) = self.pending.next_if(|&i| queries[i].start <= v) {
+            let pos = self.active.partition_point(|&a| a < i);
+            self.active.insert(pos, i);
+        }
+        self.active.retain(|&i| queries[i].end > v);
+        self.out.extend(self.active.iter().map(|&i| (i, v)));
+    }
+}
+
+/// Builds a balanced tree from iterator, same as `BinarySearchTree::from()`.
+///
+/// # Panics
+/// Panics if iterator is empty.
+impl<T> FromIterator<T> for BinarySearchTree<T>
+    where
+        T: PartialOrd,
+{
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        BinarySearchTree::from(iter.into_iter().collect())
+    }
+}
+
+/// Builds a balanced tree from iterator of references.
+///
+/// # Panics
+/// Panics if iterator is empty.
+impl<'a, T> FromIterator<&'a T> for BinarySearchTree<T>
+    where
+        T: 'a + PartialOrd + Copy,
+{
+    fn from_iter<I: IntoIterator<Item = &'a T>>(iter: I) -> Self {
+        iter.into_iter().copied().collect()
+    }
+}
+
+/// Inserts every element of iterator, so a tree can be the
+/// target of `Iterator::collect_into()`.
+impl<T> Extend<T> for BinarySearchTree<T>
+    where
+        T: PartialOrd,
+{
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for val in iter {
+            self.insert(val);
+        }
+    }
+}
+
+/// Inserts a copy of every element of iterator.
+impl<'a, T> Extend<&'a T> for BinarySearchTree<T>
+    where
+        T: 'a + PartialOrd + Copy,
+{
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied());
+    }
+}
+
+/// implement consumable IntoIterator for BinarySearchTree, elements
+/// are popped lazily from either end as in `into_sorted_iter()`.
+impl<T> IntoIterator for BinarySearchTree<T>
+    where
+        T: PartialOrd,
+{
+    type Item = T;
+    type IntoIter = IntoSorted<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.into_sorted_iter()
+    }
+}
+
+/// Implement non-consumable IntoIterator for BinarySearchTree
+impl<'a, T> IntoIterator for &'a BinarySearchTree<T>
+    where
+        T: PartialOrd {
+    type Item = &'a T;
+    type IntoIter = BinarySearchTreeIter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        BinarySearchTreeIter::new(self)
+    }
+}
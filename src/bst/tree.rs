@@ -0,0 +1,470 @@
+//! Tree handle which can be empty.
+use std::cell::Cell;
+use std::cmp::Ordering;
+use std::ops::RangeBounds;
+use std::sync::Arc;
+
+use crate::collate::{Collated, Collation};
+use crate::observe::{OpKind, Timer};
+use super::storage::{join_links, remove_link, retain_link, split_link, Link};
+use super::{before_end, before_start, BinarySearchTree, Direction, IntoSorted};
+
+/// Handle owning an optional `BinarySearchTree` root, so an empty tree
+/// is an ordinary state. Constructors never panic on empty input and
+/// removing last element leaves an empty tree behind. Number of
+/// elements is tracked, so `len()` does not walk the tree.
+///
+/// # Example
+///
+/// ```rust
+/// use ds_bst::Tree;
+///
+/// let mut tree = Tree::from(Vec::new());
+/// assert!(tree.is_empty());
+/// tree.insert(3);
+/// assert_eq!(tree.len(), 1);
+/// assert!(tree.remove(&3));
+/// assert!(tree.is_empty());
+/// ```
+pub struct Tree<T> {
+    root: Link<T>,
+    len: usize
+}
+
+impl<T: PartialOrd> Tree<T> {
+    /// Contructor creates an empty tree.
+    pub fn new() -> Tree<T> {
+        Tree { root: None, len: 0 }
+    }
+
+    /// Number of elements, every copy of a duplicate counts.
+    /// Uses `O(1)` time.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Checks if tree has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    /// Drops every element and leaves an empty tree to be reused. Nodes
+    /// are freed with an explicit stack, so a degenerate tree does not
+    /// overflow the call stack.
+    /// Uses `O(n)` time.
+    pub fn clear(&mut self) {
+        let mut stack: Vec<Box<BinarySearchTree<T>>> = self.root.take().into_iter().collect();
+        while let Some(mut node) = stack.pop() {
+            stack.extend(node.left.take());
+            stack.extend(node.right.take());
+        }
+        self.len = 0;
+    }
+
+    /// Root node, `None` for an empty tree.
+    pub fn root(&self) -> Option<&BinarySearchTree<T>> {
+        self.root.as_deref()
+    }
+
+    /// Moves contents out into a new tree and leaves this one empty,
+    /// like `std::mem::take()`.
+    /// Uses `O(1)` time.
+    pub fn take(&mut self) -> Tree<T> {
+        Tree { root: self.root.take(), len: std::mem::take(&mut self.len) }
+    }
+
+    /// Exchanges contents of two trees without moving any nodes.
+    /// Uses `O(1)` time.
+    pub fn swap_contents(&mut self, other: &mut Tree<T>) {
+        std::mem::swap(self, other);
+    }
+
+    /// Runs `f` on root node for mutation through the tree's own
+    /// methods, `None` for an empty tree. Elements are counted again
+    /// afterwards, since `f` may insert or remove any number of them.
+    /// Uses `O(n)` time besides `f`.
+    pub fn with_root_mut<R, F>(&mut self, f: F) -> Option<R>
+        where
+            F: FnOnce(&mut BinarySearchTree<T>) -> R
+    {
+        let root = self.root.as_deref_mut()?;
+        let ret = f(root);
+        self.len = root.iter().count();
+        Some(ret)
+    }
+
+    /// Consumes handle into its root node.
+    pub fn into_root(self) -> Option<BinarySearchTree<T>> {
+        self.root.map(|r| *r)
+    }
+
+    /// Inserts an element in a tree.
+    /// Uses `O(n)` time.
+    pub fn insert(&mut self, val: T) {
+        match self.root {
+            None => {
+                let timer = Timer::start();
+                self.root = Some(Box::new(BinarySearchTree::new(val)));
+                timer.finish(OpKind::Insert, 0);
+            },
+            Some(ref mut root) => root.insert(val)
+        }
+        self.len += 1;
+    }
+
+    /// Removes one copy of `val`, returns `false` if it does not exist.
+    /// Uses `O(n)` time.
+    pub fn remove(&mut self, val: &T) -> bool {
+        let timer = Timer::start();
+        let mut depth = 0;
+        let removed = remove_link(&mut self.root, val, &mut depth);
+        timer.finish(OpKind::Remove, depth);
+        self.len -= removed as usize;
+        removed
+    }
+
+    /// Keeps only elements within `range` for which `f` returns `true`,
+    /// rejecting every element leaves an empty tree. Sub trees entirely
+    /// outside of `range` are never visited.
+    /// Uses `O(n)` time.
+    pub fn retain_range<R, F>(&mut self, range: R, mut f: F)
+        where
+            R: RangeBounds<T>,
+            F: FnMut(&T) -> bool
+    {
+        let mut removed = 0;
+        let mut keep = |v: &T| {
+            let kept = f(v);
+            removed += !kept as usize;
+            kept
+        };
+        self.root = retain_link(self.root.take(), &range, &mut keep);
+        self.len -= removed;
+    }
+
+    /// Removes all elements within `range` and returns them as their
+    /// own tree. Highest node within `range` is found first, every other
+    /// element of `range` lies below it. Its sub trees are split at ends
+    /// of `range` and outer parts are joined back in its place, so
+    /// elements are not moved one by one and height never grows. A
+    /// `range` covering every element, such as `..`, leaves tree empty.
+    /// Uses `O(n)` time, besides `O(k)` to count `k` popped elements.
+    pub fn pop_range<R: RangeBounds<T>>(&mut self, range: R) -> Tree<T> {
+        let timer = Timer::start();
+        let visited = Cell::new(0);
+        let mut slot = &mut self.root;
+        loop {
+            let side = match slot.as_deref() {
+                None => {
+                    timer.finish(OpKind::PopRange, visited.get());
+                    return Tree::new();
+                },
+                Some(node) if before_start(range.start_bound(), &node.val) => Direction::Right,
+                Some(node) if !before_end(range.end_bound(), &node.val) => Direction::Left,
+                Some(_) => break
+            };
+            visited.set(visited.get() + 1);
+            let node = slot.as_mut().unwrap();
+            slot = match side {
+                Direction::Left => &mut node.left,
+                Direction::Right => &mut node.right
+            };
+        }
+        let mut top = slot.take().unwrap();
+
+        let visit = |goes_left: bool| {
+            visited.set(visited.get() + 1);
+            goes_left
+        };
+        let (before, low) = split_link(top.left.take(), &|v| visit(before_start(range.start_bound(), v)));
+        let (high, after) = split_link(top.right.take(), &|v| visit(before_end(range.end_bound(), v)));
+        *slot = join_links(before, after);
+        top.left = low;
+        top.right = high;
+        timer.finish(OpKind::PopRange, visited.get() + 1);
+
+        let popped = top.iter().count();
+        self.len -= popped;
+        Tree { root: Some(top), len: popped }
+    }
+
+    /// Checks if element exists in a tree.
+    /// Uses `O(n)` time.
+    pub fn contains(&self, val: &T) -> bool {
+        self.root().is_some_and(|r| r.contains(val))
+    }
+
+    /// Returns reference to smallest element.
+    /// Uses `O(n)` time.
+    pub fn first(&self) -> Option<&T> {
+        self.root().map(|r| r.first())
+    }
+
+    /// Returns reference to largest element.
+    /// Uses `O(n)` time.
+    pub fn last(&self) -> Option<&T> {
+        self.root().map(|r| r.last())
+    }
+
+    /// Iterates elements in sorted order.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &T> + '_ {
+        self.root().into_iter().flat_map(|r| r.iter())
+    }
+}
+
+impl<T: PartialOrd + Copy> Tree<T> {
+    /// Inorder traverse tree which yields elements in sorted order.
+    /// Uses `O(n)` time.
+    pub fn inorder(&self) -> Vec<T> {
+        self.root().map_or_else(Vec::new, |r| r.inorder())
+    }
+
+    /// Counts elements within `range`.
+    /// Uses `O(n)` time.
+    pub fn range_count<R: RangeBounds<T>>(&self, range: R) -> usize {
+        self.root().map_or(0, |r| r.range_count(range))
+    }
+}
+
+/// Ordered string interner, every distinct string is stored once.
+impl Tree<Arc<str>> {
+    /// Returns canonical copy of `s`, storing it first if it is new.
+    /// Copies are shared, so the string lives on even if tree drops it.
+    /// Uses `O(n)` time.
+    pub fn get_or_insert(&mut self, s: &str) -> Arc<str> {
+        if let Some(found) = self.get_interned(s) {
+            return Arc::clone(found);
+        }
+        let interned: Arc<str> = Arc::from(s);
+        self.insert(Arc::clone(&interned));
+        interned
+    }
+
+    /// Returns canonical copy of `s` if it is stored, looking it up
+    /// by `&str` so no `Arc` is allocated.
+    /// Uses `O(n)` time.
+    pub fn get_interned(&self, s: &str) -> Option<&Arc<str>> {
+        let mut current = self.root();
+        while let Some(node) = current {
+            current = match (*node.val).cmp(s) {
+                Ordering::Equal => return Some(&node.val),
+                Ordering::Greater => node.left.as_deref(),
+                Ordering::Less => node.right.as_deref()
+            };
+        }
+        None
+    }
+}
+
+/// Tree of strings ordered by a `Collation` preset.
+impl<C: Collation> Tree<Collated<C>> {
+    /// Contructor creates an empty tree of strings ordered by given collation,
+    /// e.g. `Tree::with_collation(CaseFold)`, which wraps keys itself.
+    pub fn with_collation(_: C) -> Tree<Collated<C>> {
+        Tree::new()
+    }
+
+    /// Inserts a copy of `s`.
+    /// Uses `O(n)` time.
+    pub fn insert_str(&mut self, s: &str) {
+        self.insert(Collated::new(s));
+    }
+
+    /// Returns stored key equal to `s` under the collation, looking it
+    /// up by `&str` so no key is allocated.
+    /// Uses `O(n)` time.
+    pub fn get_str(&self, s: &str) -> Option<&Collated<C>> {
+        let mut current = self.root();
+        while let Some(node) = current {
+            current = match C::compare(&node.val, s) {
+                Ordering::Equal => return Some(&node.val),
+                Ordering::Greater => node.left.as_deref(),
+                Ordering::Less => node.right.as_deref()
+            };
+        }
+        None
+    }
+
+    /// Checks if a key equal to `s` under the collation is stored.
+    /// Uses `O(n)` time.
+    pub fn contains_str(&self, s: &str) -> bool {
+        self.get_str(s).is_some()
+    }
+}
+
+impl<T: PartialOrd> Default for Tree<T> {
+    fn default() -> Self {
+        Tree::new()
+    }
+}
+
+/// Builds a balanced tree, empty vector gives an empty tree.
+impl<T: PartialOrd> From<Vec<T>> for Tree<T> {
+    fn from(data: Vec<T>) -> Self {
+        if data.is_empty() {
+            Tree::new()
+        } else {
+            Tree::from(BinarySearchTree::from(data))
+        }
+    }
+}
+
+/// Wraps a root node, its elements are counted once.
+/// Uses `O(n)` time.
+impl<T: PartialOrd> From<BinarySearchTree<T>> for Tree<T> {
+    fn from(root: BinarySearchTree<T>) -> Self {
+        let len = root.iter().count();
+        Tree { root: Some(Box::new(root)), len }
+    }
+}
+
+impl<T: PartialOrd> FromIterator<T> for Tree<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Tree::from(iter.into_iter().collect::<Vec<T>>())
+    }
+}
+
+/// Consumes tree into an iterator popping elements lazily from either end.
+impl<T: PartialOrd> IntoIterator for Tree<T> {
+    type Item = T;
+    type IntoIter = IntoSorted<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoSorted::new(self.root)
+    }
+}
+
+impl<T: PartialOrd> Extend<T> for Tree<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for val in iter {
+            self.insert(val);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{BinarySearchTree, Tree};
+
+    #[test]
+    fn empty_states() {
+        let mut tree: Tree<i32> = Tree::new();
+        assert!(tree.is_empty());
+        assert_eq!(tree.first(), None);
+        assert_eq!(tree.iter().count(), 0);
+        assert!(!tree.remove(&1));
+
+        tree.extend([5, 3, 8, 3]);
+        assert_eq!(tree.len(), 4);
+        assert_eq!(tree.inorder(), vec![3, 3, 5, 8]);
+        assert_eq!(tree.range_count(3..6), 3);
+        for v in [5, 3, 8, 3] {
+            assert!(tree.remove(&v));
+        }
+        assert!(!tree.remove(&3));
+        assert_eq!(tree.len(), 0);
+        assert!(tree.is_empty());
+        assert!(tree.into_root().is_none());
+
+        let tree: Tree<i32> = (1..=7).collect();
+        assert_eq!(tree.len(), 7);
+        assert_eq!(tree.root().map(|r| r.height()), Some(3));
+        assert_eq!(tree.iter().next_back(), Some(&7));
+        assert!(tree.into_iter().rev().eq((1..=7).rev()));
+        assert!(Tree::<i32>::from(Vec::new()).is_empty());
+    }
+    #[test]
+    fn clear() {
+        let mut tree = Tree::new();
+        tree.extend(0..5000);
+        assert_eq!(tree.len(), 5000);
+        tree.clear();
+        assert!(tree.is_empty());
+        assert_eq!(tree.len(), 0);
+        tree.insert(7);
+        assert_eq!(tree.inorder(), vec![7]);
+    }
+    #[test]
+    fn take() {
+        let mut front: Tree<i32> = (1..=3).collect();
+        let mut back = front.take();
+        assert!(front.is_empty());
+        assert_eq!(back.len(), 3);
+        front.insert(10);
+        front.swap_contents(&mut back);
+        assert_eq!(front.inorder(), vec![1, 2, 3]);
+        assert_eq!((back.len(), back.inorder()), (1, vec![10]));
+    }
+    #[test]
+    fn with_root_mut() {
+        let mut tree = Tree::from(BinarySearchTree::from(vec![1, 2, 3]));
+        assert_eq!(tree.with_root_mut(|root| root.insert(4)), Some(()));
+        assert_eq!(tree.len(), 4);
+        assert_eq!(tree.with_root_mut(|root| root.remove_max_k(2)), Some(vec![4, 3]));
+        assert_eq!(tree.len(), 2);
+        assert!(Tree::<i32>::new().with_root_mut(|root| root.insert(1)).is_none());
+    }
+    #[test]
+    fn interning() {
+        let mut names: Tree<Arc<str>> = Tree::new();
+        let a = names.get_or_insert("ada");
+        let b = names.get_or_insert("bob");
+        let again = names.get_or_insert("ada");
+        assert!(Arc::ptr_eq(&a, &again));
+        assert!(!Arc::ptr_eq(&a, &b));
+        assert_eq!(names.len(), 2);
+        assert_eq!(names.get_interned("bob").map(|s| &**s), Some("bob"));
+        assert!(names.get_interned("eve").is_none());
+        assert_eq!(names.iter().map(|s| &**s).collect::<Vec<_>>(), ["ada", "bob"]);
+    }
+    #[test]
+    fn retain_range() {
+        let mut tree: Tree<i32> = (1..=10).collect();
+        tree.retain_range(3..8, |v| v % 2 == 0);
+        assert_eq!(tree.inorder(), vec![1, 2, 4, 6, 8, 9, 10]);
+        assert_eq!(tree.len(), 7);
+        tree.retain_range(.., |_| false);
+        assert!(tree.is_empty());
+        assert_eq!(tree.len(), 0);
+    }
+    #[test]
+    fn pop_range() {
+        let mut tree: Tree<i32> = (1..=10).collect();
+        let popped = tree.pop_range(3..7);
+        assert_eq!((popped.len(), popped.inorder()), (4, vec![3, 4, 5, 6]));
+        assert_eq!((tree.len(), tree.inorder()), (6, vec![1, 2, 7, 8, 9, 10]));
+
+        assert!(tree.pop_range(3..=6).is_empty());
+        assert_eq!(tree.pop_range(9..).inorder(), vec![9, 10]);
+        assert_eq!(tree.pop_range(..=1).inorder(), vec![1]);
+        assert_eq!(tree.inorder(), vec![2, 7, 8]);
+
+        let popped = tree.pop_range(..);
+        assert_eq!(popped.inorder(), vec![2, 7, 8]);
+        assert!(tree.is_empty());
+        assert_eq!(tree.len(), 0);
+        assert!(tree.pop_range(..).is_empty());
+    }
+    #[test]
+    fn pop_range_keeps_height() {
+        let mut tree: Tree<i32> = (1..=1023).collect();
+        for start in (0..1000).step_by(40) {
+            assert_eq!(tree.pop_range(start..start + 5).len(), if start == 0 { 4 } else { 5 });
+        }
+        assert_eq!(tree.len(), 1023 - 124);
+        assert!(tree.root().is_some_and(|r| r.height() <= 10));
+    }
+    #[test]
+    fn remove_keeps_height() {
+        let mut tree: Tree<i32> = (1..=127).collect();
+        // every removed element sits at root with two children
+        for v in 64..74 {
+            assert_eq!(tree.root().map(|r| r.val), Some(v));
+            assert!(tree.remove(&v));
+        }
+        assert_eq!(tree.root().map(|r| r.height()), Some(7));
+        assert!(tree.iter().copied().eq((1..64).chain(74..=127)));
+    }
+}
@@ -0,0 +1,118 @@
+/// Encoding of keys to bytes for serialized formats such as
+/// `FrozenBst::to_bytes()`.
+///
+/// Encodings have to preserve order: comparing two encodings byte by
+/// byte gives the same result as comparing values, so data stays
+/// sorted on disk and can be checked without decoding.
+///
+/// Integers are stored big endian with sign bit flipped, floats in
+/// total order (negative `NaN` first, positive `NaN` last) and strings
+/// as their UTF-8 bytes.
+pub trait KeyCodec: Sized {
+    /// Appends encoding of value to `out`.
+    fn encode(&self, out: &mut Vec<u8>);
+
+    /// Decodes value from whole of `bytes`, `None` if they are malformed.
+    fn decode(bytes: &[u8]) -> Option<Self>;
+}
+
+macro_rules! unsigned_codec {
+    ($($t:ty),*) => {$(
+        impl KeyCodec for $t {
+            fn encode(&self, out: &mut Vec<u8>) {
+                out.extend_from_slice(&self.to_be_bytes());
+            }
+
+            fn decode(bytes: &[u8]) -> Option<Self> {
+                Some(<$t>::from_be_bytes(bytes.try_into().ok()?))
+            }
+        }
+    )*};
+}
+
+macro_rules! signed_codec {
+    ($($t:ty => $u:ty),*) => {$(
+        impl KeyCodec for $t {
+            fn encode(&self, out: &mut Vec<u8>) {
+                ((*self as $u) ^ (1 << (<$u>::BITS - 1))).encode(out);
+            }
+
+            fn decode(bytes: &[u8]) -> Option<Self> {
+                Some((<$u>::decode(bytes)? ^ (1 << (<$u>::BITS - 1))) as $t)
+            }
+        }
+    )*};
+}
+
+macro_rules! float_codec {
+    ($($t:ty => $u:ty),*) => {$(
+        impl KeyCodec for $t {
+            fn encode(&self, out: &mut Vec<u8>) {
+                let bits = self.to_bits();
+                let sign = 1 << (<$u>::BITS - 1);
+                let key = if bits & sign == 0 { bits ^ sign } else { !bits };
+                key.encode(out);
+            }
+
+            fn decode(bytes: &[u8]) -> Option<Self> {
+                let key = <$u>::decode(bytes)?;
+                let sign = 1 << (<$u>::BITS - 1);
+                let bits = if key & sign != 0 { key ^ sign } else { !key };
+                Some(<$t>::from_bits(bits))
+            }
+        }
+    )*};
+}
+
+unsigned_codec!(u8, u16, u32, u64, u128);
+signed_codec!(i8 => u8, i16 => u16, i32 => u32, i64 => u64, i128 => u128);
+float_codec!(f32 => u32, f64 => u64);
+
+impl KeyCodec for String {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(self.as_bytes());
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        String::from_utf8(bytes.to_vec()).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::KeyCodec;
+
+    fn bytes<T: KeyCodec>(v: T) -> Vec<u8> {
+        let mut out = Vec::new();
+        v.encode(&mut out);
+        out
+    }
+
+    fn check_order<T: KeyCodec + PartialOrd + Copy + std::fmt::Debug>(sorted: &[T]) {
+        for w in sorted.windows(2) {
+            assert!(bytes(w[0]) < bytes(w[1]), "{:?} < {:?}", w[0], w[1]);
+        }
+        for &v in sorted {
+            assert_eq!(T::decode(&bytes(v)), Some(v));
+        }
+    }
+
+    #[test]
+    fn order_preserved() {
+        check_order(&[0u8, 1, 127, 255]);
+        check_order(&[0u64, 1, 256, u64::MAX]);
+        check_order(&[i32::MIN, -70_000, -1, 0, 1, 70_000, i32::MAX]);
+        check_order(&[i128::MIN, -1, 0, i128::MAX]);
+        check_order(&[f64::NEG_INFINITY, -1e300, -1.5, -0.0, 0.0, 1e-300, 2.5, f64::INFINITY]);
+        check_order(&[f32::MIN, -1.0, 0.5, f32::MAX]);
+
+        let words = ["", "a", "ab", "b", "ž"];
+        for w in words.windows(2) {
+            assert!(bytes(w[0].to_string()) < bytes(w[1].to_string()));
+        }
+        assert_eq!(String::decode(&bytes(String::from("ž"))).as_deref(), Some("ž"));
+        assert_eq!(String::decode(&[0xff]), None);
+        assert_eq!(u32::decode(&[1, 2]), None);
+        assert!(f64::decode(&bytes(f64::NAN)).unwrap().is_nan());
+    }
+}
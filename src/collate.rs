@@ -0,0 +1,295 @@
+//! Ready-made orderings for string keys.
+//!
+//! Trees order elements through `PartialOrd` alone, so an ordering other
+//! than the byte order of `str` is chosen by wrapping keys in one of these
+//! newtypes. Equality follows the same ordering, so lookups find keys
+//! which differ only in case.
+//!
+//! A `Collation` preset given to `Tree::with_collation()` or
+//! `BstMap::with_collation()` does the wrapping instead, keys are then
+//! inserted and looked up by `&str`. With feature `unicode` there is also
+//! `UnicodeCollation`, a multi-level order for Latin script.
+//!
+//! # Example
+//!
+//! ```rust
+//! use ds_bst::{AsciiCaseFold, AsciiCaseInsensitive, BinarySearchTree, Tree};
+//!
+//! let words = vec!["banana", "Apple", "cherry"];
+//! let root = BinarySearchTree::from(words.into_iter().map(AsciiCaseInsensitive).collect());
+//!
+//! assert!(root.contains(&AsciiCaseInsensitive("APPLE")));
+//! assert_eq!(root.first().0, "Apple");
+//!
+//! let mut tree = Tree::with_collation(AsciiCaseFold);
+//! tree.insert_str("banana");
+//! tree.insert_str("Apple");
+//! assert!(tree.contains_str("APPLE"));
+//! assert_eq!(tree.first().map(|w| w.as_str()), Some("Apple"));
+//! ```
+use std::cmp::Ordering;
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::Deref;
+
+/// Ordering of strings, chosen by type so keys need not carry it.
+pub trait Collation {
+    /// Compares `a` with `b`, strings are equal keys when it returns
+    /// `Ordering::Equal`.
+    fn compare(a: &str, b: &str) -> Ordering;
+}
+
+/// Preset ignoring case of ASCII letters, other characters compare by
+/// their bytes, as `AsciiCaseInsensitive` does.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AsciiCaseFold;
+
+/// Preset comparing Unicode lowercase forms, as `CaseInsensitive` does.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CaseFold;
+
+impl Collation for AsciiCaseFold {
+    fn compare(a: &str, b: &str) -> Ordering {
+        a.bytes().map(|b| b.to_ascii_lowercase()).cmp(b.bytes().map(|b| b.to_ascii_lowercase()))
+    }
+}
+
+impl Collation for CaseFold {
+    fn compare(a: &str, b: &str) -> Ordering {
+        a.chars().flat_map(char::to_lowercase).cmp(b.chars().flat_map(char::to_lowercase))
+    }
+}
+
+/// String key ordered by collation `C`, made by trees and maps from
+/// `with_collation()` so callers pass plain `&str`.
+pub struct Collated<C> {
+    s: String,
+    collation: PhantomData<C>
+}
+
+impl<C> Collated<C> {
+    /// Contructor creates a key holding a copy of `s`.
+    pub fn new(s: &str) -> Collated<C> {
+        Collated { s: String::from(s), collation: PhantomData }
+    }
+
+    /// String as it was inserted.
+    pub fn as_str(&self) -> &str {
+        &self.s
+    }
+}
+
+impl<C: Collation> Ord for Collated<C> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        C::compare(&self.s, &other.s)
+    }
+}
+
+impl<C: Collation> PartialOrd for Collated<C> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<C: Collation> PartialEq for Collated<C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<C: Collation> Eq for Collated<C> {}
+
+impl<C> Deref for Collated<C> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.s
+    }
+}
+
+impl<C> Clone for Collated<C> {
+    fn clone(&self) -> Self {
+        Collated { s: self.s.clone(), collation: PhantomData }
+    }
+}
+
+impl<C> fmt::Debug for Collated<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.s.fmt(f)
+    }
+}
+
+impl<C> fmt::Display for Collated<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.s.fmt(f)
+    }
+}
+
+/// Preset ordering Latin script the way dictionaries do, level by level.
+/// Letters compare first by base letter ignoring accents and case, with
+/// `ß`, `æ`, `œ` and `ĳ` expanded to two letters. Ties are broken by
+/// accents, unaccented letters first, then by case, lowercase first, and
+/// last by bytes, so only identical strings are equal. Accents are known
+/// for Latin-1 Supplement and Latin Extended-A, other characters compare
+/// by their lowercase form.
+#[cfg(feature = "unicode")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UnicodeCollation;
+
+/// Base letters of U+00C0 to U+017F, `.` for characters kept as they are
+/// and `*` for ones expanded to two letters.
+#[cfg(feature = "unicode")]
+const LATIN_BASE: &[u8; 192] = b"AAAAAA*CEEEEIIIIDNOOOOO.OUUUUY.*aaaaaa*ceeeeiiiidnooooo.ouuuuy.y\
+    AaAaAaCcCcCcCcDdDdEeEeEeEeEeGgGgGgGgHhHhIiIiIiIiIi**JjKkkLlLlLlLlLlNnNnNnnNn\
+    OoOoOo**RrRrRrSsSsSsSsTtTtTtUuUuUuUuUuUuWwYyYZzZzZzs";
+
+/// Lowercase base letters of `c`, the second one is used by expansions.
+#[cfg(feature = "unicode")]
+fn base(c: char) -> ([char; 2], usize) {
+    match c {
+        'ß' => (['s', 's'], 2),
+        'Æ' | 'æ' => (['a', 'e'], 2),
+        'Œ' | 'œ' => (['o', 'e'], 2),
+        'Ĳ' | 'ĳ' => (['i', 'j'], 2),
+        _ => match (c as usize).checked_sub(0xc0).and_then(|i| LATIN_BASE.get(i)) {
+            Some(&b) if b != b'.' => ([b.to_ascii_lowercase() as char, ' '], 1),
+            _ => ([c.to_lowercase().next().unwrap_or(c), ' '], 1)
+        }
+    }
+}
+
+/// Accent weight of `c`, `0` for a letter without one.
+#[cfg(feature = "unicode")]
+fn accent(c: char) -> u32 {
+    let lower = c.to_lowercase().next().unwrap_or(c);
+    match base(c) {
+        ([b, _], 1) if b == lower => 0,
+        _ => lower as u32
+    }
+}
+
+#[cfg(feature = "unicode")]
+fn bases(s: &str) -> impl Iterator<Item = char> + '_ {
+    s.chars().flat_map(|c| {
+        let (letters, n) = base(c);
+        letters.into_iter().take(n)
+    })
+}
+
+#[cfg(feature = "unicode")]
+impl Collation for UnicodeCollation {
+    fn compare(a: &str, b: &str) -> Ordering {
+        bases(a).cmp(bases(b))
+            .then_with(|| a.chars().map(accent).cmp(b.chars().map(accent)))
+            .then_with(|| a.chars().map(char::is_uppercase).cmp(b.chars().map(char::is_uppercase)))
+            .then_with(|| a.cmp(b))
+    }
+}
+
+/// Orders strings ignoring case of ASCII letters, other characters
+/// compare by their bytes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AsciiCaseInsensitive<S>(pub S);
+
+/// Orders strings by their Unicode lowercase form, so `"Ärger"` and
+/// `"ärger"` are equal. Characters are lowered one by one without
+/// locale rules.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CaseInsensitive<S>(pub S);
+
+impl<S: AsRef<str>> Ord for AsciiCaseInsensitive<S> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        AsciiCaseFold::compare(self.0.as_ref(), other.0.as_ref())
+    }
+}
+
+impl<S: AsRef<str>> Ord for CaseInsensitive<S> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        CaseFold::compare(self.0.as_ref(), other.0.as_ref())
+    }
+}
+
+macro_rules! ordered_by_cmp {
+    ($($name:ident),*) => {$(
+        impl<S: AsRef<str>> PartialOrd for $name<S> {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl<S: AsRef<str>> PartialEq for $name<S> {
+            fn eq(&self, other: &Self) -> bool {
+                self.cmp(other) == Ordering::Equal
+            }
+        }
+
+        impl<S: AsRef<str>> Eq for $name<S> {}
+
+        impl<S: fmt::Display> fmt::Display for $name<S> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.0.fmt(f)
+            }
+        }
+    )*};
+}
+
+ordered_by_cmp!(AsciiCaseInsensitive, CaseInsensitive);
+
+#[cfg(test)]
+mod tests {
+    use super::{AsciiCaseFold, AsciiCaseInsensitive, CaseFold, CaseInsensitive};
+    use crate::{BstMap, Tree};
+
+    #[test]
+    fn case_insensitive() {
+        let mut tree = Tree::new();
+        for w in ["pear", "Apple", "banana", "APPLE", "Zoo"] {
+            tree.insert(AsciiCaseInsensitive(w));
+        }
+        let order: Vec<&str> = tree.iter().map(|w| w.0).collect();
+        assert_eq!(order, ["Apple", "APPLE", "banana", "pear", "Zoo"]);
+        assert!(tree.contains(&AsciiCaseInsensitive("ZOO")));
+        assert!(AsciiCaseInsensitive("Ärger") != AsciiCaseInsensitive("ärger"));
+
+        let mut map = BstMap::new();
+        map.insert(CaseInsensitive(String::from("Ärger")), 1);
+        assert_eq!(map.insert(CaseInsensitive(String::from("ärger")), 2), Some(1));
+        assert_eq!(map.len(), 1);
+        assert!(CaseInsensitive("apple") < CaseInsensitive("BANANA"));
+    }
+    #[test]
+    fn presets() {
+        let mut tree = Tree::with_collation(AsciiCaseFold);
+        for w in ["pear", "Apple", "banana", "APPLE", "Zoo"] {
+            tree.insert_str(w);
+        }
+        let order: Vec<&str> = tree.iter().map(|w| w.as_str()).collect();
+        assert_eq!(order, ["Apple", "APPLE", "banana", "pear", "Zoo"]);
+        assert_eq!(tree.get_str("zoo").map(|w| w.as_str()), Some("Zoo"));
+        assert!(!tree.contains_str("Ärger"));
+
+        let mut map = BstMap::with_collation(CaseFold);
+        assert_eq!(map.insert_str("Ärger", 1), None);
+        assert_eq!(map.insert_str("ärger", 2), Some(1));
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.get_str("ÄRGER"), Some(&2));
+        assert_eq!(map.keys().next().map(|k| k.as_str()), Some("Ärger"));
+    }
+    #[cfg(feature = "unicode")]
+    #[test]
+    fn unicode_collation() {
+        use super::{Collated, UnicodeCollation};
+
+        let mut tree = Tree::with_collation(UnicodeCollation);
+        for w in ["zebra", "Äpfel", "Straße", "apfel", "ähnlich", "Apfel", "strasse", "ahnen", "Strasse", "Œuvre"] {
+            tree.insert_str(w);
+        }
+        let order: Vec<&str> = tree.iter().map(|w| w.as_str()).collect();
+        assert_eq!(order, [
+            "ahnen", "ähnlich", "apfel", "Apfel", "Äpfel", "Œuvre", "strasse", "Strasse", "Straße", "zebra"
+        ]);
+        assert!(tree.contains_str("Äpfel"));
+        assert!(!tree.contains_str("äpfel"));
+        assert!(Collated::<UnicodeCollation>::new("Ærø") < Collated::new("Aesir"));
+    }
+}
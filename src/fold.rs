@@ -0,0 +1,188 @@
+//! Monoids which aggregate elements of a range.
+//!
+//! `BinarySearchTree::range_fold()` folds a range from scratch, while
+//! an `AugmentedTree<T, F>` keeps fold `F` of every sub tree in its
+//! nodes and answers `range_fold()` from them, visiting only nodes on
+//! two paths from root of a balanced tree, `O(log n)`.
+//!
+//! # Example
+//!
+//! ```rust
+//! use ds_bst::{AugmentedTree, BinarySearchTree, Count, Sum};
+//!
+//! let root = BinarySearchTree::from(vec![1, 2, 3, 4, 5]);
+//! assert_eq!(root.range_fold::<Sum, _>(2..5), 9);
+//!
+//! let mut tree: AugmentedTree<_, Count> = AugmentedTree::new();
+//! for v in 1..=5 {
+//!     tree.insert(v);
+//! }
+//! assert_eq!(tree.range_fold(2..), 4);
+//! ```
+use std::ops::Add;
+
+/// Aggregation of elements, `combine` has to be associative with
+/// `identity` as neutral element. Elements are combined in sorted
+/// order, so it does not need to be commutative.
+pub trait Fold<T> {
+    /// Aggregated value.
+    type Acc: Clone;
+
+    /// Aggregate of no elements.
+    fn identity() -> Self::Acc;
+
+    /// Aggregate of a single element.
+    fn from_value(v: &T) -> Self::Acc;
+
+    /// Aggregate of two adjacent runs of elements.
+    fn combine(a: Self::Acc, b: Self::Acc) -> Self::Acc;
+}
+
+/// No aggregate, for trees which keep none.
+impl<T> Fold<T> for () {
+    type Acc = ();
+
+    fn identity() {}
+
+    fn from_value(_: &T) {}
+
+    fn combine(_: (), _: ()) {}
+}
+
+/// Sum of elements, `T::default()` for none.
+pub struct Sum;
+
+impl<T: Copy + Default + Add<Output = T>> Fold<T> for Sum {
+    type Acc = T;
+
+    fn identity() -> T {
+        T::default()
+    }
+
+    fn from_value(v: &T) -> T {
+        *v
+    }
+
+    fn combine(a: T, b: T) -> T {
+        a + b
+    }
+}
+
+/// Number of elements.
+pub struct Count;
+
+impl<T> Fold<T> for Count {
+    type Acc = usize;
+
+    fn identity() -> usize {
+        0
+    }
+
+    fn from_value(_: &T) -> usize {
+        1
+    }
+
+    fn combine(a: usize, b: usize) -> usize {
+        a + b
+    }
+}
+
+/// Smallest element, `None` for none.
+pub struct Min;
+
+impl<T: PartialOrd + Copy> Fold<T> for Min {
+    type Acc = Option<T>;
+
+    fn identity() -> Option<T> {
+        None
+    }
+
+    fn from_value(v: &T) -> Option<T> {
+        Some(*v)
+    }
+
+    fn combine(a: Option<T>, b: Option<T>) -> Option<T> {
+        match (a, b) {
+            (Some(x), Some(y)) => Some(if y < x { y } else { x }),
+            (x, y) => x.or(y)
+        }
+    }
+}
+
+/// Largest element, `None` for none.
+pub struct Max;
+
+impl<T: PartialOrd + Copy> Fold<T> for Max {
+    type Acc = Option<T>;
+
+    fn identity() -> Option<T> {
+        None
+    }
+
+    fn from_value(v: &T) -> Option<T> {
+        Some(*v)
+    }
+
+    fn combine(a: Option<T>, b: Option<T>) -> Option<T> {
+        match (a, b) {
+            (Some(x), Some(y)) => Some(if y > x { y } else { x }),
+            (x, y) => x.or(y)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Count, Fold, Max, Min, Sum};
+    use crate::augmented::AugmentedTree;
+    use crate::bst::BinarySearchTree;
+
+    /// Concatenation, which is not commutative.
+    struct Concat;
+
+    impl Fold<u8> for Concat {
+        type Acc = String;
+
+        fn identity() -> String {
+            String::new()
+        }
+
+        fn from_value(v: &u8) -> String {
+            v.to_string()
+        }
+
+        fn combine(a: String, b: String) -> String {
+            a + &b
+        }
+    }
+
+    #[test]
+    fn folds() {
+        let values: Vec<u8> = vec![7, 3, 9, 1, 5, 5, 8, 2];
+        let root = BinarySearchTree::from(values.clone());
+        let mut tree: AugmentedTree<u8, Concat> = AugmentedTree::new();
+        let mut sums: AugmentedTree<u32, Sum> = AugmentedTree::new();
+        for &v in &values {
+            tree.insert(v);
+            sums.insert(v as u32);
+        }
+        tree.remove(9);
+        sums.remove(9);
+
+        for lo in 0..10 {
+            for hi in lo..11 {
+                let expected: String = (lo..hi).filter(|v| values.contains(v) && *v != 9)
+                    .flat_map(|v| vec![v; values.iter().filter(|&&x| x == v).count()])
+                    .map(|v| v.to_string()).collect();
+                assert_eq!(tree.range_fold(lo..hi), expected);
+                let sum: u32 = expected.bytes().map(|b| (b - b'0') as u32).sum();
+                assert_eq!(sums.range_fold(lo as u32..hi as u32), sum);
+            }
+        }
+        assert_eq!(root.range_fold::<Concat, _>(2..=7), "23557");
+        assert_eq!(root.range_fold::<Count, _>(..), 8);
+        assert_eq!(root.range_fold::<Min, _>(4..), Some(5));
+        assert_eq!(root.range_fold::<Max, _>(..7), Some(5));
+        assert_eq!(root.range_fold::<Max, _>(10..), None);
+    }
+}
@@ -0,0 +1,219 @@
+use std::cmp::Ordering;
+
+use crate::codec::KeyCodec;
+
+/// Leading bytes of `FrozenBst::to_bytes()` output.
+const MAGIC: &[u8; 4] = b"BSTF";
+
+/// Immutable tree in one dense block, made by `BinarySearchTree::freeze()`.
+///
+/// Elements are stored in breadth first (Eytzinger) order of a complete
+/// tree, children of slot `k` are at `2k` and `2k + 1` counting from
+/// `1`. Searches walk down this implicit tree, so the first levels
+/// share cache lines and no pointers are followed. Suited for a build
+/// once and query forever phase.
+///
+/// # Example
+///
+/// ```rust
+/// use ds_bst::BinarySearchTree;
+///
+/// let frozen = BinarySearchTree::from(vec![40, 10, 30, 20]).freeze();
+/// assert!(frozen.contains(&30));
+/// assert_eq!(frozen.lower_bound(&25), Some(&30));
+/// assert_eq!(frozen.iter().copied().collect::<Vec<_>>(), vec![10, 20, 30, 40]);
+/// ```
+pub struct FrozenBst<T> {
+    data: Box<[T]>
+}
+
+impl<T: PartialOrd> FrozenBst<T> {
+    /// Contructor lays out already sorted elements.
+    pub(crate) fn from_sorted(sorted: Vec<T>) -> FrozenBst<T> {
+        fn place<T>(k: usize, slots: &mut [Option<T>], values: &mut std::vec::IntoIter<T>) {
+            if k <= slots.len() {
+                place(2 * k, slots, values);
+                slots[k - 1] = values.next();
+                place(2 * k + 1, slots, values);
+            }
+        }
+
+        let mut slots: Vec<Option<T>> = std::iter::repeat_with(|| None).take(sorted.len()).collect();
+        place(1, &mut slots, &mut sorted.into_iter());
+        FrozenBst { data: slots.into_iter().map(|v| v.unwrap()).collect() }
+    }
+
+    /// Number of elements.
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Checks if there are no elements.
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Returns smallest element which is not less than `val`.
+    /// Uses `O(log n)` time.
+    pub fn lower_bound(&self, val: &T) -> Option<&T> {
+        let mut k = 1;
+        while k <= self.data.len() {
+            k = 2 * k + (self.data[k - 1] < *val) as usize;
+        }
+        k >>= k.trailing_ones() + 1;
+        if k == 0 { None } else { Some(&self.data[k - 1]) }
+    }
+
+    /// Returns stored element equal to `val`.
+    /// Uses `O(log n)` time.
+    pub fn get(&self, val: &T) -> Option<&T> {
+        self.lower_bound(val).filter(|v| *v == val)
+    }
+
+    /// Checks if element exists.
+    /// Uses `O(log n)` time.
+    pub fn contains(&self, val: &T) -> bool {
+        self.get(val).is_some()
+    }
+
+    /// Returns smallest element.
+    pub fn first(&self) -> Option<&T> {
+        self.leftmost(1).map(|k| &self.data[k - 1])
+    }
+
+    /// Returns largest element.
+    pub fn last(&self) -> Option<&T> {
+        if self.data.is_empty() {
+            return None;
+        }
+        let mut k = 1;
+        while 2 * k < self.data.len() {
+            k = 2 * k + 1;
+        }
+        Some(&self.data[k - 1])
+    }
+
+    /// Iterates elements in sorted order.
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        std::iter::successors(self.leftmost(1), move |&k| self.successor(k)).map(move |k| &self.data[k - 1])
+    }
+
+    fn leftmost(&self, mut k: usize) -> Option<usize> {
+        if k > self.data.len() {
+            return None;
+        }
+        while 2 * k <= self.data.len() {
+            k *= 2;
+        }
+        Some(k)
+    }
+
+    fn successor(&self, k: usize) -> Option<usize> {
+        if let Some(next) = self.leftmost(2 * k + 1) {
+            return Some(next);
+        }
+        let k = k >> (k.trailing_ones() + 1);
+        if k == 0 { None } else { Some(k) }
+    }
+}
+
+impl<T: PartialOrd + KeyCodec> FrozenBst<T> {
+    /// Serializes elements in sorted order with their `KeyCodec`. Layout is
+    /// `BSTF` magic, element count as little endian `u64`, then every
+    /// element as little endian `u32` length followed by its encoding.
+    /// Uses `O(n)` time.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = MAGIC.to_vec();
+        out.extend_from_slice(&(self.len() as u64).to_le_bytes());
+        let mut key = Vec::new();
+        for v in self.iter() {
+            key.clear();
+            v.encode(&mut key);
+            out.extend_from_slice(&(key.len() as u32).to_le_bytes());
+            out.extend_from_slice(&key);
+        }
+        out
+    }
+
+    /// Reads output of `to_bytes()`, returns `None` if it is truncated,
+    /// malformed or elements are out of order.
+    /// Uses `O(n)` time.
+    pub fn from_bytes(bytes: &[u8]) -> Option<FrozenBst<T>> {
+        fn take<'a>(bytes: &mut &'a [u8], n: usize) -> Option<&'a [u8]> {
+            if bytes.len() < n {
+                return None;
+            }
+            let (head, rest) = bytes.split_at(n);
+            *bytes = rest;
+            Some(head)
+        }
+
+        let mut bytes = bytes;
+        if take(&mut bytes, MAGIC.len())? != MAGIC {
+            return None;
+        }
+        let count = u64::from_le_bytes(take(&mut bytes, 8)?.try_into().ok()?);
+        let mut sorted: Vec<T> = Vec::new();
+        for _ in 0..count {
+            let len = u32::from_le_bytes(take(&mut bytes, 4)?.try_into().ok()?);
+            let v = T::decode(take(&mut bytes, len as usize)?)?;
+            if sorted.last().is_some_and(|last| !matches!(last.partial_cmp(&v), Some(Ordering::Less | Ordering::Equal))) {
+                return None;
+            }
+            sorted.push(v);
+        }
+        if !bytes.is_empty() {
+            return None;
+        }
+        Some(FrozenBst::from_sorted(sorted))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FrozenBst;
+    use crate::bst::BinarySearchTree;
+
+    #[test]
+    fn queries() {
+        for n in 1..40 {
+            let values: Vec<u32> = (0..n).map(|v| v * 2).collect();
+            let frozen = BinarySearchTree::from(values.clone()).freeze();
+            assert_eq!(frozen.len(), n as usize);
+            assert_eq!(frozen.iter().copied().collect::<Vec<_>>(), values);
+            assert_eq!((frozen.first(), frozen.last()), (values.first(), values.last()));
+            for x in 0..2 * n + 1 {
+                assert_eq!(frozen.lower_bound(&x), values.iter().find(|&&v| v >= x));
+                assert_eq!(frozen.contains(&x), x % 2 == 0 && x < 2 * n);
+            }
+        }
+    }
+    #[test]
+    fn owned_values() {
+        let root: BinarySearchTree<String> = ["b", "a", "c"].iter().map(|s| s.to_string()).collect();
+        let frozen = root.freeze();
+        assert_eq!(frozen.get(&"b".to_string()).map(String::as_str), Some("b"));
+        assert!(!frozen.is_empty());
+    }
+    #[test]
+    fn bytes_round_trip() {
+        let frozen = BinarySearchTree::from(vec![-3.5, 2.0, 0.0, 10.25]).freeze();
+        let bytes = frozen.to_bytes();
+        let back = FrozenBst::<f64>::from_bytes(&bytes).unwrap();
+        assert_eq!(back.iter().copied().collect::<Vec<_>>(), vec![-3.5, 0.0, 2.0, 10.25]);
+
+        let words: BinarySearchTree<String> = ["b", "a"].iter().map(|s| s.to_string()).collect();
+        let back = FrozenBst::<String>::from_bytes(&words.freeze().to_bytes()).unwrap();
+        assert!(back.contains(&"a".to_string()));
+
+        assert!(FrozenBst::<f64>::from_bytes(&bytes[..bytes.len() - 1]).is_none());
+        assert!(FrozenBst::<f64>::from_bytes(b"BSTX").is_none());
+        let mut unsorted = bytes.clone();
+        let (a, b) = (16, 16 + 12);
+        for i in 0..12 {
+            unsorted.swap(a + i, b + i);
+        }
+        assert!(FrozenBst::<f64>::from_bytes(&unsorted).is_none());
+        assert!(FrozenBst::<u8>::from_bytes(&FrozenBst::<u8>::from_sorted(Vec::new()).to_bytes()).unwrap().is_empty());
+    }
+}
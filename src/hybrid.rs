@@ -0,0 +1,390 @@
+use std::cmp::max;
+
+/// Sub trees of up to this many elements are kept as sorted arrays by
+/// `HybridTree::new()`.
+const DEFAULT_LEAF_SIZE: usize = 16;
+
+enum Node<T> {
+    /// Sorted elements of a sub tree no larger than leaf size.
+    Leaf(Vec<T>),
+    Branch(Box<Branch<T>>)
+}
+
+/// Node of a sub tree larger than leaf size.
+struct Branch<T> {
+    val: T,
+    /// Number of elements in sub tree rooted at this node.
+    len: usize,
+    left: Node<T>,
+    right: Node<T>
+}
+
+impl<T> Node<T> {
+    fn len(&self) -> usize {
+        match self {
+            Node::Leaf(items) => items.len(),
+            Node::Branch(b) => b.len
+        }
+    }
+
+    /// Appends elements of sub tree to `out` in sorted order.
+    fn drain_into(self, out: &mut Vec<T>) {
+        match self {
+            Node::Leaf(mut items) => out.append(&mut items),
+            Node::Branch(b) => {
+                let Branch { val, left, right, .. } = *b;
+                left.drain_into(out);
+                out.push(val);
+                right.drain_into(out);
+            }
+        }
+    }
+}
+
+/// Hybrid of a binary search tree and sorted arrays: sub trees of up
+/// to `leaf_size` elements are stored as one sorted array instead of
+/// a node per element.
+///
+/// A leaf which grows past `leaf_size` is split around its middle
+/// element into a branch node with two leaves, and a branch whose sub
+/// tree shrinks to `leaf_size` is linearized back into one leaf. Small
+/// sub trees thus take one allocation and are searched in contiguous
+/// memory, cutting allocations and pointer chasing near the bottom of
+/// the tree. Branches are not rebalanced, so height `h` counts branch
+/// levels of the unbalanced tree above the leaves.
+///
+/// # Example
+///
+/// ```rust
+/// use ds_bst::HybridTree;
+///
+/// let mut tree = HybridTree::with_leaf_size(4);
+/// for v in [5, 1, 9, 3, 7, 2, 8] {
+///     tree.insert(v);
+/// }
+/// assert!(tree.contains(&7));
+/// assert!(tree.remove(&7));
+/// assert_eq!(tree.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3, 5, 8, 9]);
+/// ```
+pub struct HybridTree<T> {
+    root: Node<T>,
+    leaf_size: usize
+}
+
+impl<T: PartialOrd> HybridTree<T> {
+    /// Contructor creates an empty tree storing sub trees of up to 16
+    /// elements as sorted arrays.
+    pub fn new() -> HybridTree<T> {
+        HybridTree::with_leaf_size(DEFAULT_LEAF_SIZE)
+    }
+
+    /// Contructor creates an empty tree storing sub trees of up to
+    /// `leaf_size` elements as sorted arrays.
+    ///
+    /// # Panics
+    /// Panics if `leaf_size` is `0`.
+    pub fn with_leaf_size(leaf_size: usize) -> HybridTree<T> {
+        assert!(leaf_size > 0, "Leaf size must be positive");
+        HybridTree { root: Node::Leaf(Vec::new()), leaf_size }
+    }
+
+    /// Largest number of elements kept in one sorted array.
+    pub fn leaf_size(&self) -> usize {
+        self.leaf_size
+    }
+
+    /// Number of elements in a tree.
+    /// Uses `O(1)` time.
+    pub fn len(&self) -> usize {
+        self.root.len()
+    }
+
+    /// Checks if tree has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Inserts an element in a tree, after copies equal to it. A leaf
+    /// growing past leaf size is split in two.
+    /// Uses `O(h + b)` time for leaf size `b`.
+    pub fn insert(&mut self, val: T) {
+        fn insert<T: PartialOrd>(node: &mut Node<T>, val: T, leaf_size: usize) {
+            match node {
+                Node::Leaf(items) => {
+                    let i = items.partition_point(|v| *v <= val);
+                    items.insert(i, val);
+                    if items.len() > leaf_size {
+                        let mut left = std::mem::take(items);
+                        let right = left.split_off(left.len() / 2 + 1);
+                        let val = left.pop().expect("split leaf is not empty");
+                        let (left, right) = (Node::Leaf(left), Node::Leaf(right));
+                        *node = Node::Branch(Box::new(Branch { val, len: leaf_size + 1, left, right }));
+                    }
+                },
+                Node::Branch(b) => {
+                    b.len += 1;
+                    if b.val > val {
+                        insert(&mut b.left, val, leaf_size);
+                    } else {
+                        insert(&mut b.right, val, leaf_size);
+                    }
+                }
+            }
+        }
+
+        insert(&mut self.root, val, self.leaf_size);
+    }
+
+    /// Removes one copy of `val`, returns `false` if it does not exist.
+    /// A branch whose sub tree shrinks to leaf size becomes a leaf.
+    /// Uses `O(h + b)` time for leaf size `b`.
+    pub fn remove(&mut self, val: &T) -> bool {
+        fn remove<T: PartialOrd>(node: &mut Node<T>, val: &T, leaf_size: usize) -> bool {
+            let removed = match node {
+                Node::Leaf(items) => {
+                    let i = items.partition_point(|v| v < val);
+                    if items.get(i) != Some(val) {
+                        return false;
+                    }
+                    items.remove(i);
+                    return true;
+                },
+                Node::Branch(b) if b.val == *val => {
+                    replace_val(b, leaf_size);
+                    true
+                },
+                Node::Branch(b) if b.val > *val => remove(&mut b.left, val, leaf_size),
+                Node::Branch(b) if b.val < *val => remove(&mut b.right, val, leaf_size),
+                Node::Branch(_) => false
+            };
+            if removed {
+                shrink(node, leaf_size);
+            }
+            removed
+        }
+
+        remove(&mut self.root, val, self.leaf_size)
+    }
+
+    /// Checks if element exists in a tree.
+    /// Uses `O(h + log b)` time for leaf size `b`.
+    pub fn contains(&self, val: &T) -> bool {
+        let mut node = &self.root;
+        loop {
+            match node {
+                Node::Leaf(items) => {
+                    let i = items.partition_point(|v| v < val);
+                    return items.get(i) == Some(val);
+                },
+                Node::Branch(b) if b.val == *val => return true,
+                Node::Branch(b) if b.val > *val => node = &b.left,
+                Node::Branch(b) if b.val < *val => node = &b.right,
+                Node::Branch(_) => return false
+            }
+        }
+    }
+
+    /// Iterates elements in sorted order.
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        let mut iter = Iter { stack: Vec::new(), leaf: [].iter() };
+        iter.push_left(&self.root);
+        iter
+    }
+
+    /// Calculates tree maximum height, counting a leaf as one level.
+    /// Uses `O(n / b)` time for leaf size `b`.
+    pub fn height(&self) -> usize {
+        fn walk<T>(node: &Node<T>) -> usize {
+            match node {
+                Node::Leaf(_) => 1,
+                Node::Branch(b) => max(walk(&b.left), walk(&b.right)) + 1
+            }
+        }
+
+        walk(&self.root)
+    }
+}
+
+impl<T: PartialOrd + Copy> HybridTree<T> {
+    /// Inorder traverse tree which yields elements in sorted order.
+    /// Uses `O(n)` time.
+    pub fn inorder(&self) -> Vec<T> {
+        self.iter().copied().collect()
+    }
+}
+
+/// Removes largest element of non-empty sub tree.
+fn pop_max<T>(node: &mut Node<T>, leaf_size: usize) -> T {
+    let max = match node {
+        Node::Leaf(items) => return items.pop().expect("sub tree is not empty"),
+        Node::Branch(b) if b.right.len() > 0 => pop_max(&mut b.right, leaf_size),
+        Node::Branch(b) => replace_val(b, leaf_size)
+    };
+    shrink(node, leaf_size);
+    max
+}
+
+/// Removes smallest element of non-empty sub tree.
+fn pop_min<T>(node: &mut Node<T>, leaf_size: usize) -> T {
+    let min = match node {
+        Node::Leaf(items) => return items.remove(0),
+        Node::Branch(b) if b.left.len() > 0 => pop_min(&mut b.left, leaf_size),
+        Node::Branch(b) => replace_val(b, leaf_size)
+    };
+    shrink(node, leaf_size);
+    min
+}
+
+/// Takes value of branch, one of its neighbours in sorted order takes
+/// its place. Length of branch is left for `shrink()` to update.
+fn replace_val<T>(b: &mut Branch<T>, leaf_size: usize) -> T {
+    let next = if b.left.len() > 0 {
+        pop_max(&mut b.left, leaf_size)
+    } else {
+        pop_min(&mut b.right, leaf_size)
+    };
+    std::mem::replace(&mut b.val, next)
+}
+
+/// Counts one element removed below a branch, linearizing it into a
+/// leaf when it is no longer larger than leaf size.
+fn shrink<T>(node: &mut Node<T>, leaf_size: usize) {
+    if let Node::Branch(b) = node {
+        b.len -= 1;
+        if b.len <= leaf_size {
+            let mut items = Vec::with_capacity(leaf_size + 1);
+            std::mem::replace(node, Node::Leaf(Vec::new())).drain_into(&mut items);
+            *node = Node::Leaf(items);
+        }
+    }
+}
+
+/// In-order iterator holding branches whose value and right sub tree
+/// are still to be visited, and rest of current leaf.
+struct Iter<'a, T> {
+    stack: Vec<&'a Branch<T>>,
+    leaf: std::slice::Iter<'a, T>
+}
+
+impl<'a, T> Iter<'a, T> {
+    fn push_left(&mut self, mut node: &'a Node<T>) {
+        loop {
+            match node {
+                Node::Leaf(items) => {
+                    self.leaf = items.iter();
+                    return;
+                },
+                Node::Branch(b) => {
+                    self.stack.push(b);
+                    node = &b.left;
+                }
+            }
+        }
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        if let Some(v) = self.leaf.next() {
+            return Some(v);
+        }
+        let b = self.stack.pop()?;
+        self.push_left(&b.right);
+        Some(&b.val)
+    }
+}
+
+impl<T: PartialOrd> Default for HybridTree<T> {
+    fn default() -> Self {
+        HybridTree::new()
+    }
+}
+
+impl<T: PartialOrd> Extend<T> for HybridTree<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for v in iter {
+            self.insert(v);
+        }
+    }
+}
+
+impl<T: PartialOrd> FromIterator<T> for HybridTree<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut tree = HybridTree::new();
+        tree.extend(iter);
+        tree
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HybridTree, Node};
+
+    /// Checks stored lengths, leaf sizes and order, returns length.
+    fn check<T: PartialOrd>(node: &Node<T>, leaf_size: usize) -> usize {
+        match node {
+            Node::Leaf(items) => {
+                assert!(items.len() <= leaf_size);
+                assert!(items.windows(2).all(|w| w[0] <= w[1]));
+                items.len()
+            },
+            Node::Branch(b) => {
+                let len = check(&b.left, leaf_size) + check(&b.right, leaf_size) + 1;
+                assert_eq!(b.len, len);
+                assert!(len > leaf_size);
+                len
+            }
+        }
+    }
+
+    #[test]
+    fn leaves_split_and_merge() {
+        let mut tree = HybridTree::with_leaf_size(4);
+        let mut model = Vec::new();
+        for i in 0..200 {
+            let v = (i * 37) % 50;
+            tree.insert(v);
+            model.push(v);
+            check(&tree.root, 4);
+        }
+        model.sort();
+        assert_eq!(tree.len(), 200);
+        assert_eq!(tree.inorder(), model);
+        assert!(tree.height() > 1);
+
+        for i in 0..200 {
+            let v = (i * 13) % 50;
+            assert!(tree.remove(&v));
+            let at = model.iter().position(|&x| x == v).unwrap();
+            model.remove(at);
+            check(&tree.root, 4);
+            assert_eq!(tree.inorder(), model);
+        }
+        assert!(tree.is_empty());
+        assert_eq!(tree.height(), 1);
+        assert!(!tree.remove(&0));
+    }
+    #[test]
+    fn lookups() {
+        let tree: HybridTree<i32> = (0..100).map(|v| v * 2).collect();
+        assert_eq!(tree.leaf_size(), 16);
+        assert!(tree.contains(&0));
+        assert!(tree.contains(&198));
+        assert!(!tree.contains(&99));
+        assert!(!tree.contains(&-1));
+        assert!(tree.iter().copied().eq((0..100).map(|v| v * 2)));
+
+        let mut small = HybridTree::new();
+        small.extend([3, 1, 2]);
+        assert_eq!(small.height(), 1);
+        assert!(small.remove(&2));
+        assert_eq!(small.inorder(), vec![1, 3]);
+    }
+    #[test]
+    #[should_panic(expected = "Leaf size must be positive")]
+    fn zero_leaf_size() {
+        HybridTree::<i32>::with_leaf_size(0);
+    }
+}
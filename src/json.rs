@@ -0,0 +1,206 @@
+use std::str::Chars;
+
+/// Values which `BinarySearchTree::to_json_tree()` writes as JSON
+/// literals and `BinarySearchTree::from_json_tree()` reads back.
+///
+/// Integers and finite floats are written as numbers, `bool` as `true`
+/// or `false`, and characters and strings as JSON strings with quotes,
+/// backslashes and control characters escaped. Non-finite floats have
+/// no JSON number, so they are written as strings `"NaN"`, `"inf"` and
+/// `"-inf"`. Output is valid JSON for every value.
+pub trait JsonValue: Sized {
+    /// Appends JSON literal of value to `out`.
+    fn write_json(&self, out: &mut String);
+
+    /// Reads value from whole of JSON literal `s`, `None` if it is
+    /// malformed or holds a value of another type.
+    fn read_json(s: &str) -> Option<Self>;
+}
+
+/// Appends `s` as a quoted JSON string.
+fn write_string(s: &str, out: &mut String) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c < ' ' => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c)
+        }
+    }
+    out.push('"');
+}
+
+/// Reads a quoted JSON string, resolving its escapes.
+fn read_string(s: &str) -> Option<String> {
+    let mut chars = s.strip_prefix('"')?.strip_suffix('"')?.chars();
+    let mut out = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return None,
+            c if c < ' ' => return None,
+            '\\' => out.push(match chars.next()? {
+                '"' => '"',
+                '\\' => '\\',
+                '/' => '/',
+                'b' => '\u{8}',
+                'f' => '\u{c}',
+                'n' => '\n',
+                'r' => '\r',
+                't' => '\t',
+                'u' => {
+                    let unit = read_hex(&mut chars)?;
+                    if (0xd800..0xdc00).contains(&unit) {
+                        if chars.next()? != '\\' || chars.next()? != 'u' {
+                            return None;
+                        }
+                        let low = read_hex(&mut chars)?;
+                        char::from_u32(0x10000 + ((unit - 0xd800) << 10) + low.checked_sub(0xdc00)?)?
+                    } else {
+                        char::from_u32(unit)?
+                    }
+                },
+                _ => return None
+            }),
+            c => out.push(c)
+        }
+    }
+    Some(out)
+}
+
+/// Reads four hex digits of a `\u` escape.
+fn read_hex(chars: &mut Chars<'_>) -> Option<u32> {
+    let digits: String = chars.take(4).collect();
+    if digits.len() == 4 && digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        u32::from_str_radix(&digits, 16).ok()
+    } else {
+        None
+    }
+}
+
+/// Checks that `s` starts like a JSON number does, since `parse()` also
+/// accepts forms such as `+1` or `inf`.
+fn is_number(s: &str) -> bool {
+    let digits = s.strip_prefix('-').unwrap_or(s);
+    digits.starts_with(|c: char| c.is_ascii_digit())
+        && !s.contains(|c: char| c.is_alphabetic() && c != 'e' && c != 'E')
+}
+
+macro_rules! integer_json {
+    ($($t:ty),*) => {$(
+        impl JsonValue for $t {
+            fn write_json(&self, out: &mut String) {
+                out.push_str(&self.to_string());
+            }
+
+            fn read_json(s: &str) -> Option<Self> {
+                if is_number(s) { s.parse().ok() } else { None }
+            }
+        }
+    )*};
+}
+
+macro_rules! float_json {
+    ($($t:ty),*) => {$(
+        impl JsonValue for $t {
+            fn write_json(&self, out: &mut String) {
+                if self.is_finite() {
+                    out.push_str(&self.to_string());
+                } else if self.is_nan() {
+                    out.push_str("\"NaN\"");
+                } else if *self > 0.0 {
+                    out.push_str("\"inf\"");
+                } else {
+                    out.push_str("\"-inf\"");
+                }
+            }
+
+            fn read_json(s: &str) -> Option<Self> {
+                match s {
+                    "\"NaN\"" => Some(<$t>::NAN),
+                    "\"inf\"" => Some(<$t>::INFINITY),
+                    "\"-inf\"" => Some(<$t>::NEG_INFINITY),
+                    _ if is_number(s) => s.parse().ok(),
+                    _ => None
+                }
+            }
+        }
+    )*};
+}
+
+integer_json!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+float_json!(f32, f64);
+
+impl JsonValue for bool {
+    fn write_json(&self, out: &mut String) {
+        out.push_str(if *self { "true" } else { "false" });
+    }
+
+    fn read_json(s: &str) -> Option<Self> {
+        match s {
+            "true" => Some(true),
+            "false" => Some(false),
+            _ => None
+        }
+    }
+}
+
+impl JsonValue for char {
+    fn write_json(&self, out: &mut String) {
+        write_string(self.encode_utf8(&mut [0; 4]), out);
+    }
+
+    fn read_json(s: &str) -> Option<Self> {
+        let s = read_string(s)?;
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(c),
+            _ => None
+        }
+    }
+}
+
+impl JsonValue for String {
+    fn write_json(&self, out: &mut String) {
+        write_string(self, out);
+    }
+
+    fn read_json(s: &str) -> Option<Self> {
+        read_string(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::JsonValue;
+
+    fn json<T: JsonValue>(v: T) -> String {
+        let mut out = String::new();
+        v.write_json(&mut out);
+        out
+    }
+
+    #[test]
+    fn literals() {
+        assert_eq!(json(-12i32), "-12");
+        assert_eq!(json(1.5f64), "1.5");
+        assert_eq!(json(f64::NEG_INFINITY), "\"-inf\"");
+        assert_eq!(json(true), "true");
+        assert_eq!(json('"'), "\"\\\"\"");
+        assert_eq!(json(String::from("a\\b\n\u{1}")), "\"a\\\\b\\n\\u0001\"");
+
+        assert_eq!(i32::read_json("-12"), Some(-12));
+        assert_eq!(i32::read_json("+12"), None);
+        assert_eq!(f64::read_json("inf"), None);
+        assert!(f64::read_json("\"NaN\"").unwrap().is_nan());
+        assert_eq!(f64::read_json("2.5e3"), Some(2500.0));
+        assert_eq!(char::read_json("\"\\u00e9\""), Some('é'));
+        assert_eq!(char::read_json("\"ab\""), None);
+        assert_eq!(String::read_json("\"\\ud83d\\ude00\"").as_deref(), Some("😀"));
+        assert_eq!(String::read_json("\"a\"b\""), None);
+        assert_eq!(bool::read_json("1"), None);
+    }
+}
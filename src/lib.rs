@@ -1,3 +1,58 @@
-pub use crate::bst::BinarySearchTree;
+pub use crate::adaptive::{AdaptiveStats, AdaptiveTree, BalanceStrategy};
+pub use crate::augmented::{AugmentedNode, AugmentedTree};
+pub use crate::avl::AvlTree;
+pub use crate::bst::{
+    BinarySearchTree, BinarySearchTreeIter, Chunks, CursorMut, Direction, DuplicatePolicy, Edit,
+    ImportOutcome, ImportReport, Incomparable, InsertOutcome, IntoSorted, NodeRef, OpResult,
+    Operation, OutOfRange, RebalanceTask, RebuiltSubtree, Shape, Subtrees, Tree, Unordered,
+    WithPath
+};
+pub use crate::bounded::{BoundedBst, EvictionPolicy};
+pub use crate::codec::KeyCodec;
+pub use crate::collate::{AsciiCaseFold, AsciiCaseInsensitive, CaseFold, CaseInsensitive, Collated, Collation};
+#[cfg(feature = "unicode")]
+pub use crate::collate::UnicodeCollation;
+pub use crate::fold::{Count, Fold, Max, Min, Sum};
+pub use crate::frozen::FrozenBst;
+pub use crate::hybrid::HybridTree;
+pub use crate::json::JsonValue;
+pub use crate::map::{BstMap, BstMapIter, BstMapIterMut, Versioned};
+pub use crate::multiway::{MultiWayTree, MultiWayTreeIter};
+#[cfg(feature = "observe")]
+pub use crate::observe::OpKind;
+pub use crate::packed::PackedStaticBst;
+pub use crate::sandbox::{OrderViolation, Sandbox};
+pub use crate::static_bst::{SortedIndex, StaticBst};
+pub use crate::weight_balanced::WeightBalancedTree;
+pub use crate::weighted::WeightedTree;
+#[cfg(feature = "rc")]
+pub use crate::rc_tree::{RcBinarySearchTree, RcNode};
 
+mod adaptive;
+mod augmented;
+mod avl;
+mod balanced;
+mod bounded;
 mod bst;
+mod codec;
+mod collate;
+mod fold;
+mod frozen;
+mod hybrid;
+mod json;
+mod map;
+mod multiway;
+#[cfg(feature = "observe")]
+pub mod observe;
+#[cfg(not(feature = "observe"))]
+mod observe;
+mod packed;
+mod sandbox;
+mod search;
+mod static_bst;
+mod weight_balanced;
+mod weighted;
+#[cfg(feature = "rc")]
+mod rc_tree;
+#[cfg(feature = "testing")]
+pub mod testing;
@@ -0,0 +1,712 @@
+use std::cmp::Ordering;
+use std::ops::{Add, Bound, RangeBounds};
+
+use crate::bst::{before_end, before_start, BinarySearchTree, BinarySearchTreeIter, Link};
+use crate::collate::{Collated, Collation};
+use crate::fold::{Fold, Sum};
+
+/// Stored pair, ordered by key alone, with fold `acc` of values in its
+/// sub tree.
+struct Entry<K, V, A = ()> {
+    key: K,
+    value: V,
+    acc: A
+}
+
+impl<K: PartialEq, V, A> PartialEq for Entry<K, V, A> {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl<K: PartialOrd, V, A> PartialOrd for Entry<K, V, A> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.key.partial_cmp(&other.key)
+    }
+}
+
+/// Ordered map built from `BinarySearchTree` nodes holding key-value
+/// pairs ordered by key. Unlike the set-like tree it can be empty and
+/// every key is stored once, inserting an existing key replaces value.
+///
+/// Every node also keeps fold `F` of values in its sub tree, updated on
+/// the way back up from each insert or remove, e.g. `BstMap<K, V, Sum>`
+/// answers `prefix_sum()` in one descent. Values of such a map can only
+/// be changed by inserting them again, so methods giving mutable access
+/// to values exist for the default `F = ()` alone.
+///
+/// # Example
+///
+/// ```rust
+/// use ds_bst::BstMap;
+///
+/// let mut map = BstMap::new();
+/// map.insert("b", 2);
+/// map.insert("a", 1);
+/// assert_eq!(map.insert("b", 3), Some(2));
+///
+/// assert_eq!(map.get(&"b"), Some(&3));
+/// assert_eq!(map.iter().collect::<Vec<_>>(), vec![(&"a", &1), (&"b", &3)]);
+/// assert_eq!(map.remove(&"a"), Some(1));
+/// ```
+pub struct BstMap<K, V, F: Fold<V> = ()> {
+    root: Link<Entry<K, V, F::Acc>>,
+    len: usize
+}
+
+type Node<K, V, A = ()> = BinarySearchTree<Entry<K, V, A>>;
+
+fn acc<K, V, F: Fold<V>>(link: &Link<Entry<K, V, F::Acc>>) -> F::Acc {
+    match link {
+        None => F::identity(),
+        Some(node) => node.val.acc.clone()
+    }
+}
+
+/// Recomputes fold of values in sub tree of `node` from its children.
+fn update<K, V, F: Fold<V>>(node: &mut Node<K, V, F::Acc>) {
+    let left = acc::<K, V, F>(&node.left);
+    let right = acc::<K, V, F>(&node.right);
+    node.val.acc = F::combine(F::combine(left, F::from_value(&node.val.value)), right);
+}
+
+/// Detaches minimum node of a sub tree, its right child takes its place.
+/// Folds are updated on the way up.
+fn pop_min<K, V, F: Fold<V>>(link: &mut Link<Entry<K, V, F::Acc>>) -> Link<Entry<K, V, F::Acc>> {
+    let node = link.as_mut()?;
+    if node.left.is_some() {
+        let min = pop_min::<K, V, F>(&mut node.left);
+        update::<K, V, F>(node);
+        return min;
+    }
+    let mut min = link.take()?;
+    *link = min.right.take();
+    Some(min)
+}
+
+impl<K: PartialOrd, V> BstMap<K, V> {
+    /// Contructor creates an empty map.
+    pub fn new() -> BstMap<K, V> {
+        BstMap::default()
+    }
+
+    /// Builds a map from set-like `tree` whose elements become keys and
+    /// `f` of each key its value, called in sorted order. Every node is
+    /// rebuilt in the same shape, so keys are not compared again unless
+    /// `tree` holds duplicates, whose first copies are kept.
+    /// Uses `O(n)` time.
+    pub(crate) fn from_tree<F>(tree: BinarySearchTree<K>, mut f: F) -> BstMap<K, V>
+        where
+            F: FnMut(&K) -> V
+    {
+        fn convert<K, V, F: FnMut(&K) -> V>(link: Link<K>, f: &mut F, len: &mut usize) -> Link<Entry<K, V>> {
+            let node = *link?;
+            let left = convert(node.left, f, len);
+            let value = f(&node.val);
+            *len += 1;
+            let right = convert(node.right, f, len);
+            Some(Box::new(BinarySearchTree { val: Entry { key: node.val, value, acc: () }, left, right }))
+        }
+
+        let unique = tree.iter().is_sorted_by(|a, b| a < b);
+        let mut len = 0;
+        let root = convert(Some(Box::new(tree)), &mut f, &mut len);
+        if unique {
+            return BstMap { root, len };
+        }
+
+        let mut entries = root.map_or_else(Vec::new, |r| r.into_sorted_vec());
+        entries.dedup();
+        BstMap { len: entries.len(), root: BinarySearchTree::from_sorted_iter(entries).map(Box::new) }
+    }
+
+    /// Returns mutable value stored under `key`.
+    /// Uses `O(n)` time.
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let mut current = self.root.as_deref_mut();
+        while let Some(node) = current {
+            current = match node.val.key.partial_cmp(key) {
+                Some(Ordering::Equal) => return Some(&mut node.val.value),
+                Some(Ordering::Greater) => node.left.as_deref_mut(),
+                Some(Ordering::Less) => node.right.as_deref_mut(),
+                None => None
+            };
+        }
+        None
+    }
+
+    /// Returns largest key not greater than `key` with mutable access to
+    /// its value, same descent as `BinarySearchTree::floor_bound()`.
+    /// Uses `O(n)` time.
+    pub fn floor_entry_mut(&mut self, key: &K) -> Option<(&K, &mut V)> {
+        let mut found = None;
+        let mut current = self.root.as_deref_mut();
+        while let Some(node) = current {
+            let BinarySearchTree { val, left, right } = node;
+            if before_end(Bound::Included(key), &val.key) {
+                found = Some((&val.key, &mut val.value));
+                current = right.as_deref_mut();
+            } else {
+                current = left.as_deref_mut();
+            }
+        }
+        found
+    }
+
+    /// Returns smallest key not less than `key` with mutable access to
+    /// its value, same descent as `BinarySearchTree::ceiling_bound()`.
+    /// Uses `O(n)` time.
+    pub fn ceiling_entry_mut(&mut self, key: &K) -> Option<(&K, &mut V)> {
+        let mut found = None;
+        let mut current = self.root.as_deref_mut();
+        while let Some(node) = current {
+            let BinarySearchTree { val, left, right } = node;
+            if before_start(Bound::Included(key), &val.key) {
+                current = right.as_deref_mut();
+            } else {
+                found = Some((&val.key, &mut val.value));
+                current = left.as_deref_mut();
+            }
+        }
+        found
+    }
+
+    /// Iterates pairs in order of keys with mutable access to values,
+    /// keys stay immutable so order of map cannot be broken.
+    pub fn iter_mut(&mut self) -> BstMapIterMut<'_, K, V> {
+        let mut iter = BstMapIterMut { stack: Vec::new() };
+        iter.push_left(self.root.as_deref_mut());
+        iter
+    }
+
+    /// Iterates pairs whose keys lie within `range` with mutable access
+    /// to values, e.g. `map.range_mut(a..b)`. Stack is seeded by searching
+    /// for start of range as `BinarySearchTree::range()` does and walk stops
+    /// at first key after its end, so other sub trees are never entered.
+    /// Uses `O(h + k)` time for `k` pairs yielded.
+    pub fn range_mut<'a, R>(&'a mut self, range: R) -> impl Iterator<Item = (&'a K, &'a mut V)> + 'a
+        where
+            R: RangeBounds<K> + 'a
+    {
+        let mut iter = BstMapIterMut { stack: Vec::new() };
+        let mut node = self.root.as_deref_mut();
+        while let Some(n) = node {
+            let BinarySearchTree { val, left, right } = n;
+            if before_start(range.start_bound(), &val.key) {
+                node = right.as_deref_mut();
+            } else {
+                iter.stack.push((&val.key, &mut val.value, right.as_deref_mut()));
+                node = left.as_deref_mut();
+            }
+        }
+        iter.take_while(move |(k, _)| before_end(range.end_bound(), *k))
+    }
+
+    /// Iterates mutable values in order of their keys, payloads are
+    /// updated in place without removing and inserting them again.
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut V> + '_ {
+        self.iter_mut().map(|(_, v)| v)
+    }
+
+    /// Converts every value with `f`, called in order of keys. Nodes are
+    /// rebuilt one by one in the same shape, so keys are neither compared
+    /// nor moved between sub trees.
+    /// Uses `O(n)` time.
+    pub fn map_values<U, F>(self, mut f: F) -> BstMap<K, U>
+        where
+            F: FnMut(V) -> U
+    {
+        fn convert<K, V, U, F: FnMut(V) -> U>(link: Link<Entry<K, V>>, f: &mut F) -> Link<Entry<K, U>> {
+            let node = *link?;
+            let left = convert(node.left, f);
+            let val = Entry { key: node.val.key, value: f(node.val.value), acc: () };
+            let right = convert(node.right, f);
+            Some(Box::new(BinarySearchTree { val, left, right }))
+        }
+
+        BstMap { root: convert(self.root, &mut f), len: self.len }
+    }
+}
+
+impl<K: PartialOrd, V, F: Fold<V>> BstMap<K, V, F> {
+    /// Number of entries.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Checks if map has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Inserts a pair, returns previous value if key existed. Folds of
+    /// nodes on the path are updated on the way back up.
+    /// Uses `O(n)` time.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        fn insert<K: PartialOrd, V, F: Fold<V>>(link: &mut Link<Entry<K, V, F::Acc>>, key: K, value: V) -> Option<V> {
+            let node = match link {
+                None => {
+                    let acc = F::from_value(&value);
+                    *link = Some(Box::new(BinarySearchTree::new(Entry { key, value, acc })));
+                    return None;
+                },
+                Some(node) => node
+            };
+            let old = match node.val.key.partial_cmp(&key) {
+                Some(Ordering::Equal) => Some(std::mem::replace(&mut node.val.value, value)),
+                Some(Ordering::Greater) => insert::<K, V, F>(&mut node.left, key, value),
+                _ => insert::<K, V, F>(&mut node.right, key, value)
+            };
+            update::<K, V, F>(node);
+            old
+        }
+
+        let old = insert::<K, V, F>(&mut self.root, key, value);
+        if old.is_none() {
+            self.len += 1;
+        }
+        old
+    }
+
+    fn find(&self, key: &K) -> Option<&Entry<K, V, F::Acc>> {
+        self.find_by(|k| k.partial_cmp(key))
+    }
+
+    /// Finds entry whose key `order` compares as equal, descending by
+    /// what it returns for keys on the way.
+    fn find_by<O>(&self, order: O) -> Option<&Entry<K, V, F::Acc>>
+        where
+            O: Fn(&K) -> Option<Ordering>
+    {
+        let mut current = self.root.as_deref();
+        while let Some(node) = current {
+            current = match order(&node.val.key) {
+                Some(Ordering::Equal) => return Some(&node.val),
+                Some(Ordering::Greater) => node.left.as_deref(),
+                Some(Ordering::Less) => node.right.as_deref(),
+                None => None
+            };
+        }
+        None
+    }
+
+    /// Returns value stored under `key`.
+    /// Uses `O(n)` time.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.find(key).map(|e| &e.value)
+    }
+
+    /// Checks if `key` is stored.
+    /// Uses `O(n)` time.
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.find(key).is_some()
+    }
+
+    /// Removes `key`, returns its value if it existed. In-order successor
+    /// of removed node takes its place and folds of nodes on the path are
+    /// updated on the way back up.
+    /// Uses `O(n)` time.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        fn remove<K: PartialOrd, V, F: Fold<V>>(link: &mut Link<Entry<K, V, F::Acc>>, key: &K) -> Option<V> {
+            let node = link.as_mut()?;
+            let value = match node.val.key.partial_cmp(key)? {
+                Ordering::Greater => remove::<K, V, F>(&mut node.left, key)?,
+                Ordering::Less => remove::<K, V, F>(&mut node.right, key)?,
+                Ordering::Equal => match pop_min::<K, V, F>(&mut node.right) {
+                    None => {
+                        let left = node.left.take();
+                        return std::mem::replace(link, left).map(|n| n.val.value);
+                    },
+                    Some(successor) => std::mem::replace(&mut node.val, successor.val).value
+                }
+            };
+            update::<K, V, F>(node);
+            Some(value)
+        }
+
+        let value = remove::<K, V, F>(&mut self.root, key)?;
+        self.len -= 1;
+        Some(value)
+    }
+
+    /// Iterates pairs in order of keys.
+    pub fn iter(&self) -> BstMapIter<'_, K, V, F> {
+        BstMapIter { inner: self.root.as_deref().map(|root| root.iter()) }
+    }
+
+    /// Consumes map into parallel vectors of keys and values sorted by
+    /// key, filled in one in-order pass without pairing them up first.
+    /// Uses `O(n)` time.
+    pub fn into_columns(self) -> (Vec<K>, Vec<V>) {
+        let mut keys = Vec::with_capacity(self.len);
+        let mut values = Vec::with_capacity(self.len);
+        if let Some(root) = self.root {
+            for entry in root.into_sorted_iter() {
+                keys.push(entry.key);
+                values.push(entry.value);
+            }
+        }
+        (keys, values)
+    }
+
+    /// Iterates keys in sorted order.
+    pub fn keys(&self) -> impl Iterator<Item = &K> + '_ {
+        self.iter().map(|(k, _)| k)
+    }
+
+    /// Iterates values in order of their keys.
+    pub fn values(&self) -> impl Iterator<Item = &V> + '_ {
+        self.iter().map(|(_, v)| v)
+    }
+}
+
+impl<K: PartialOrd, V: Copy + Default + Add<Output = V>> BstMap<K, V, Sum> {
+    /// Sums values of all keys not greater than `key`, `V::default()`
+    /// when there is none. Walk descends towards `key` adding stored sum
+    /// of left sub tree and value of each node where it turns right.
+    /// Uses `O(h)` time.
+    pub fn prefix_sum(&self, key: &K) -> V {
+        let mut sum = V::default();
+        let mut current = self.root.as_deref();
+        while let Some(node) = current {
+            if before_end(Bound::Included(key), &node.val.key) {
+                sum = sum + acc::<K, V, Sum>(&node.left) + node.val.value;
+                current = node.right.as_deref();
+            } else {
+                current = node.left.as_deref();
+            }
+        }
+        sum
+    }
+}
+
+/// Map keyed by strings ordered by a `Collation` preset.
+impl<C: Collation, V> BstMap<Collated<C>, V> {
+    /// Contructor creates an empty map whose string keys are ordered by
+    /// given collation, e.g. `BstMap::with_collation(CaseFold)`, which wraps
+    /// keys itself.
+    pub fn with_collation(_: C) -> BstMap<Collated<C>, V> {
+        BstMap::new()
+    }
+}
+
+impl<C: Collation, V, F: Fold<V>> BstMap<Collated<C>, V, F> {
+    /// Inserts a pair under a copy of `key`, returns previous value if an
+    /// equal key existed. That key is kept as it was first inserted.
+    /// Uses `O(n)` time.
+    pub fn insert_str(&mut self, key: &str, value: V) -> Option<V> {
+        self.insert(Collated::new(key), value)
+    }
+
+    /// Returns value stored under a key equal to `key`, looking it up by
+    /// `&str` so no key is allocated.
+    /// Uses `O(n)` time.
+    pub fn get_str(&self, key: &str) -> Option<&V> {
+        self.find_by(|k| Some(C::compare(k, key))).map(|e| &e.value)
+    }
+}
+
+/// History of a value by time, values of a `BstMap<K, Versioned<V>>`
+/// are looked up as of a point in time with `get_at()`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Versioned<V> {
+    /// Values with times they were set from, in order of time.
+    history: Vec<(u64, V)>
+}
+
+impl<V> Versioned<V> {
+    /// Value set last at or before time `t`.
+    /// Uses `O(log k)` time for `k` versions.
+    pub fn at(&self, t: u64) -> Option<&V> {
+        match self.history.partition_point(|(at, _)| *at <= t) {
+            0 => None,
+            i => Some(&self.history[i - 1].1)
+        }
+    }
+
+    /// Most recent value.
+    pub fn latest(&self) -> Option<&V> {
+        self.history.last().map(|(_, v)| v)
+    }
+
+    /// Versions in order of time.
+    pub fn history(&self) -> &[(u64, V)] {
+        &self.history
+    }
+}
+
+/// Versioned mode of a map, every key keeps its values by time.
+impl<K: PartialOrd, V> BstMap<K, Versioned<V>> {
+    /// Sets `value` of `key` from time `t` on, a value already set at
+    /// exactly `t` is replaced. Times need not come in order.
+    /// Uses `O(n + k)` time for `k` versions of key.
+    pub fn insert_at(&mut self, key: K, t: u64, value: V) {
+        if self.get(&key).is_none() {
+            self.insert(key, Versioned { history: vec![(t, value)] });
+            return;
+        }
+        let history = &mut self.get_mut(&key).unwrap().history;
+        let i = history.partition_point(|(at, _)| *at < t);
+        match history.get_mut(i) {
+            Some((at, v)) if *at == t => *v = value,
+            _ => history.insert(i, (t, value))
+        }
+    }
+
+    /// Returns value of `key` as of time `t`, `None` if key did not
+    /// exist yet.
+    /// Uses `O(n + log k)` time for `k` versions of key.
+    pub fn get_at(&self, key: &K, t: u64) -> Option<&V> {
+        self.get(key)?.at(t)
+    }
+}
+
+impl<K: PartialOrd, V, F: Fold<V>> Default for BstMap<K, V, F> {
+    fn default() -> Self {
+        BstMap { root: None, len: 0 }
+    }
+}
+
+/// Iterator returned by `BstMap::iter()`.
+pub struct BstMapIter<'a, K, V, F: Fold<V> = ()> {
+    inner: Option<BinarySearchTreeIter<'a, Entry<K, V, F::Acc>>>
+}
+
+impl<'a, K, V, F: Fold<V>> Iterator for BstMapIter<'a, K, V, F> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.as_mut()?.next().map(|e| (&e.key, &e.value))
+    }
+}
+
+impl<'a, K, V, F: Fold<V>> DoubleEndedIterator for BstMapIter<'a, K, V, F> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.as_mut()?.next_back().map(|e| (&e.key, &e.value))
+    }
+}
+
+/// Pair not yielded yet with right sub tree of its node.
+type Pending<'a, K, V> = (&'a K, &'a mut V, Option<&'a mut Node<K, V>>);
+
+/// Iterator returned by `BstMap::iter_mut()`.
+pub struct BstMapIterMut<'a, K, V> {
+    /// Pairs along left spine of next node.
+    stack: Vec<Pending<'a, K, V>>
+}
+
+impl<'a, K, V> BstMapIterMut<'a, K, V> {
+    fn push_left(&mut self, mut node: Option<&'a mut Node<K, V>>) {
+        while let Some(n) = node {
+            let BinarySearchTree { val, left, right } = n;
+            self.stack.push((&val.key, &mut val.value, right.as_deref_mut()));
+            node = left.as_deref_mut();
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for BstMapIterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (key, value, right) = self.stack.pop()?;
+        self.push_left(right);
+        Some((key, value))
+    }
+}
+
+/// Inserts every pair, later values win for repeated keys.
+impl<K: PartialOrd, V, F: Fold<V>> Extend<(K, V)> for BstMap<K, V, F> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+impl<K: PartialOrd, V, F: Fold<V>> FromIterator<(K, V)> for BstMap<K, V, F> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = BstMap::default();
+        map.extend(iter);
+        map
+    }
+}
+
+/// Consumes map into pairs in order of keys.
+impl<K: PartialOrd, V, F: Fold<V>> IntoIterator for BstMap<K, V, F> {
+    type Item = (K, V);
+    type IntoIter = std::vec::IntoIter<(K, V)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let pairs: Vec<(K, V)> = match self.root {
+            None => Vec::new(),
+            Some(root) => root.into_sorted_vec().into_iter().map(|e| (e.key, e.value)).collect()
+        };
+        pairs.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BstMap, Entry, Link, Versioned};
+    use crate::fold::Sum;
+
+    #[test]
+    fn map() {
+        let mut map: BstMap<String, Vec<u32>> = BstMap::new();
+        for (i, word) in ["pear", "apple", "fig", "apple", "kiwi", "pear"].iter().enumerate() {
+            match map.get_mut(&word.to_string()) {
+                Some(seen) => seen.push(i as u32),
+                None => { map.insert(word.to_string(), vec![i as u32]); }
+            }
+        }
+        assert_eq!(map.len(), 4);
+        assert_eq!(map.get(&"apple".to_string()), Some(&vec![1, 3]));
+        assert_eq!(map.keys().cloned().collect::<Vec<_>>(), ["apple", "fig", "kiwi", "pear"]);
+
+        assert_eq!(map.remove(&"pear".to_string()), Some(vec![0, 5]));
+        assert_eq!(map.remove(&"pear".to_string()), None);
+        assert_eq!(map.insert("fig".to_string(), vec![]), Some(vec![2]));
+        assert!(!map.contains_key(&"pear".to_string()));
+        assert_eq!(map.iter().next_back().map(|(k, _)| k.as_str()), Some("kiwi"));
+
+        let pairs: Vec<_> = map.into_iter().map(|(k, v)| (k, v.len())).collect();
+        assert_eq!(pairs, [("apple".to_string(), 2), ("fig".to_string(), 0), ("kiwi".to_string(), 1)]);
+    }
+    #[test]
+    fn values_mut() {
+        let mut map: BstMap<_, _> = [(3, 30), (1, 10), (2, 20), (5, 50)].into_iter().collect();
+        for v in map.values_mut() {
+            *v += 1;
+        }
+        let mut seen = Vec::new();
+        for (k, v) in map.iter_mut() {
+            seen.push(*k);
+            *v *= *k;
+        }
+        assert_eq!(seen, [1, 2, 3, 5]);
+        assert_eq!(map.values().copied().collect::<Vec<_>>(), [11, 42, 93, 255]);
+        assert_eq!(BstMap::<i32, i32>::new().values_mut().count(), 0);
+    }
+    #[test]
+    fn range_mut() {
+        let mut map: BstMap<_, _> = [50, 30, 70, 20, 40, 60, 80].into_iter().map(|k| (k, k)).collect();
+        for (_, v) in map.range_mut(35..=60) {
+            *v += 1;
+        }
+        assert_eq!(map.values().copied().collect::<Vec<_>>(), [20, 30, 41, 51, 61, 70, 80]);
+        assert_eq!(map.range_mut(..).count(), 7);
+        assert_eq!(map.range_mut(81..).count(), 0);
+        assert_eq!(map.range_mut(41..50).count(), 0);
+        assert_eq!(map.range_mut(..=20).map(|(k, _)| *k).collect::<Vec<_>>(), [20]);
+    }
+    #[test]
+    fn map_values() {
+        let map: BstMap<_, _> = [(2, "bb"), (1, "a"), (3, "ccc")].into_iter().collect();
+        let mut order = Vec::new();
+        let lens = map.map_values(|v| {
+            order.push(v);
+            v.len()
+        });
+        assert_eq!(order, ["a", "bb", "ccc"]);
+        assert_eq!(lens.len(), 3);
+        assert_eq!(lens.get(&2), Some(&2));
+        assert_eq!(lens.iter().collect::<Vec<_>>(), [(&1, &1), (&2, &2), (&3, &3)]);
+        assert!(BstMap::<i32, i32>::new().map_values(|v| v + 1).is_empty());
+    }
+    #[test]
+    fn floor_ceiling_entry_mut() {
+        // free blocks of memory by start address and length
+        let mut free: BstMap<u32, u32> = [(0, 16), (64, 32), (256, 8)].into_iter().collect();
+        if let Some((_, len)) = free.floor_entry_mut(&70) {
+            *len -= 4;
+        }
+        assert_eq!(free.get(&64), Some(&28));
+        assert_eq!(free.floor_entry_mut(&64).map(|(k, _)| *k), Some(64));
+        assert_eq!(free.ceiling_entry_mut(&65).map(|(k, v)| (*k, *v)), Some((256, 8)));
+        assert_eq!(free.ceiling_entry_mut(&0).map(|(k, _)| *k), Some(0));
+        assert!(free.ceiling_entry_mut(&257).is_none());
+        assert!(BstMap::<u32, u32>::new().floor_entry_mut(&1).is_none());
+    }
+    #[test]
+    fn prefix_sum() {
+        let mut sales: BstMap<u32, f64, Sum> = [(3, 1.5), (1, 2.0), (7, 4.0)].into_iter().collect();
+        assert_eq!(sales.prefix_sum(&0), 0.0);
+        assert_eq!(sales.prefix_sum(&3), 3.5);
+        assert_eq!(sales.prefix_sum(&6), 3.5);
+        sales.insert(5, 0.5);
+        assert_eq!(sales.insert(1, 3.0), Some(2.0));
+        assert_eq!(sales.prefix_sum(&6), 5.0);
+        assert_eq!(sales.prefix_sum(&u32::MAX), 9.0);
+    }
+    #[test]
+    fn stored_sums_after_removes() {
+        fn check(link: &Link<Entry<u32, u32, u32>>) -> u32 {
+            match link {
+                None => 0,
+                Some(node) => {
+                    let sum = check(&node.left) + node.val.value + check(&node.right);
+                    assert_eq!(node.val.acc, sum);
+                    sum
+                }
+            }
+        }
+
+        let mut map: BstMap<u32, u32, Sum> = (0..100).map(|k| ((k * 37) % 100, k)).collect();
+        assert_eq!(check(&map.root), 4950);
+        for k in (0..100).step_by(3) {
+            assert!(map.remove(&((k * 11) % 100)).is_some());
+            check(&map.root);
+        }
+        assert_eq!(map.remove(&0), None);
+        let keys: Vec<u32> = map.keys().copied().collect();
+        let mut sum = 0;
+        for k in 0..100 {
+            if keys.contains(&k) {
+                sum += map.get(&k).unwrap();
+            }
+            assert_eq!(map.prefix_sum(&k), sum);
+        }
+        for k in keys {
+            map.remove(&k);
+            check(&map.root);
+        }
+        assert!(map.is_empty());
+        assert_eq!(map.prefix_sum(&99), 0);
+    }
+    #[test]
+    fn versioned() {
+        let mut prices: BstMap<&str, Versioned<u32>> = BstMap::new();
+        prices.insert_at("tea", 10, 3);
+        prices.insert_at("tea", 30, 5);
+        prices.insert_at("tea", 20, 4);
+        prices.insert_at("milk", 15, 2);
+        prices.insert_at("tea", 30, 6);
+
+        assert_eq!(prices.get_at(&"tea", 9), None);
+        assert_eq!(prices.get_at(&"tea", 10), Some(&3));
+        assert_eq!(prices.get_at(&"tea", 25), Some(&4));
+        assert_eq!(prices.get_at(&"tea", 99), Some(&6));
+        assert_eq!(prices.get_at(&"milk", 99), Some(&2));
+        assert_eq!(prices.get_at(&"salt", 99), None);
+        assert_eq!(prices.get(&"tea").map(|h| h.history().len()), Some(3));
+        assert_eq!(prices.get(&"tea").and_then(|h| h.latest()), Some(&6));
+    }
+    #[test]
+    fn into_columns() {
+        let map: BstMap<_, _> = [("b", 2.0), ("c", 3.0), ("a", 1.0)].into_iter().collect();
+        let (keys, values) = map.into_columns();
+        assert_eq!(keys, ["a", "b", "c"]);
+        assert_eq!(values, [1.0, 2.0, 3.0]);
+        assert_eq!(BstMap::<u8, u8>::new().into_columns(), (vec![], vec![]));
+    }
+    #[test]
+    fn remove_all() {
+        let mut map: BstMap<_, _> = (0..50).map(|k| ((k * 17) % 50, k)).collect();
+        for k in 0..50 {
+            assert!(map.remove(&((k * 31) % 50)).is_some());
+        }
+        assert!(map.is_empty());
+        assert_eq!(map.iter().count(), 0);
+    }
+}
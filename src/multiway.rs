@@ -0,0 +1,407 @@
+/// Minimum degree, every node except root holds between
+/// `DEGREE - 1` and `2 * DEGREE - 1` keys.
+const DEGREE: usize = 6;
+const MAX_KEYS: usize = 2 * DEGREE - 1;
+
+struct Node<T> {
+    keys: Vec<T>,
+    children: Vec<Node<T>>
+}
+
+impl<T> Node<T> {
+    fn leaf(keys: Vec<T>) -> Node<T> {
+        Node { keys, children: Vec::new() }
+    }
+
+    fn is_leaf(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    fn is_full(&self) -> bool {
+        self.keys.len() == MAX_KEYS
+    }
+}
+
+/// Implements a B-tree style search tree which keeps several sorted keys
+/// per node.
+///
+/// It offers same set operations as `BinarySearchTree` while needing
+/// about a tenth of the nodes, so lookups chase far fewer pointers and
+/// inserts allocate far less often. Every leaf is at the same depth.
+///
+/// # Example
+///
+/// ```rust
+/// use ds_bst::MultiWayTree;
+///
+/// let mut root = MultiWayTree::from(vec![5, 1, 9, 3]);
+/// root.insert(7);
+///
+/// assert!(root.exists(7));
+/// assert_eq!(root.inorder(), vec![1, 3, 5, 7, 9]);
+/// assert_eq!(root.find_max(), 9);
+/// ```
+pub struct MultiWayTree<T> {
+    root: Node<T>,
+    len: usize
+}
+
+/// Iterator over elements of a `MultiWayTree` in sorted order.
+///
+/// Keeps a stack of nodes on the path to the next element, each with
+/// index of its next key.
+pub struct MultiWayTreeIter<'a, T> {
+    stack: Vec<(&'a Node<T>, usize)>
+}
+
+impl<T: PartialOrd + Copy> MultiWayTree<T> {
+    /// Contructor creates MultiWayTree with a single element
+    pub fn new(v: T) -> MultiWayTree<T> {
+        MultiWayTree {
+            root: Node::leaf(vec![v]),
+            len: 1
+        }
+    }
+
+    /// Builds tree from vector input.
+    ///
+    /// # Panics
+    /// Panics if vector is empty.
+    pub fn from(mut data: Vec<T>) -> MultiWayTree<T> {
+        data.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+        let mut values = data.into_iter();
+        let mut root = match values.next() {
+            None => { panic!("Empty node"); },
+            Some(v) => MultiWayTree::new(v)
+        };
+        for v in values {
+            root.insert(v);
+        }
+        root
+    }
+
+    /// Number of elements, every copy of a duplicate counts.
+    /// Uses `O(1)` time.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Checks if tree has no elements, never true as a tree holds at
+    /// least one.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Inserts an element in a tree.
+    /// Uses `O(log n)` time.
+    pub fn insert(&mut self, val: T) {
+        self.len += 1;
+        if self.root.is_full() {
+            let old = std::mem::replace(&mut self.root, Node::leaf(Vec::new()));
+            self.root.children.push(old);
+            MultiWayTree::split_child(&mut self.root, 0);
+        }
+
+        let mut node = &mut self.root;
+        loop {
+            let mut i = node.keys.partition_point(|k| *k <= val);
+            if node.is_leaf() {
+                node.keys.insert(i, val);
+                return;
+            }
+            if node.children[i].is_full() {
+                MultiWayTree::split_child(node, i);
+                if node.keys[i] <= val {
+                    i += 1;
+                }
+            }
+            node = &mut node.children[i];
+        }
+    }
+
+    /// Splits full `i`-th child of `parent` around its median key.
+    fn split_child(parent: &mut Node<T>, i: usize) {
+        let child = &mut parent.children[i];
+        let keys = child.keys.split_off(DEGREE);
+        let children = if child.is_leaf() { Vec::new() } else { child.children.split_off(DEGREE) };
+        let median = child.keys.pop().unwrap();
+
+        parent.keys.insert(i, median);
+        parent.children.insert(i + 1, Node { keys, children });
+    }
+
+    /// Removes one copy of `val`, returns `false` if it does not exist or
+    /// is the only element, a tree cannot be empty. Children are refilled
+    /// from a sibling or merged on the way down, so a single pass does.
+    /// Uses `O(log n)` time.
+    pub fn remove(&mut self, val: T) -> bool {
+        if self.len == 1 || !remove(&mut self.root, val) {
+            return false;
+        }
+        if self.root.keys.is_empty() {
+            self.root = self.root.children.remove(0);
+        }
+        self.len -= 1;
+        true
+    }
+
+    /// Iterates elements in sorted order.
+    pub fn iter(&self) -> MultiWayTreeIter<'_, T> {
+        let mut iter = MultiWayTreeIter { stack: Vec::new() };
+        iter.push_left(&self.root);
+        iter
+    }
+
+    /// Checks if element exists in a tree.
+    /// Uses `O(log n)` time.
+    pub fn exists(&self, val: T) -> bool {
+        let mut node = &self.root;
+        loop {
+            let i = node.keys.partition_point(|k| *k < val);
+            if i < node.keys.len() && node.keys[i] == val {
+                return true;
+            }
+            if node.is_leaf() {
+                return false;
+            }
+            node = &node.children[i];
+        }
+    }
+
+    /// Finds minimum element in a tree.
+    /// Uses `O(log n)` time.
+    pub fn find_min(&self) -> T {
+        let mut node = &self.root;
+        while !node.is_leaf() {
+            node = &node.children[0];
+        }
+        node.keys[0]
+    }
+
+    /// Finds maximum element in a tree.
+    /// Uses `O(log n)` time.
+    pub fn find_max(&self) -> T {
+        let mut node = &self.root;
+        while let Some(child) = node.children.last() {
+            node = child;
+        }
+        node.keys[node.keys.len() - 1]
+    }
+
+    /// Inorder traverse tree which yields elements in sorted order.
+    /// Uses `O(n)` time.
+    pub fn inorder(&self) -> Vec<T> {
+        fn walk<T: Copy>(node: &Node<T>, ret: &mut Vec<T>) {
+            for (i, key) in node.keys.iter().enumerate() {
+                if let Some(child) = node.children.get(i) {
+                    walk(child, ret);
+                }
+                ret.push(*key);
+            }
+            if let Some(child) = node.children.get(node.keys.len()) {
+                walk(child, ret);
+            }
+        }
+
+        let mut ret = Vec::new();
+        walk(&self.root, &mut ret);
+        ret
+    }
+
+    /// Calculates tree height in nodes, every leaf is at this depth.
+    pub fn height(&self) -> usize {
+        let mut node = &self.root;
+        let mut height = 1;
+        while let Some(child) = node.children.first() {
+            node = child;
+            height += 1;
+        }
+        height
+    }
+}
+
+/// Removes one copy of `val` below `node`, which holds at least
+/// `DEGREE` keys unless it is root.
+fn remove<T: PartialOrd + Copy>(node: &mut Node<T>, val: T) -> bool {
+    let mut i = node.keys.partition_point(|k| *k < val);
+    if i < node.keys.len() && node.keys[i] == val {
+        if node.is_leaf() {
+            node.keys.remove(i);
+            return true;
+        }
+        if node.children[i].keys.len() >= DEGREE {
+            let pred = max_key(&node.children[i]);
+            node.keys[i] = pred;
+            return remove(&mut node.children[i], pred);
+        }
+        if node.children[i + 1].keys.len() >= DEGREE {
+            let succ = min_key(&node.children[i + 1]);
+            node.keys[i] = succ;
+            return remove(&mut node.children[i + 1], succ);
+        }
+        merge_children(node, i);
+        return remove(&mut node.children[i], val);
+    }
+    if node.is_leaf() {
+        return false;
+    }
+
+    if node.children[i].keys.len() < DEGREE {
+        if i > 0 && node.children[i - 1].keys.len() >= DEGREE {
+            let (left, right) = node.children.split_at_mut(i);
+            let (left, child) = (&mut left[i - 1], &mut right[0]);
+            let key = std::mem::replace(&mut node.keys[i - 1], left.keys.pop().unwrap());
+            child.keys.insert(0, key);
+            if let Some(c) = left.children.pop() {
+                child.children.insert(0, c);
+            }
+        } else if i + 1 < node.children.len() && node.children[i + 1].keys.len() >= DEGREE {
+            let (left, right) = node.children.split_at_mut(i + 1);
+            let (child, right) = (&mut left[i], &mut right[0]);
+            let key = std::mem::replace(&mut node.keys[i], right.keys.remove(0));
+            child.keys.push(key);
+            if !right.is_leaf() {
+                child.children.push(right.children.remove(0));
+            }
+        } else if i + 1 < node.children.len() {
+            merge_children(node, i);
+        } else {
+            merge_children(node, i - 1);
+            i -= 1;
+        }
+    }
+    remove(&mut node.children[i], val)
+}
+
+/// Merges `i + 1`-th child of `node` and key between them into `i`-th.
+fn merge_children<T>(node: &mut Node<T>, i: usize) {
+    let key = node.keys.remove(i);
+    let right = node.children.remove(i + 1);
+    let child = &mut node.children[i];
+    child.keys.push(key);
+    child.keys.extend(right.keys);
+    child.children.extend(right.children);
+}
+
+fn min_key<T: Copy>(mut node: &Node<T>) -> T {
+    while let Some(child) = node.children.first() {
+        node = child;
+    }
+    node.keys[0]
+}
+
+fn max_key<T: Copy>(mut node: &Node<T>) -> T {
+    while let Some(child) = node.children.last() {
+        node = child;
+    }
+    node.keys[node.keys.len() - 1]
+}
+
+impl<'a, T> MultiWayTreeIter<'a, T> {
+    fn push_left(&mut self, mut node: &'a Node<T>) {
+        loop {
+            self.stack.push((node, 0));
+            match node.children.first() {
+                None => return,
+                Some(child) => node = child
+            }
+        }
+    }
+}
+
+impl<'a, T> Iterator for MultiWayTreeIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (node, i) = self.stack.last_mut()?;
+            let node: &'a Node<T> = node;
+            if *i < node.keys.len() {
+                let key = &node.keys[*i];
+                *i += 1;
+                if let Some(child) = node.children.get(*i) {
+                    self.push_left(child);
+                }
+                return Some(key);
+            }
+            self.stack.pop();
+        }
+    }
+}
+
+impl<'a, T: PartialOrd + Copy> IntoIterator for &'a MultiWayTree<T> {
+    type Item = &'a T;
+    type IntoIter = MultiWayTreeIter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MultiWayTree, Node, DEGREE, MAX_KEYS};
+
+    fn assert_valid<T: PartialOrd>(node: &Node<T>, is_root: bool) {
+        assert!(node.keys.len() <= MAX_KEYS);
+        assert!(is_root || node.keys.len() >= DEGREE - 1);
+        assert!(node.keys.windows(2).all(|w| w[0] <= w[1]));
+        if !node.is_leaf() {
+            assert_eq!(node.children.len(), node.keys.len() + 1);
+            for child in &node.children {
+                assert_valid(child, false);
+            }
+        }
+    }
+
+    #[test]
+    fn build() {
+        let mut root = MultiWayTree::new(0);
+        for v in 1..2000 {
+            root.insert(v);
+        }
+        assert_valid(&root.root, true);
+        assert_eq!(root.inorder(), (0..2000).collect::<Vec<_>>());
+        assert_eq!(root.find_min(), 0);
+        assert_eq!(root.find_max(), 1999);
+        assert!(root.height() <= 5);
+        assert!(root.exists(1234));
+        assert!(!root.exists(2000));
+    }
+    #[test]
+    fn duplicates() {
+        let mut root = MultiWayTree::from(vec![3, 3, 1]);
+        for _ in 0..30 {
+            root.insert(2);
+        }
+        assert_valid(&root.root, true);
+        assert_eq!(root.inorder().iter().filter(|&&v| v == 2).count(), 30);
+        assert!(root.exists(3));
+        assert!(!root.exists(4));
+    }
+    #[test]
+    fn remove() {
+        let mut root = MultiWayTree::new(0);
+        for v in 1..1000 {
+            root.insert((v * 37) % 500);
+        }
+        assert_eq!(root.len(), 1000);
+        assert!(root.iter().copied().eq(root.inorder()));
+
+        for v in (0..500).rev() {
+            assert!(root.remove(v));
+            assert_valid(&root.root, true);
+        }
+        assert!(!root.remove(500));
+        assert_eq!(root.len(), 500);
+        assert_eq!(root.inorder(), (0..500).collect::<Vec<_>>());
+
+        for v in 0..499 {
+            assert!(root.remove(v));
+        }
+        assert_valid(&root.root, true);
+        assert!(!root.remove(499));
+        assert_eq!((root.len(), root.height()), (1, 1));
+        assert_eq!((&root).into_iter().collect::<Vec<_>>(), vec![&499]);
+    }
+}
@@ -0,0 +1,185 @@
+//! Process wide hook observing tree operations.
+//!
+//! A registered hook is called after each observed call with kind of
+//! operation, number of nodes visited and time it took, e.g. to feed
+//! latency histograms. Without a hook the cost is a single atomic load.
+//!
+//! Observed calls are lookups, inserts, removes and range queries of
+//! `BinarySearchTree` and `Tree` listed in `OpKind`. Other calls which
+//! insert through `insert()`, such as `insert_full()` or `extend()`,
+//! report each `Insert`, and `apply()` reports operation it runs.
+//! Bulk construction, traversals, set size queries, exports and batch
+//! or rebuild helpers such as `apply_sorted_updates()` are not observed,
+//! and neither are other tree types of this crate.
+//!
+//! Available with `observe` feature.
+#[cfg(feature = "observe")]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "observe")]
+use std::sync::{Arc, RwLock};
+#[cfg(feature = "observe")]
+use std::time::{Duration, Instant};
+
+/// Kind of operation passed to the hook.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OpKind {
+    /// `BinarySearchTree::insert()` and `Tree::insert()`.
+    Insert,
+    /// `BinarySearchTree::exists()`.
+    Exists,
+    /// `BinarySearchTree::find_min()`.
+    FindMin,
+    /// `BinarySearchTree::find_max()`.
+    FindMax,
+    /// `BinarySearchTree::contains()` and `Tree::contains()`.
+    Contains,
+    /// `BinarySearchTree::get()`.
+    Get,
+    /// `BinarySearchTree::floor()`.
+    Floor,
+    /// `BinarySearchTree::ceiling()`.
+    Ceiling,
+    /// `BinarySearchTree::count()`.
+    Count,
+    /// `BinarySearchTree::nth_from()`, depth counts search for start only.
+    NthFrom,
+    /// `BinarySearchTree::range()`, depth counts search for both ends
+    /// and elapsed time excludes iteration.
+    Range,
+    /// `BinarySearchTree::range_count()` and `Tree::range_count()`.
+    RangeCount,
+    /// `Tree::remove()`.
+    Remove,
+    /// `Tree::pop_range()`.
+    PopRange
+}
+
+#[cfg(feature = "observe")]
+type Hook = Arc<dyn Fn(OpKind, usize, Duration) + Send + Sync>;
+
+#[cfg(feature = "observe")]
+static HOOKED: AtomicBool = AtomicBool::new(false);
+#[cfg(feature = "observe")]
+static HOOK: RwLock<Option<Hook>> = RwLock::new(None);
+
+/// Registers `hook`, replacing previous one. It receives kind of
+/// operation, number of nodes visited and elapsed time. No lock is
+/// held while it runs, so it may call `set_hook()` or `clear_hook()`.
+///
+/// # Example
+///
+/// ```rust
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use ds_bst::{observe, BinarySearchTree, OpKind};
+///
+/// static INSERTS: AtomicUsize = AtomicUsize::new(0);
+///
+/// observe::set_hook(|kind, _depth, _elapsed| {
+///     if kind == OpKind::Insert {
+///         INSERTS.fetch_add(1, Ordering::Relaxed);
+///     }
+/// });
+/// let mut root = BinarySearchTree::new(5);
+/// root.insert(3);
+/// observe::clear_hook();
+///
+/// assert!(INSERTS.load(Ordering::Relaxed) >= 1);
+/// ```
+#[cfg(feature = "observe")]
+pub fn set_hook<F>(hook: F)
+    where
+        F: Fn(OpKind, usize, Duration) + Send + Sync + 'static
+{
+    *HOOK.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(hook));
+    HOOKED.store(true, Ordering::Release);
+}
+
+/// Removes registered hook.
+#[cfg(feature = "observe")]
+pub fn clear_hook() {
+    HOOKED.store(false, Ordering::Release);
+    *HOOK.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// Measures one operation, it does nothing without `observe` feature
+/// or when no hook is registered.
+pub(crate) struct Timer {
+    #[cfg(feature = "observe")]
+    start: Option<Instant>
+}
+
+impl Timer {
+    #[inline]
+    pub(crate) fn start() -> Timer {
+        Timer {
+            #[cfg(feature = "observe")]
+            start: HOOKED.load(Ordering::Acquire).then(Instant::now)
+        }
+    }
+
+    #[cfg(feature = "observe")]
+    #[inline]
+    pub(crate) fn finish(self, kind: OpKind, depth: usize) {
+        if let Some(start) = self.start {
+            let elapsed = start.elapsed();
+            // lock is released before calling, so hook may replace itself
+            let hook = HOOK.read().unwrap_or_else(|e| e.into_inner()).clone();
+            if let Some(hook) = hook {
+                hook(kind, depth, elapsed);
+            }
+        }
+    }
+
+    #[cfg(not(feature = "observe"))]
+    #[inline]
+    pub(crate) fn finish(self, _kind: OpKind, _depth: usize) {}
+}
+
+#[cfg(all(test, feature = "observe"))]
+mod tests {
+    use std::sync::Mutex;
+    use super::{clear_hook, set_hook, OpKind};
+    use crate::bst::{BinarySearchTree, Tree};
+
+    static SEEN: Mutex<Vec<(OpKind, usize)>> = Mutex::new(Vec::new());
+
+    #[test]
+    fn hook() {
+        let mut root = BinarySearchTree::from((1..=7).collect());
+        // other tests may run trees on other threads meanwhile
+        let this = std::thread::current().id();
+        set_hook(move |kind, depth, _| {
+            if std::thread::current().id() == this {
+                SEEN.lock().unwrap().push((kind, depth));
+            }
+        });
+        root.insert(8);
+        root.exists(5);
+        root.find_min();
+        root.contains(&6);
+        root.floor(0);
+        root.count(&7);
+        root.range(2..4).count();
+        let mut tree = Tree::from(root);
+        tree.remove(&4);
+        tree.pop_range(..3);
+
+        let seen = std::mem::take(&mut *SEEN.lock().unwrap());
+        for op in [
+            (OpKind::Insert, 3), (OpKind::Exists, 3), (OpKind::FindMin, 3),
+            (OpKind::Contains, 2), (OpKind::Floor, 3), (OpKind::Count, 4),
+            (OpKind::Range, 6), (OpKind::Remove, 1)
+        ] {
+            assert!(seen.contains(&op), "{:?} not seen", op);
+        }
+        assert!(seen.iter().any(|&(kind, _)| kind == OpKind::PopRange));
+        // nested calls are not reported again
+        assert_eq!(seen.len(), 9);
+
+        // a hook may replace itself without deadlocking
+        set_hook(|_, _, _| clear_hook());
+        tree.insert(9);
+        tree.insert(10);
+        assert!(tree.contains(&10));
+    }
+}
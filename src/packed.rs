@@ -0,0 +1,193 @@
+/// Number of keys sharing one head and one bit width.
+const BLOCK: usize = 64;
+
+/// Read-only search tree of `u64` keys in a compressed flat layout.
+///
+/// This is the `StaticBst` layout with keys split into blocks: the first
+/// key of each block is stored in full and the search descends over
+/// those heads, the rest are bit-packed differences to the previous key
+/// using as few bits as the largest difference in the block needs.
+/// Keys are decoded on the fly while scanning the one block a lookup
+/// ends in, so dense keys such as ids or timestamps take a fraction of
+/// 8 bytes each.
+///
+/// # Example
+///
+/// ```rust
+/// use ds_bst::PackedStaticBst;
+///
+/// let keys: Vec<u64> = (0..10_000).map(|v| 1_000_000 + v * 3).collect();
+/// let index = PackedStaticBst::from_sorted(&keys);
+///
+/// assert!(index.contains(1_000_300));
+/// assert!(!index.contains(1_000_301));
+/// assert!(index.size_in_bytes() < keys.len() * 2);
+/// ```
+pub struct PackedStaticBst {
+    heads: Vec<u64>,
+    widths: Vec<u8>,
+    offsets: Vec<usize>,
+    bits: Vec<u64>,
+    len: usize
+}
+
+impl PackedStaticBst {
+    /// Contructor packs an already sorted slice of keys.
+    /// Uses `O(n)` time.
+    ///
+    /// # Panics
+    /// Panics if keys are not sorted in ascending order.
+    pub fn from_sorted(keys: &[u64]) -> PackedStaticBst {
+        let mut packed = PackedStaticBst {
+            heads: Vec::new(),
+            widths: Vec::new(),
+            offsets: Vec::new(),
+            bits: Vec::new(),
+            len: keys.len()
+        };
+        let mut pos = 0;
+
+        for block in keys.chunks(BLOCK) {
+            let deltas: Vec<u64> = block.windows(2)
+                .map(|w| w[1].checked_sub(w[0]).expect("Keys are not sorted"))
+                .collect();
+            let width = 64 - deltas.iter().fold(0, |acc, d| acc | d).leading_zeros();
+
+            packed.heads.push(block[0]);
+            packed.widths.push(width as u8);
+            packed.offsets.push(pos);
+            for d in deltas {
+                write_bits(&mut packed.bits, pos, width, d);
+                pos += width as usize;
+            }
+        }
+        packed
+    }
+
+    /// Number of keys.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Checks if index has no keys.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Heap memory used by the packed keys.
+    pub fn size_in_bytes(&self) -> usize {
+        self.heads.len() * 8 + self.widths.len() + self.offsets.len() * 8 + self.bits.len() * 8
+    }
+
+    /// Checks if key exists, the heads are searched first and only
+    /// the block it falls in is decoded.
+    /// Uses `O(log n)` time.
+    pub fn contains(&self, key: u64) -> bool {
+        let block = match self.heads.partition_point(|&h| h <= key) {
+            0 => return false,
+            b => b - 1
+        };
+        self.block(block).take_while(|&k| k <= key).any(|k| k == key)
+    }
+
+    /// Returns key at position `i` in sorted order.
+    /// Uses `O(1)` time.
+    pub fn get(&self, i: usize) -> Option<u64> {
+        if i >= self.len {
+            return None;
+        }
+        self.block(i / BLOCK).nth(i % BLOCK)
+    }
+
+    /// Returns iterator which decodes keys in sorted order.
+    pub fn iter(&self) -> impl Iterator<Item = u64> + '_ {
+        (0..self.heads.len()).flat_map(move |b| self.block(b))
+    }
+
+    fn block(&self, b: usize) -> impl Iterator<Item = u64> + '_ {
+        let width = self.widths[b] as u32;
+        let start = self.offsets[b];
+        let count = usize::min(BLOCK, self.len - b * BLOCK);
+        let mut key = self.heads[b];
+
+        (0..count).map(move |j| {
+            if j > 0 {
+                key += read_bits(&self.bits, start + (j - 1) * width as usize, width);
+            }
+            key
+        })
+    }
+}
+
+fn write_bits(bits: &mut Vec<u64>, pos: usize, width: u32, val: u64) {
+    if width == 0 {
+        return;
+    }
+    let (word, shift) = (pos / 64, pos % 64);
+    while bits.len() <= (pos + width as usize - 1) / 64 {
+        bits.push(0);
+    }
+    bits[word] |= val << shift;
+    if shift + width as usize > 64 {
+        bits[word + 1] |= val >> (64 - shift);
+    }
+}
+
+fn read_bits(bits: &[u64], pos: usize, width: u32) -> u64 {
+    if width == 0 {
+        return 0;
+    }
+    let (word, shift) = (pos / 64, pos % 64);
+    let mut val = bits[word] >> shift;
+    if shift + width as usize > 64 {
+        val |= bits[word + 1] << (64 - shift);
+    }
+    if width == 64 { val } else { val & ((1 << width) - 1) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PackedStaticBst;
+
+    #[test]
+    fn lookups() {
+        let mut keys = Vec::new();
+        let mut key = 5u64;
+        for i in 0..1000u64 {
+            key += (i * 7919) % 97;
+            keys.push(key);
+        }
+        keys.push(u64::MAX - 1);
+        keys.push(u64::MAX);
+        let index = PackedStaticBst::from_sorted(&keys);
+
+        assert_eq!(index.len(), keys.len());
+        assert_eq!(index.iter().collect::<Vec<_>>(), keys);
+        for (i, &k) in keys.iter().enumerate() {
+            assert!(index.contains(k));
+            assert_eq!(index.get(i), Some(k));
+        }
+        assert!(!index.contains(4));
+        for w in keys.windows(2).filter(|w| w[1] - w[0] > 1) {
+            assert!(!index.contains(w[0] + 1));
+        }
+        assert_eq!(index.get(keys.len()), None);
+    }
+    #[test]
+    fn duplicates_and_empty() {
+        let index = PackedStaticBst::from_sorted(&[3; 200]);
+        assert!(index.contains(3));
+        assert!(!index.contains(2));
+        assert_eq!(index.size_in_bytes(), 4 * (8 + 1 + 8));
+
+        let empty = PackedStaticBst::from_sorted(&[]);
+        assert!(empty.is_empty());
+        assert!(!empty.contains(0));
+        assert_eq!(empty.iter().count(), 0);
+    }
+    #[test]
+    #[should_panic]
+    fn unsorted() {
+        PackedStaticBst::from_sorted(&[2, 1]);
+    }
+}
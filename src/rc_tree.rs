@@ -0,0 +1,405 @@
+use std::cell::RefCell;
+use std::cmp::max;
+use std::rc::{Rc, Weak};
+
+type Link<T> = Option<Rc<RefCell<Node<T>>>>;
+
+struct Node<T> {
+    val: T,
+    left: Link<T>,
+    right: Link<T>,
+    parent: Weak<RefCell<Node<T>>>
+}
+
+/// Binary search tree with shared node ownership and parent pointers.
+///
+/// Nodes live in `Rc<RefCell<_>>` and keep a `Weak` reference to their
+/// parent, so a `RcNode` handle can navigate upwards as well as down.
+/// This costs a reference count and a borrow flag per node, use
+/// `BinarySearchTree` when upward navigation is not needed.
+///
+/// Reference counts are not atomic, so neither the tree nor `RcNode`
+/// handles are `Send` or `Sync` and they stay on the thread which
+/// created them.
+///
+/// ```compile_fail
+/// fn assert_send<T: Send>() {}
+/// assert_send::<ds_bst::RcBinarySearchTree<i32>>();
+/// ```
+///
+/// Available with `rc` feature.
+///
+/// # Example
+///
+/// ```rust
+/// use ds_bst::RcBinarySearchTree;
+///
+/// let mut root = RcBinarySearchTree::new(5);
+/// root.insert(3);
+/// let node = root.insert(4);
+///
+/// assert_eq!(node.parent().map(|p| p.value()), Some(3));
+/// assert_eq!(root.inorder(), vec![3, 4, 5]);
+/// ```
+pub struct RcBinarySearchTree<T> {
+    root: Rc<RefCell<Node<T>>>,
+    min: Rc<RefCell<Node<T>>>,
+    max: Rc<RefCell<Node<T>>>
+}
+
+/// Shared handle to a node of `RcBinarySearchTree`.
+pub struct RcNode<T> {
+    node: Rc<RefCell<Node<T>>>
+}
+
+impl<T> Clone for RcNode<T> {
+    fn clone(&self) -> Self {
+        RcNode { node: Rc::clone(&self.node) }
+    }
+}
+
+impl<T: PartialOrd + Copy> RcNode<T> {
+    fn wrap(link: &Link<T>) -> Option<RcNode<T>> {
+        link.as_ref().map(|node| RcNode { node: Rc::clone(node) })
+    }
+
+    /// Value stored in this node.
+    pub fn value(&self) -> T {
+        self.node.borrow().val
+    }
+
+    /// Left child of this node.
+    pub fn left(&self) -> Option<RcNode<T>> {
+        RcNode::wrap(&self.node.borrow().left)
+    }
+
+    /// Right child of this node.
+    pub fn right(&self) -> Option<RcNode<T>> {
+        RcNode::wrap(&self.node.borrow().right)
+    }
+
+    /// Parent of this node, `None` for the root.
+    pub fn parent(&self) -> Option<RcNode<T>> {
+        self.node.borrow().parent.upgrade().map(|node| RcNode { node })
+    }
+
+    /// In-order successor of this node, found through child and parent
+    /// links without looking up keys, so equal elements are never skipped.
+    /// Uses `O(1)` amortized time over a full walk.
+    pub fn next(&self) -> Option<RcNode<T>> {
+        if let Some(mut node) = self.right() {
+            while let Some(left) = node.left() {
+                node = left;
+            }
+            return Some(node);
+        }
+        let mut current = self.clone();
+        loop {
+            let parent = current.parent()?;
+            if parent.left().is_some_and(|l| l.ptr_eq(&current)) {
+                return Some(parent);
+            }
+            current = parent;
+        }
+    }
+
+    /// In-order predecessor of this node, see `next()`.
+    /// Uses `O(1)` amortized time over a full walk.
+    pub fn prev(&self) -> Option<RcNode<T>> {
+        if let Some(mut node) = self.left() {
+            while let Some(right) = node.right() {
+                node = right;
+            }
+            return Some(node);
+        }
+        let mut current = self.clone();
+        loop {
+            let parent = current.parent()?;
+            if parent.right().is_some_and(|r| r.ptr_eq(&current)) {
+                return Some(parent);
+            }
+            current = parent;
+        }
+    }
+
+    /// Checks if both handles point to the same node.
+    pub fn ptr_eq(&self, other: &RcNode<T>) -> bool {
+        Rc::ptr_eq(&self.node, &other.node)
+    }
+}
+
+impl<T: PartialOrd + Copy> RcBinarySearchTree<T> {
+    /// Contructor creates RcBinarySearchTree root node
+    pub fn new(v: T) -> RcBinarySearchTree<T> {
+        let root = Rc::new(RefCell::new(Node {
+            val: v,
+            left: None,
+            right: None,
+            parent: Weak::new()
+        }));
+        RcBinarySearchTree {
+            min: Rc::clone(&root),
+            max: Rc::clone(&root),
+            root
+        }
+    }
+
+    /// Handle to the root node.
+    pub fn root(&self) -> RcNode<T> {
+        RcNode { node: Rc::clone(&self.root) }
+    }
+
+    /// Inserts an element in a tree and returns handle to the new node.
+    /// Uses `O(n)` time.
+    pub fn insert(&mut self, val: T) -> RcNode<T> {
+        let mut current = Rc::clone(&self.root);
+        loop {
+            let next = {
+                let node = current.borrow();
+                if node.val > val { node.left.clone() } else { node.right.clone() }
+            };
+            match next {
+                Some(child) => current = child,
+                None => return self.attach(&current, val)
+            }
+        }
+    }
+
+    /// Inserts an element starting from `hint`, a handle to a node of
+    /// this tree close to where `val` belongs, like C++ `std::set::insert`
+    /// with a hint. When `val` fits right next to the hinted node it is
+    /// attached there directly, otherwise or when `hint` belongs to
+    /// another tree it falls back to `insert()` from root. Passing the
+    /// previously inserted node saves key comparisons for sorted or
+    /// mostly sorted streams, though checking where `hint` belongs walks
+    /// parent links up to root.
+    /// Uses `O(h)` time.
+    pub fn insert_with_hint(&mut self, hint: &RcNode<T>, val: T) -> RcNode<T> {
+        let node = &hint.node;
+        if !self.owns(node) {
+            return self.insert(val);
+        }
+        let (hint_val, has_left, has_right) = {
+            let n = node.borrow();
+            (n.val, n.left.is_some(), n.right.is_some())
+        };
+
+        let fits = if hint_val > val {
+            !has_left && self.predecessor(node).is_none_or(|p| p.borrow().val <= val)
+        } else {
+            !has_right && self.successor(node).is_none_or(|s| s.borrow().val > val)
+        };
+        if fits {
+            self.attach(node, val)
+        } else {
+            self.insert(val)
+        }
+    }
+
+    /// Checks if `node` hangs below root of this tree.
+    fn owns(&self, node: &Rc<RefCell<Node<T>>>) -> bool {
+        let mut current = Rc::clone(node);
+        loop {
+            let parent = current.borrow().parent.upgrade();
+            match parent {
+                Some(p) => current = p,
+                None => return Rc::ptr_eq(&current, &self.root)
+            }
+        }
+    }
+
+    /// Hangs a new leaf below `parent` on the side `val` belongs to.
+    fn attach(&mut self, parent: &Rc<RefCell<Node<T>>>, val: T) -> RcNode<T> {
+        let child = Rc::new(RefCell::new(Node {
+            val,
+            left: None,
+            right: None,
+            parent: Rc::downgrade(parent)
+        }));
+        let mut node = parent.borrow_mut();
+        if node.val > val {
+            node.left = Some(Rc::clone(&child));
+            if Rc::ptr_eq(parent, &self.min) {
+                self.min = Rc::clone(&child);
+            }
+        } else {
+            node.right = Some(Rc::clone(&child));
+            if Rc::ptr_eq(parent, &self.max) {
+                self.max = Rc::clone(&child);
+            }
+        }
+        RcNode { node: child }
+    }
+
+    /// In-order successor of a node without right child.
+    fn successor(&self, node: &Rc<RefCell<Node<T>>>) -> Link<T> {
+        if Rc::ptr_eq(node, &self.max) {
+            return None;
+        }
+        let mut current = Rc::clone(node);
+        loop {
+            let parent = current.borrow().parent.upgrade()?;
+            let from_left = parent.borrow().left.as_ref().is_some_and(|l| Rc::ptr_eq(l, &current));
+            if from_left {
+                return Some(parent);
+            }
+            current = parent;
+        }
+    }
+
+    /// In-order predecessor of a node without left child.
+    fn predecessor(&self, node: &Rc<RefCell<Node<T>>>) -> Link<T> {
+        if Rc::ptr_eq(node, &self.min) {
+            return None;
+        }
+        let mut current = Rc::clone(node);
+        loop {
+            let parent = current.borrow().parent.upgrade()?;
+            let from_right = parent.borrow().right.as_ref().is_some_and(|r| Rc::ptr_eq(r, &current));
+            if from_right {
+                return Some(parent);
+            }
+            current = parent;
+        }
+    }
+
+    /// Finds node holding `val`.
+    /// Uses `O(n)` time.
+    pub fn find(&self, val: T) -> Option<RcNode<T>> {
+        let mut current = Some(Rc::clone(&self.root));
+        while let Some(node) = current {
+            let next = {
+                let n = node.borrow();
+                if n.val == val {
+                    None
+                } else if n.val > val {
+                    Some(n.left.clone())
+                } else if n.val < val {
+                    Some(n.right.clone())
+                } else {
+                    return None;
+                }
+            };
+            match next {
+                None => return Some(RcNode { node }),
+                Some(link) => current = link
+            }
+        }
+        None
+    }
+
+    /// Checks if element exists in a tree.
+    /// Uses `O(n)` time.
+    pub fn exists(&self, val: T) -> bool {
+        self.find(val).is_some()
+    }
+
+    /// Inorder traverse tree which yields elements in sorted order.
+    /// Uses `O(n)` time.
+    pub fn inorder(&self) -> Vec<T> {
+        fn walk<T: Copy>(link: &Link<T>, ret: &mut Vec<T>) {
+            if let Some(node) = link {
+                let node = node.borrow();
+                walk(&node.left, ret);
+                ret.push(node.val);
+                walk(&node.right, ret);
+            }
+        }
+
+        let mut ret = Vec::new();
+        walk(&Some(Rc::clone(&self.root)), &mut ret);
+        ret
+    }
+
+    /// Calculates tree maximum height
+    /// Worst case O(n)
+    pub fn height(&self) -> usize {
+        fn walk<T>(link: &Link<T>) -> usize {
+            match link {
+                None => 0,
+                Some(node) => {
+                    let node = node.borrow();
+                    max(walk(&node.left), walk(&node.right)) + 1
+                }
+            }
+        }
+
+        walk(&Some(Rc::clone(&self.root)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RcBinarySearchTree;
+
+    #[test]
+    fn navigation() {
+        let mut root = RcBinarySearchTree::new(5);
+        for v in [3, 8, 1, 4, 9] {
+            root.insert(v);
+        }
+        assert_eq!(root.inorder(), vec![1, 3, 4, 5, 8, 9]);
+        assert_eq!(root.height(), 3);
+
+        let four = root.find(4).unwrap();
+        let three = four.parent().unwrap();
+        assert_eq!(three.value(), 3);
+        assert!(three.parent().unwrap().ptr_eq(&root.root()));
+        assert!(root.root().parent().is_none());
+        assert_eq!(three.left().map(|n| n.value()), Some(1));
+
+        assert!(root.exists(9));
+        assert!(!root.exists(7));
+    }
+    #[test]
+    fn next_prev() {
+        let mut root = RcBinarySearchTree::new(5);
+        for v in [3, 8, 1, 4, 9, 5, 7] {
+            root.insert(v);
+        }
+        let mut node = root.find(1);
+        let mut forward = Vec::new();
+        while let Some(n) = node {
+            forward.push(n.value());
+            node = n.next();
+        }
+        assert_eq!(forward, root.inorder());
+
+        let mut node = root.find(9);
+        let mut backward = Vec::new();
+        while let Some(n) = node {
+            backward.push(n.value());
+            node = n.prev();
+        }
+        backward.reverse();
+        assert_eq!(backward, forward);
+    }
+    #[test]
+    fn insert_with_hint() {
+        let mut root = RcBinarySearchTree::new(0);
+        let mut hint = root.root();
+        for v in 1..100 {
+            hint = root.insert_with_hint(&hint, v);
+        }
+        assert_eq!(root.inorder(), (0..100).collect::<Vec<_>>());
+
+        let mut root = RcBinarySearchTree::new(50);
+        let hint = root.insert(20);
+        // 25 and 10 fit next to hint, the rest do not
+        assert_eq!(root.insert_with_hint(&hint, 25).parent().map(|p| p.value()), Some(20));
+        assert_eq!(root.insert_with_hint(&hint, 10).parent().map(|p| p.value()), Some(20));
+        assert_eq!(root.insert_with_hint(&hint, 30).parent().map(|p| p.value()), Some(25));
+        assert_eq!(root.insert_with_hint(&hint, 60).parent().map(|p| p.value()), Some(50));
+        for v in [20, 5, 50] {
+            root.insert_with_hint(&hint, v);
+        }
+        assert_eq!(root.inorder(), vec![5, 10, 20, 20, 25, 30, 50, 50, 60]);
+        assert_eq!(root.find(5).and_then(|n| n.prev()).map(|n| n.value()), None);
+
+        let other = RcBinarySearchTree::new(22);
+        let foreign = other.root();
+        assert_eq!(root.insert_with_hint(&foreign, 21).parent().map(|p| p.value()), Some(20));
+        assert_eq!(other.inorder(), vec![22]);
+        assert_eq!(root.inorder(), vec![5, 10, 20, 20, 21, 25, 30, 50, 50, 60]);
+    }
+}
@@ -0,0 +1,203 @@
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt;
+
+use crate::bst::{BinarySearchTree, Direction};
+
+/// Unchecked tree for building arbitrary, possibly broken, shapes.
+///
+/// Children are attached directly with `set_left()` and `set_right()`,
+/// ordering is not checked until `finish()`. It is meant for teaching,
+/// where students assemble trees by hand and want to be told exactly
+/// which edge breaks the search tree property.
+///
+/// # Example
+///
+/// ```rust
+/// use ds_bst::{Direction, Sandbox};
+///
+/// let mut root = Sandbox::new(5);
+/// root.set_left(Sandbox::new(3)).set_right(Sandbox::new(7));
+/// root.set_right(Sandbox::new(8));
+///
+/// let err = root.finish().err().unwrap();
+/// assert_eq!((err.parent, err.child, err.side), (3, 7, Direction::Right));
+/// assert_eq!(err.ancestor, 5);
+/// ```
+pub struct Sandbox<T> {
+    val: T,
+    left: Option<Box<Sandbox<T>>>,
+    right: Option<Box<Sandbox<T>>>
+}
+
+/// Edge which breaks ordering, returned by `Sandbox::finish()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderViolation<T> {
+    /// Value of the parent node.
+    pub parent: T,
+    /// Value of the misplaced child.
+    pub child: T,
+    /// Side of the parent on which child hangs.
+    pub side: Direction,
+    /// Value of the nearest ancestor whose bound child breaks,
+    /// equal to `parent` when child is on the wrong side of its parent.
+    pub ancestor: T,
+    /// Steps from root to the misplaced child.
+    pub path: Vec<Direction>
+}
+
+impl<T: fmt::Debug + PartialOrd> fmt::Display for OrderViolation<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let side = match self.side {
+            Direction::Left => "left",
+            Direction::Right => "right"
+        };
+        let relation = match self.child.partial_cmp(&self.ancestor) {
+            Some(Ordering::Greater) => "greater than",
+            Some(Ordering::Less) => "less than",
+            _ => "not comparable with"
+        };
+        write!(f, "{:?} is {} child of {:?} but is {} {:?}",
+               self.child, side, self.parent, relation, self.ancestor)
+    }
+}
+
+impl<T: fmt::Debug + PartialOrd> Error for OrderViolation<T> {}
+
+impl<T: PartialOrd + Copy> Sandbox<T> {
+    /// Contructor creates a single node
+    pub fn new(v: T) -> Sandbox<T> {
+        Sandbox {
+            val: v,
+            left: None,
+            right: None
+        }
+    }
+
+    /// Replaces left child and returns it for further editing.
+    pub fn set_left(&mut self, child: Sandbox<T>) -> &mut Sandbox<T> {
+        self.left.insert(Box::new(child))
+    }
+
+    /// Replaces right child and returns it for further editing.
+    pub fn set_right(&mut self, child: Sandbox<T>) -> &mut Sandbox<T> {
+        self.right.insert(Box::new(child))
+    }
+
+    /// Detaches left child.
+    pub fn take_left(&mut self) -> Option<Sandbox<T>> {
+        self.left.take().map(|n| *n)
+    }
+
+    /// Detaches right child.
+    pub fn take_right(&mut self) -> Option<Sandbox<T>> {
+        self.right.take().map(|n| *n)
+    }
+
+    /// Left child for further editing.
+    pub fn left_mut(&mut self) -> Option<&mut Sandbox<T>> {
+        self.left.as_deref_mut()
+    }
+
+    /// Right child for further editing.
+    pub fn right_mut(&mut self) -> Option<&mut Sandbox<T>> {
+        self.right.as_deref_mut()
+    }
+
+    /// Checks ordering of every edge and turns sandbox into a tree.
+    /// Returns first violating edge in preorder otherwise.
+    pub fn finish(self) -> Result<BinarySearchTree<T>, OrderViolation<T>> {
+        let mut path = Vec::new();
+        self.check(None, None, &mut path)?;
+        Ok(self.into_tree())
+    }
+
+    /// Checks children of this node, `lower` and `upper` are the nearest
+    /// ancestors this sub tree must stay above and below.
+    fn check(&self, lower: Option<T>, upper: Option<T>, path: &mut Vec<Direction>) -> Result<(), OrderViolation<T>> {
+        let children = [
+            (Direction::Left, &self.left, lower, Some(self.val)),
+            (Direction::Right, &self.right, Some(self.val), upper)
+        ];
+
+        for (side, child, lo, hi) in children {
+            let child = match child {
+                None => continue,
+                Some(c) => c
+            };
+            path.push(side);
+
+            let broken = match side {
+                Direction::Left => hi.filter(|&h| !not_greater(child.val, h))
+                    .or(lo.filter(|&l| !not_greater(l, child.val))),
+                Direction::Right => lo.filter(|&l| !not_greater(l, child.val))
+                    .or(hi.filter(|&h| !not_greater(child.val, h)))
+            };
+            if let Some(ancestor) = broken {
+                return Err(OrderViolation {
+                    parent: self.val,
+                    child: child.val,
+                    side,
+                    ancestor,
+                    path: path.clone()
+                });
+            }
+
+            child.check(lo, hi, path)?;
+            path.pop();
+        }
+        Ok(())
+    }
+
+    fn into_tree(self) -> BinarySearchTree<T> {
+        BinarySearchTree {
+            val: self.val,
+            left: self.left.map(|n| Box::new(n.into_tree())),
+            right: self.right.map(|n| Box::new(n.into_tree()))
+        }
+    }
+}
+
+fn not_greater<T: PartialOrd>(a: T, b: T) -> bool {
+    matches!(a.partial_cmp(&b), Some(Ordering::Less | Ordering::Equal))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Sandbox;
+    use crate::bst::Direction;
+
+    #[test]
+    fn valid() {
+        let mut root = Sandbox::new(4);
+        let left = root.set_left(Sandbox::new(2));
+        left.set_left(Sandbox::new(1));
+        left.set_right(Sandbox::new(3));
+        root.set_right(Sandbox::new(6)).set_left(Sandbox::new(5));
+
+        let tree = root.finish().unwrap();
+        assert_eq!(tree.inorder(), vec![1, 2, 3, 4, 5, 6]);
+        assert_eq!(tree.preorder(), vec![4, 2, 1, 3, 6, 5]);
+    }
+    #[test]
+    fn violations() {
+        let mut root = Sandbox::new(4);
+        root.set_right(Sandbox::new(2));
+        let err = root.finish().err().unwrap();
+        assert_eq!((err.parent, err.child, err.ancestor), (4, 2, 4));
+        assert_eq!(err.path, vec![Direction::Right]);
+        assert_eq!(err.to_string(), "2 is right child of 4 but is less than 4");
+
+        let mut root = Sandbox::new(10);
+        root.set_right(Sandbox::new(20))
+            .set_left(Sandbox::new(15))
+            .set_left(Sandbox::new(5));
+        let err = root.finish().err().unwrap();
+        assert_eq!((err.parent, err.child, err.side, err.ancestor), (15, 5, Direction::Left, 10));
+        assert_eq!(err.path, vec![Direction::Right, Direction::Left, Direction::Left]);
+
+        let mut root = Sandbox::new(1.0);
+        root.set_left(Sandbox::new(f64::NAN));
+        assert!(root.finish().is_err());
+    }
+}
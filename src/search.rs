@@ -0,0 +1,79 @@
+//! Read-only walks shared by trees which keep nodes of their own type.
+use std::ops::RangeBounds;
+
+use crate::bst::{before_end, before_start};
+
+/// Node of a binary search tree, smaller elements to its left and
+/// others to its right.
+pub(crate) trait SearchNode: Sized {
+    type Val: PartialOrd;
+
+    fn val(&self) -> &Self::Val;
+    fn left(&self) -> Option<&Self>;
+    fn right(&self) -> Option<&Self>;
+}
+
+/// Checks if `val` exists in sub tree of `root`.
+/// Uses `O(h)` time.
+pub(crate) fn exists<N: SearchNode>(root: Option<&N>, val: &N::Val) -> bool {
+    let mut current = root;
+    while let Some(node) = current {
+        if node.val() == val {
+            return true;
+        }
+        current = if node.val() > val { node.left() } else { node.right() };
+    }
+    false
+}
+
+/// Iterator over nodes in sorted order, holding left spines still to
+/// be visited.
+pub(crate) struct Nodes<'a, N> {
+    stack: Vec<&'a N>
+}
+
+impl<'a, N: SearchNode> Nodes<'a, N> {
+    /// Iterates all nodes of sub tree of `root`.
+    pub(crate) fn new(root: Option<&'a N>) -> Nodes<'a, N> {
+        let mut nodes = Nodes { stack: Vec::new() };
+        nodes.push_left(root);
+        nodes
+    }
+
+    /// Iterates nodes within `range`, sub trees before its start are
+    /// skipped while seeding the stack.
+    /// Uses `O(h)` time to start.
+    pub(crate) fn range<R>(root: Option<&'a N>, range: R) -> impl Iterator<Item = &'a N> + 'a
+        where
+            R: RangeBounds<N::Val> + 'a
+    {
+        let mut nodes = Nodes { stack: Vec::new() };
+        let mut current = root;
+        while let Some(node) = current {
+            if before_start(range.start_bound(), node.val()) {
+                current = node.right();
+            } else {
+                nodes.stack.push(node);
+                current = node.left();
+            }
+        }
+        nodes.take_while(move |node| before_end(range.end_bound(), node.val()))
+    }
+
+    fn push_left(&mut self, mut current: Option<&'a N>) {
+        while let Some(node) = current {
+            self.stack.push(node);
+            current = node.left();
+        }
+    }
+}
+
+impl<'a, N: SearchNode> Iterator for Nodes<'a, N> {
+    type Item = &'a N;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        self.push_left(node.right());
+        Some(node)
+    }
+}
@@ -0,0 +1,255 @@
+use std::cmp::max;
+use std::ops::{Bound, RangeBounds};
+
+/// Read-only binary search tree stored as a flat sorted array.
+///
+/// The tree is implicit: the middle element is the root and the
+/// halves on either side are the left and right sub trees, which is
+/// the same shape `BinarySearchTree::from()` builds. Construction is a
+/// `const fn`, so lookup tables can be baked into a `static` with
+/// zero startup cost.
+///
+/// Input must already be sorted in ascending order, it is not checked.
+///
+/// # Example
+///
+/// ```rust
+/// use ds_bst::StaticBst;
+///
+/// static PRIMES: StaticBst<u32> = StaticBst::from_sorted(&[2, 3, 5, 7, 11, 13]);
+///
+/// assert!(PRIMES.contains(7));
+/// assert!(!PRIMES.contains(8));
+///
+/// let small: Vec<_> = PRIMES.range(3..11).collect();
+/// assert_eq!(small, vec![&3, &5, &7]);
+/// ```
+pub struct StaticBst<'a, T> {
+    data: &'a [T]
+}
+
+impl<'a, T> StaticBst<'a, T> {
+    /// Contructor creates a tree from an already sorted slice.
+    /// Uses `O(1)` time and can be used in `const` context.
+    pub const fn from_sorted(data: &'a [T]) -> StaticBst<'a, T> {
+        StaticBst { data }
+    }
+
+    /// Number of elements in a tree.
+    pub const fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Checks if tree has no elements.
+    pub const fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+}
+
+impl<'a, T: PartialOrd + Copy> StaticBst<'a, T> {
+    /// Checks if element exists in a tree.
+    /// Uses `O(log n)` time.
+    pub fn contains(&self, val: T) -> bool {
+        let mut start = 0;
+        let mut end = self.data.len();
+
+        while start < end {
+            let mid = start + (end - start - 1) / 2;
+            let node = self.data[mid];
+            if node == val {
+                return true;
+            }
+            if node > val {
+                end = mid;
+            } else {
+                start = mid + 1;
+            }
+        }
+        false
+    }
+
+    /// Checks many elements at once, `result[i]` tells if `probes[i]`
+    /// exists. All probes descend one level together with a branch-free
+    /// step, so loads of different probes are independent and overlap
+    /// instead of waiting on each other. `cargo bench --bench batch`
+    /// compares it with `contains()` per probe.
+    /// Uses `O(m log n)` time for `m` probes.
+    pub fn contains_batch(&self, probes: &[T]) -> Vec<bool> {
+        if self.data.is_empty() {
+            return vec![false; probes.len()];
+        }
+
+        let mut base = vec![0; probes.len()];
+        let mut size = self.data.len();
+        while size > 1 {
+            let half = size / 2;
+            for (b, &probe) in base.iter_mut().zip(probes) {
+                let mid = *b + half;
+                *b = if self.data[mid] > probe { *b } else { mid };
+            }
+            size -= half;
+        }
+
+        base.iter().zip(probes).map(|(&b, &probe)| self.data[b] == probe).collect()
+    }
+
+    /// Iterates elements within `range` in sorted order.
+    /// Uses `O(log n)` time to find the bounds.
+    pub fn range<R: RangeBounds<T>>(&self, range: R) -> std::slice::Iter<'a, T> {
+        let start = match range.start_bound() {
+            Bound::Included(&lo) => self.data.partition_point(|v| *v < lo),
+            Bound::Excluded(&lo) => self.data.partition_point(|v| *v <= lo),
+            Bound::Unbounded => 0
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&hi) => self.data.partition_point(|v| *v <= hi),
+            Bound::Excluded(&hi) => self.data.partition_point(|v| *v < hi),
+            Bound::Unbounded => self.data.len()
+        };
+
+        self.data[start..max(start, end)].iter()
+    }
+
+    /// Counts elements which are smaller than `val`.
+    /// Uses `O(log n)` time.
+    pub fn rank(&self, val: T) -> usize {
+        self.data.partition_point(|v| *v < val)
+    }
+
+    /// Returns element at position `k` in sorted order, counting from `0`.
+    /// Uses `O(1)` time.
+    pub fn select(&self, k: usize) -> Option<T> {
+        self.data.get(k).copied()
+    }
+
+    /// Iterates all elements in sorted order.
+    pub fn iter(&self) -> std::slice::Iter<'a, T> {
+        self.data.iter()
+    }
+
+    /// Finds minimum element in a tree.
+    pub fn find_min(&self) -> Option<T> {
+        self.data.first().copied()
+    }
+
+    /// Finds maximum element in a tree.
+    pub fn find_max(&self) -> Option<T> {
+        self.data.last().copied()
+    }
+}
+
+/// Owned sorted snapshot of a tree, made by
+/// `BinarySearchTree::export_index()`.
+///
+/// It holds a compact copy of the elements, so it can be sent to another
+/// thread and queried there while the source tree keeps changing.
+pub struct SortedIndex<T> {
+    data: Box<[T]>
+}
+
+impl<T> SortedIndex<T> {
+    /// Number of elements.
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Checks if index has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Elements in sorted order.
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+
+    /// Read-only tree view for lookups.
+    pub fn as_tree(&self) -> StaticBst<'_, T> {
+        StaticBst::from_sorted(&self.data)
+    }
+
+    /// Iterates elements in sorted order.
+    pub fn keys(&self) -> std::slice::Iter<'_, T> {
+        self.data.iter()
+    }
+}
+
+impl<T: PartialOrd + Copy> SortedIndex<T> {
+    /// Checks if element exists.
+    /// Uses `O(log n)` time.
+    pub fn contains(&self, val: T) -> bool {
+        self.as_tree().contains(val)
+    }
+
+    /// Counts elements which are smaller than `val`.
+    /// Uses `O(log n)` time.
+    pub fn rank(&self, val: T) -> usize {
+        self.as_tree().rank(val)
+    }
+
+    /// Returns element at position `k` in sorted order, counting from `0`.
+    /// Uses `O(1)` time.
+    pub fn select(&self, k: usize) -> Option<T> {
+        self.as_tree().select(k)
+    }
+}
+
+impl<T> From<Box<[T]>> for SortedIndex<T> {
+    /// Wraps already sorted elements, order is not checked.
+    fn from(data: Box<[T]>) -> Self {
+        SortedIndex { data }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StaticBst;
+    use std::ops::Bound;
+
+    static TABLE: StaticBst<i32> = StaticBst::from_sorted(&[1, 3, 5, 7, 9, 11]);
+
+    #[test]
+    fn contains() {
+        for v in [1, 3, 5, 7, 9, 11] {
+            assert!(TABLE.contains(v));
+        }
+        for v in [0, 2, 4, 6, 8, 10, 12] {
+            assert!(!TABLE.contains(v));
+        }
+        assert_eq!(TABLE.len(), 6);
+        assert_eq!(TABLE.find_min(), Some(1));
+        assert_eq!(TABLE.find_max(), Some(11));
+    }
+    #[test]
+    fn contains_batch() {
+        let probes: Vec<i32> = (-1..13).collect();
+        let expected: Vec<bool> = probes.iter().map(|&v| TABLE.contains(v)).collect();
+        assert_eq!(TABLE.contains_batch(&probes), expected);
+
+        let empty: StaticBst<i32> = StaticBst::from_sorted(&[]);
+        assert_eq!(empty.contains_batch(&[1, 2]), vec![false, false]);
+    }
+    #[test]
+    fn rank_select() {
+        assert_eq!(TABLE.rank(7), 3);
+        assert_eq!(TABLE.rank(8), 4);
+        assert_eq!(TABLE.rank(0), 0);
+        assert_eq!(TABLE.select(3), Some(7));
+        assert_eq!(TABLE.select(6), None);
+    }
+    #[test]
+    fn range() {
+        let v: Vec<_> = TABLE.range(3..=9).copied().collect();
+        assert_eq!(v, vec![3, 5, 7, 9]);
+        let v: Vec<_> = TABLE.range(4..).copied().collect();
+        assert_eq!(v, vec![5, 7, 9, 11]);
+        let v: Vec<_> = TABLE.range(..1).copied().collect();
+        assert!(v.is_empty());
+        assert_eq!(TABLE.range((Bound::Included(9), Bound::Excluded(3))).count(), 0);
+
+        let empty: StaticBst<i32> = StaticBst::from_sorted(&[]);
+        assert!(empty.is_empty());
+        assert!(!empty.contains(1));
+        assert_eq!(empty.find_min(), None);
+    }
+}
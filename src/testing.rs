@@ -0,0 +1,157 @@
+//! Test support for checking tree backends against a reference model.
+//!
+//! Every backend implementing `Backend` can be driven by a script of
+//! `Operation`s with `replay()`, which compares each result with `Model`,
+//! a plain sorted vector. `assert_matches_btreeset()` checks contents of
+//! anything iterating in order against a `BTreeSet`.
+//!
+//! Available with `testing` feature.
+//!
+//! # Example
+//!
+//! ```rust
+//! use std::collections::BTreeSet;
+//! use ds_bst::{BinarySearchTree, Operation};
+//! use ds_bst::testing::{assert_matches_btreeset, replay, Model};
+//!
+//! let mut root = BinarySearchTree::new(5);
+//! let mut model = Model::new(5);
+//! replay(&mut root, &mut model, &[Operation::Insert(3), Operation::Remove(5), Operation::Min]);
+//!
+//! assert_matches_btreeset(&root, &BTreeSet::from([3]));
+//! ```
+use std::collections::BTreeSet;
+use std::fmt::Debug;
+use std::ops::RangeBounds;
+
+use crate::bst::{BinarySearchTree, OpResult, Operation};
+
+/// Tree backend which can be driven by `replay()`.
+pub trait Backend<T> {
+    /// Runs a single operation, with same results as
+    /// `BinarySearchTree::apply()`.
+    fn apply(&mut self, op: Operation<T>) -> OpResult<T>;
+}
+
+impl<T: PartialOrd + Copy> Backend<T> for BinarySearchTree<T> {
+    fn apply(&mut self, op: Operation<T>) -> OpResult<T> {
+        BinarySearchTree::apply(self, op)
+    }
+}
+
+/// Reference model keeping every copy in a sorted vector.
+pub struct Model<T> {
+    values: Vec<T>
+}
+
+impl<T: PartialOrd + Copy> Model<T> {
+    /// Contructor creates a model with a single element, like
+    /// `BinarySearchTree::new()`.
+    pub fn new(v: T) -> Model<T> {
+        Model { values: vec![v] }
+    }
+
+    /// Elements in sorted order.
+    pub fn values(&self) -> &[T] {
+        &self.values
+    }
+}
+
+impl<T: PartialOrd + Copy> Backend<T> for Model<T> {
+    fn apply(&mut self, op: Operation<T>) -> OpResult<T> {
+        match op {
+            Operation::Insert(v) => {
+                let i = self.values.partition_point(|x| *x <= v);
+                self.values.insert(i, v);
+                OpResult::Inserted
+            },
+            Operation::Remove(v) => match self.values.iter().position(|x| *x == v) {
+                Some(_) if self.values.len() == 1 => OpResult::Refused,
+                Some(i) => {
+                    self.values.remove(i);
+                    OpResult::Removed(true)
+                },
+                None => OpResult::Removed(false)
+            },
+            Operation::Contains(v) => OpResult::Contains(self.values.contains(&v)),
+            Operation::Count(v) => OpResult::Count(self.values.iter().filter(|x| **x == v).count()),
+            Operation::QueryRange(start, end) => {
+                let range = (start, end);
+                OpResult::Range(self.values.iter().copied().filter(|x| range.contains(x)).collect())
+            },
+            Operation::Min => OpResult::Min(self.values[0]),
+            Operation::Max => OpResult::Max(self.values[self.values.len() - 1])
+        }
+    }
+}
+
+/// Runs every operation on both `backend` and `model`.
+///
+/// # Panics
+/// Panics at the first operation whose results differ.
+pub fn replay<T, B>(backend: &mut B, model: &mut Model<T>, ops: &[Operation<T>])
+    where
+        T: PartialOrd + Copy + Debug,
+        B: Backend<T>
+{
+    for (i, &op) in ops.iter().enumerate() {
+        let got = backend.apply(op);
+        let expected = model.apply(op);
+        if got != expected {
+            panic!("operation {} {:?}: backend returned {:?}, model {:?}", i, op, got, expected);
+        }
+    }
+}
+
+/// Checks that `tree` yields exactly elements of `set` in ascending
+/// order, each once.
+///
+/// # Panics
+/// Panics if contents or order differ.
+pub fn assert_matches_btreeset<'a, I, T>(tree: I, set: &BTreeSet<T>)
+    where
+        I: IntoIterator<Item = &'a T>,
+        T: Ord + Debug + 'a
+{
+    let values: Vec<&T> = tree.into_iter().collect();
+    if let Some(w) = values.windows(2).find(|w| w[0] >= w[1]) {
+        panic!("tree is not strictly ascending, {:?} comes before {:?}", w[0], w[1]);
+    }
+    let expected: Vec<&T> = set.iter().collect();
+    assert_eq!(values, expected, "tree does not match set");
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+    use std::ops::Bound::{Excluded, Included};
+    use super::{assert_matches_btreeset, replay, Model};
+    use crate::bst::{BinarySearchTree, Operation};
+
+    #[test]
+    fn replay_script() {
+        let mut root = BinarySearchTree::new(10);
+        let mut model = Model::new(10);
+        let mut ops = Vec::new();
+        for v in 0..50 {
+            ops.push(Operation::Insert((v * 37) % 23));
+            ops.push(Operation::Remove((v * 11) % 23));
+            ops.push(Operation::QueryRange(Included(v % 23), Excluded(v % 23 + 5)));
+            ops.push(Operation::Count(v % 23));
+            ops.push(Operation::Max);
+        }
+        replay(&mut root, &mut model, &ops);
+        assert_eq!(root.inorder(), model.values());
+    }
+    #[test]
+    fn matches_set() {
+        let root = BinarySearchTree::from(vec![4, 2, 9]);
+        assert_matches_btreeset(&root, &BTreeSet::from([2, 4, 9]));
+    }
+    #[test]
+    #[should_panic]
+    fn duplicates_do_not_match_set() {
+        let root = BinarySearchTree::from(vec![4, 4, 9]);
+        assert_matches_btreeset(&root, &BTreeSet::from([4, 9]));
+    }
+}
@@ -0,0 +1,394 @@
+use std::cmp::max;
+
+use crate::balanced::{self, balance, metric as size, update, Link, Metric, Node};
+use crate::search::{self, Nodes};
+
+/// Sub tree weights may differ at most by this factor.
+const DELTA: usize = 3;
+/// Decides between single and double rotation.
+const GAMMA: usize = 2;
+
+/// Nodes keep size of their sub tree, weight of a sub tree is its
+/// size plus one.
+enum Size {}
+
+impl Metric for Size {
+    fn of(left: usize, right: usize) -> usize {
+        left + right + 1
+    }
+
+    fn heavier(a: usize, b: usize) -> bool {
+        a + 1 > DELTA * (b + 1)
+    }
+
+    fn single_rotation(inner: usize, outer: usize) -> bool {
+        inner + 1 < GAMMA * (outer + 1)
+    }
+}
+
+/// Implements a weight-balanced (BB[α]) binary search tree.
+///
+/// Every node keeps the size of its sub tree, balance is restored with
+/// rotations whenever one side becomes more than three times heavier
+/// than the other. The sizes make rank queries and splitting or joining
+/// by rank cheap, all of them run in `O(log n)` time.
+///
+/// # Example
+///
+/// ```rust
+/// use ds_bst::WeightBalancedTree;
+///
+/// let mut tree = WeightBalancedTree::new();
+/// for v in 1..=10 {
+///     tree.insert(v);
+/// }
+/// assert_eq!(tree.select(3), Some(4));
+/// assert_eq!(tree.rank(4), 3);
+///
+/// let (small, large) = tree.split_at(5);
+/// assert_eq!(small.inorder(), vec![1, 2, 3, 4, 5]);
+/// assert_eq!(large.inorder(), vec![6, 7, 8, 9, 10]);
+///
+/// let tree = WeightBalancedTree::join(small, large);
+/// assert_eq!(tree.len(), 10);
+/// ```
+pub struct WeightBalancedTree<T> {
+    root: Link<T>
+}
+
+fn heavier<T>(a: &Link<T>, b: &Link<T>) -> bool {
+    Size::heavier(size(a), size(b))
+}
+
+/// Joins two trees of any sizes with node `mid` between them, reusing
+/// nodes along the spine of the heavier tree.
+fn link<T>(left: Link<T>, mut mid: Box<Node<T>>, right: Link<T>) -> Box<Node<T>> {
+    if heavier(&left, &right) {
+        let mut l = left.unwrap();
+        l.right = Some(link(l.right.take(), mid, right));
+        balance::<Size, _, _>(&mut l);
+        l
+    } else if heavier(&right, &left) {
+        let mut r = right.unwrap();
+        r.left = Some(link(left, mid, r.left.take()));
+        balance::<Size, _, _>(&mut r);
+        r
+    } else {
+        mid.left = left;
+        mid.right = right;
+        update::<Size, _, _>(&mut mid);
+        mid
+    }
+}
+
+/// Joins two trees of any sizes.
+fn merge<T>(left: Link<T>, right: Link<T>) -> Link<T> {
+    match (left, right) {
+        (None, r) => r,
+        (l, None) => l,
+        (l, mut r) => {
+            let min = balanced::pop_min::<Size, _, _>(&mut r)?;
+            Some(link(l, min, r))
+        }
+    }
+}
+
+/// Splits tree into first `k` elements and the rest.
+fn split<T>(tree: Link<T>, k: usize) -> (Link<T>, Link<T>) {
+    match tree {
+        None => (None, None),
+        Some(mut t) => {
+            let (left, right) = (t.left.take(), t.right.take());
+            let ls = size(&left);
+            if k <= ls {
+                let (a, b) = split(left, k);
+                (a, Some(link(b, t, right)))
+            } else {
+                let (a, b) = split(right, k - ls - 1);
+                (Some(link(left, t, a)), b)
+            }
+        }
+    }
+}
+
+impl<T> Default for WeightBalancedTree<T> {
+    fn default() -> Self {
+        WeightBalancedTree { root: None }
+    }
+}
+
+impl<T: PartialOrd + Copy> WeightBalancedTree<T> {
+    /// Contructor creates an empty tree
+    pub fn new() -> WeightBalancedTree<T> {
+        WeightBalancedTree::default()
+    }
+
+    /// Number of elements in a tree.
+    /// Uses `O(1)` time.
+    pub fn len(&self) -> usize {
+        size(&self.root)
+    }
+
+    /// Checks if tree has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    /// Inserts an element in a tree.
+    /// Uses `O(log n)` time.
+    pub fn insert(&mut self, val: T) {
+        balanced::insert::<Size, _, _>(&mut self.root, val);
+    }
+
+    /// Removes one copy of `val`, returns `false` if it does not exist.
+    /// Uses `O(log n)` time.
+    pub fn remove(&mut self, val: T) -> bool {
+        balanced::remove::<Size, _, _>(&mut self.root, &val)
+    }
+
+    /// Checks if element exists in a tree.
+    /// Uses `O(log n)` time.
+    pub fn exists(&self, val: T) -> bool {
+        search::exists(self.root.as_deref(), &val)
+    }
+
+    /// Returns element at position `k` in sorted order, counting from `0`.
+    /// Uses `O(log n)` time.
+    pub fn select(&self, mut k: usize) -> Option<T> {
+        let mut current = &self.root;
+        while let Some(node) = current {
+            let ls = size(&node.left);
+            if k < ls {
+                current = &node.left;
+            } else if k == ls {
+                return Some(node.val);
+            } else {
+                k -= ls + 1;
+                current = &node.right;
+            }
+        }
+        None
+    }
+
+    /// Counts elements which are smaller than `val`.
+    /// Uses `O(log n)` time.
+    pub fn rank(&self, val: T) -> usize {
+        let mut rank = 0;
+        let mut current = &self.root;
+        while let Some(node) = current {
+            if node.val < val {
+                rank += size(&node.left) + 1;
+                current = &node.right;
+            } else {
+                current = &node.left;
+            }
+        }
+        rank
+    }
+
+    /// Returns number of leading elements in sorted order for which `pred`
+    /// holds, like `slice::partition_point()`. `pred` has to be true for
+    /// a prefix of elements, sizes of passed left sub trees are added up.
+    /// Uses `O(log n)` time.
+    pub fn partition_point<P>(&self, mut pred: P) -> usize
+        where
+            P: FnMut(&T) -> bool
+    {
+        let mut count = 0;
+        let mut current = &self.root;
+        while let Some(node) = current {
+            if pred(&node.val) {
+                count += size(&node.left) + 1;
+                current = &node.right;
+            } else {
+                current = &node.left;
+            }
+        }
+        count
+    }
+
+    /// Returns element `n` positions after `val` in sorted order, or
+    /// before it when `n` is negative, by rank arithmetic. Position of
+    /// `val` is its rank, so `nth_from(val, 0)` is the first element not
+    /// less than `val`. Returns `None` when offset runs past either end.
+    /// Uses `O(log n)` time.
+    pub fn nth_from(&self, val: T, n: isize) -> Option<T> {
+        let rank = self.rank(val) as isize;
+        self.select(usize::try_from(rank.checked_add(n)?).ok()?)
+    }
+
+    /// Counts elements in buckets split by sorted `boundaries`, bucket
+    /// `i` holding those from boundary `i - 1` up to boundary `i` excluded,
+    /// as `BinarySearchTree::group_by_ranges()` does. Counts are
+    /// differences of ranks of boundaries.
+    /// Uses `O(k log n)` time for `k` boundaries.
+    pub fn group_by_ranges(&self, boundaries: &[T]) -> Vec<usize> {
+        let mut prev = 0;
+        let mut counts: Vec<usize> = boundaries.iter().map(|&b| {
+            let rank = self.rank(b).max(prev);
+            let count = rank - prev;
+            prev = rank;
+            count
+        }).collect();
+        counts.push(self.len() - prev);
+        counts
+    }
+
+    /// Splits tree into first `k` elements and the rest.
+    /// Uses `O(log n)` time.
+    pub fn split_at(self, k: usize) -> (WeightBalancedTree<T>, WeightBalancedTree<T>) {
+        let (left, right) = split(self.root, k);
+        (WeightBalancedTree { root: left }, WeightBalancedTree { root: right })
+    }
+
+    /// Joins two trees where every element of `left` is not greater
+    /// than any element of `right`.
+    /// Uses `O(log n)` time.
+    pub fn join(left: WeightBalancedTree<T>, right: WeightBalancedTree<T>) -> WeightBalancedTree<T> {
+        WeightBalancedTree { root: merge(left.root, right.root) }
+    }
+
+    /// Removes every element smaller than `cutoff` and returns them in
+    /// sorted order, the low end expiry of a sliding window. Balancing
+    /// keeps monotonically increasing inserts at `O(log n)` time and
+    /// expired elements are split off at once rather than one by one.
+    /// Uses `O(log n + k)` time for `k` expired elements.
+    pub fn expire_below(&mut self, cutoff: T) -> Vec<T> {
+        let k = self.rank(cutoff);
+        let (expired, rest) = split(self.root.take(), k);
+        self.root = rest;
+        WeightBalancedTree { root: expired }.inorder()
+    }
+
+    /// Finds minimum element in a tree.
+    pub fn find_min(&self) -> Option<T> {
+        self.select(0)
+    }
+
+    /// Finds maximum element in a tree.
+    pub fn find_max(&self) -> Option<T> {
+        self.len().checked_sub(1).and_then(|k| self.select(k))
+    }
+
+    /// Inorder traverse tree which yields elements in sorted order.
+    /// Uses `O(n)` time.
+    pub fn inorder(&self) -> Vec<T> {
+        Nodes::new(self.root.as_deref()).map(|node| node.val).collect()
+    }
+
+    /// Calculates tree maximum height
+    pub fn height(&self) -> usize {
+        fn walk<T>(link: &Link<T>) -> usize {
+            match link {
+                None => 0,
+                Some(node) => max(walk(&node.left), walk(&node.right)) + 1
+            }
+        }
+
+        walk(&self.root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Link, WeightBalancedTree, DELTA, size};
+
+    fn weight<T>(link: &Link<T>) -> usize {
+        size(link) + 1
+    }
+
+    fn assert_balanced<T>(link: &Link<T>) {
+        if let Some(node) = link {
+            assert_eq!(node.metric, size(&node.left) + size(&node.right) + 1);
+            assert!(weight(&node.left) <= DELTA * weight(&node.right));
+            assert!(weight(&node.right) <= DELTA * weight(&node.left));
+            assert_balanced(&node.left);
+            assert_balanced(&node.right);
+        }
+    }
+
+    #[test]
+    fn sorted_inserts_stay_balanced() {
+        let mut tree = WeightBalancedTree::new();
+        for v in 0..1000 {
+            tree.insert(v);
+        }
+        assert_balanced(&tree.root);
+        assert_eq!(tree.len(), 1000);
+        assert!(tree.height() <= 20);
+        assert_eq!(tree.inorder(), (0..1000).collect::<Vec<_>>());
+        assert!(tree.exists(999));
+        assert!(!tree.exists(1000));
+    }
+    #[test]
+    fn rank_select() {
+        let mut tree = WeightBalancedTree::new();
+        for v in (0..100).rev() {
+            tree.insert(v * 2);
+        }
+        assert_eq!(tree.select(0), Some(0));
+        assert_eq!(tree.select(50), Some(100));
+        assert_eq!(tree.select(100), None);
+        assert_eq!(tree.rank(101), 51);
+        assert_eq!(tree.find_min(), Some(0));
+        assert_eq!(tree.find_max(), Some(198));
+
+        assert!(tree.remove(100));
+        assert!(!tree.remove(100));
+        assert_eq!(tree.select(50), Some(102));
+        assert_balanced(&tree.root);
+    }
+    #[test]
+    fn rank_arithmetic() {
+        let mut tree = WeightBalancedTree::new();
+        for v in 0..100 {
+            tree.insert(v * 3);
+        }
+        assert_eq!(tree.partition_point(|&v| v < 31), 11);
+        assert_eq!(tree.partition_point(|_| true), 100);
+        assert_eq!(tree.nth_from(30, 5), Some(45));
+        assert_eq!(tree.nth_from(31, -1), Some(30));
+        assert_eq!(tree.nth_from(0, -1), None);
+        assert_eq!(tree.nth_from(0, 100), None);
+        assert_eq!(tree.group_by_ranges(&[3, 10, 10, 299]), vec![1, 3, 0, 96, 0]);
+        assert_eq!(tree.group_by_ranges(&[]), vec![100]);
+    }
+    #[test]
+    fn sliding_window() {
+        let mut window = WeightBalancedTree::new();
+        let mut expired = Vec::new();
+        for t in 0..1000 {
+            window.insert(t);
+            if t % 100 == 99 {
+                expired.extend(window.expire_below(t - 49));
+                assert_eq!(window.find_min(), Some(t - 49));
+                assert_balanced(&window.root);
+            }
+        }
+        assert_eq!(window.len(), 50);
+        assert_eq!(expired, (0..950).collect::<Vec<_>>());
+        assert!(window.expire_below(0).is_empty());
+    }
+    #[test]
+    fn split_join() {
+        let mut tree = WeightBalancedTree::new();
+        for v in 0..500 {
+            tree.insert(v);
+        }
+        let (a, b) = tree.split_at(123);
+        assert_balanced(&a.root);
+        assert_balanced(&b.root);
+        assert_eq!(a.len(), 123);
+        assert_eq!(b.find_min(), Some(123));
+
+        let small: WeightBalancedTree<i32> = WeightBalancedTree::new();
+        let tree = WeightBalancedTree::join(small, b);
+        let tree = WeightBalancedTree::join(a, tree);
+        assert_balanced(&tree.root);
+        assert_eq!(tree.inorder(), (0..500).collect::<Vec<_>>());
+
+        let (all, none) = tree.split_at(1000);
+        assert_eq!(all.len(), 500);
+        assert!(none.is_empty());
+    }
+}
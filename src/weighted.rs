@@ -0,0 +1,267 @@
+use crate::search::{self, Nodes, SearchNode};
+
+type Link<T> = Option<Box<Node<T>>>;
+
+struct Node<T> {
+    val: T,
+    weight: f64,
+    sum: f64,
+    left: Link<T>,
+    right: Link<T>
+}
+
+impl<T> Node<T> {
+    fn update(&mut self) {
+        self.sum = self.weight + sum(&self.left) + sum(&self.right);
+    }
+}
+
+impl<T: PartialOrd> SearchNode for Node<T> {
+    type Val = T;
+
+    fn val(&self) -> &T {
+        &self.val
+    }
+
+    fn left(&self) -> Option<&Self> {
+        self.left.as_deref()
+    }
+
+    fn right(&self) -> Option<&Self> {
+        self.right.as_deref()
+    }
+}
+
+fn sum<T>(link: &Link<T>) -> f64 {
+    match link {
+        None => 0.0,
+        Some(node) => node.sum
+    }
+}
+
+/// Binary search tree where every element carries a weight.
+///
+/// Nodes keep the total weight of their sub tree, which allows weighted
+/// random selection and weight prefix sums over an ordered population
+/// that keeps changing. Like `BinarySearchTree` it does not rebalance.
+///
+/// # Example
+///
+/// ```rust
+/// use ds_bst::WeightedTree;
+///
+/// let mut tree = WeightedTree::new();
+/// tree.insert('a', 1.0);
+/// tree.insert('b', 3.0);
+/// tree.insert('c', 1.0);
+///
+/// assert_eq!(tree.total_weight(), 5.0);
+/// assert_eq!(tree.weight_rank('c'), 4.0);
+/// // `u` is a uniform sample from [0, 1), e.g. `rng.gen()`
+/// assert_eq!(tree.sample_weighted(0.5), Some('b'));
+/// ```
+pub struct WeightedTree<T> {
+    root: Link<T>
+}
+
+impl<T> Default for WeightedTree<T> {
+    fn default() -> Self {
+        WeightedTree { root: None }
+    }
+}
+
+impl<T: PartialOrd + Copy> WeightedTree<T> {
+    /// Contructor creates an empty tree
+    pub fn new() -> WeightedTree<T> {
+        WeightedTree::default()
+    }
+
+    /// Checks if tree has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    /// Sum of weights of all elements.
+    /// Uses `O(1)` time.
+    pub fn total_weight(&self) -> f64 {
+        sum(&self.root)
+    }
+
+    /// Inserts an element with `weight` in a tree.
+    /// Uses `O(n)` time.
+    ///
+    /// # Panics
+    /// Panics if `weight` is negative, infinite or `NaN`, which would
+    /// corrupt weight sums of every sub tree above it.
+    pub fn insert(&mut self, val: T, weight: f64) {
+        assert!(weight.is_finite() && weight >= 0.0, "Weight must be finite and not negative");
+        fn insert<T: PartialOrd>(link: &mut Link<T>, val: T, weight: f64) {
+            match link {
+                None => *link = Some(Box::new(Node { val, weight, sum: weight, left: None, right: None })),
+                Some(node) => {
+                    if node.val > val {
+                        insert(&mut node.left, val, weight);
+                    } else {
+                        insert(&mut node.right, val, weight);
+                    }
+                    node.update();
+                }
+            }
+        }
+
+        insert(&mut self.root, val, weight);
+    }
+
+    /// Removes one copy of `val` and returns its weight.
+    /// Uses `O(n)` time.
+    pub fn remove(&mut self, val: T) -> Option<f64> {
+        fn pop_min<T>(link: &mut Link<T>) -> Option<Box<Node<T>>> {
+            match link {
+                None => None,
+                Some(node) if node.left.is_some() => {
+                    let min = pop_min(&mut node.left);
+                    node.update();
+                    min
+                },
+                Some(_) => {
+                    let mut min = link.take()?;
+                    *link = min.right.take();
+                    Some(min)
+                }
+            }
+        }
+
+        fn remove<T: PartialOrd>(link: &mut Link<T>, val: T) -> Option<f64> {
+            let node = link.as_mut()?;
+            let removed = if node.val == val {
+                let weight = node.weight;
+                match pop_min(&mut node.right) {
+                    None => *link = node.left.take(),
+                    Some(min) => {
+                        node.val = min.val;
+                        node.weight = min.weight;
+                        node.update();
+                    }
+                }
+                return Some(weight);
+            } else if node.val > val {
+                remove(&mut node.left, val)
+            } else if node.val < val {
+                remove(&mut node.right, val)
+            } else {
+                None
+            };
+            node.update();
+            removed
+        }
+
+        remove(&mut self.root, val)
+    }
+
+    /// Checks if element exists in a tree.
+    /// Uses `O(n)` time.
+    pub fn exists(&self, val: T) -> bool {
+        search::exists(self.root.as_deref(), &val)
+    }
+
+    /// Sum of weights of all elements smaller than `val`.
+    /// Uses `O(n)` time.
+    pub fn weight_rank(&self, val: T) -> f64 {
+        let mut rank = 0.0;
+        let mut current = &self.root;
+        while let Some(node) = current {
+            if node.val < val {
+                rank += sum(&node.left) + node.weight;
+                current = &node.right;
+            } else {
+                current = &node.left;
+            }
+        }
+        rank
+    }
+
+    /// Picks an element with probability proportional to its weight,
+    /// `u` has to be a uniform random sample from `[0, 1)`.
+    /// Returns `None` for an empty tree.
+    /// Uses `O(n)` time.
+    pub fn sample_weighted(&self, u: f64) -> Option<T> {
+        let mut target = u * self.total_weight();
+        let mut current = self.root.as_ref()?;
+        loop {
+            let left = sum(&current.left);
+            if target < left {
+                current = current.left.as_ref()?;
+                continue;
+            }
+            target -= left;
+            if target < current.weight {
+                return Some(current.val);
+            }
+            target -= current.weight;
+            match current.right {
+                None => return Some(current.val),
+                Some(ref right) => current = right
+            }
+        }
+    }
+
+    /// Inorder traverse tree which yields elements with their weights.
+    /// Uses `O(n)` time.
+    pub fn inorder(&self) -> Vec<(T, f64)> {
+        Nodes::new(self.root.as_deref()).map(|node| (node.val, node.weight)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WeightedTree;
+
+    #[test]
+    fn sample() {
+        let mut tree = WeightedTree::new();
+        assert_eq!(tree.sample_weighted(0.5), None);
+        for (v, w) in [(3, 1.0), (1, 2.0), (5, 1.0), (4, 4.0), (2, 2.0)] {
+            tree.insert(v, w);
+        }
+        assert_eq!(tree.total_weight(), 10.0);
+        assert_eq!(tree.sample_weighted(0.0), Some(1));
+        assert_eq!(tree.sample_weighted(0.35), Some(2));
+        assert_eq!(tree.sample_weighted(0.45), Some(3));
+        assert_eq!(tree.sample_weighted(0.55), Some(4));
+        assert_eq!(tree.sample_weighted(0.95), Some(5));
+        assert_eq!(tree.weight_rank(4), 5.0);
+        assert_eq!(tree.weight_rank(6), 10.0);
+    }
+    #[test]
+    fn remove() {
+        let mut tree = WeightedTree::new();
+        for v in [5, 2, 8, 1, 3, 7, 9] {
+            tree.insert(v, v as f64);
+        }
+        assert_eq!(tree.remove(5), Some(5.0));
+        assert_eq!(tree.remove(1), Some(1.0));
+        assert_eq!(tree.remove(6), None);
+        assert!(!tree.exists(5));
+        assert_eq!(tree.total_weight(), 29.0);
+        assert_eq!(tree.weight_rank(8), 12.0);
+        assert_eq!(tree.inorder().iter().map(|p| p.0).collect::<Vec<_>>(), vec![2, 3, 7, 8, 9]);
+
+        for v in [2, 3, 7, 8, 9] {
+            tree.remove(v);
+        }
+        assert!(tree.is_empty());
+    }
+    #[test]
+    #[should_panic(expected = "Weight must be finite and not negative")]
+    fn negative_weight() {
+        let mut tree = WeightedTree::new();
+        tree.insert(1, -1.0);
+    }
+    #[test]
+    #[should_panic(expected = "Weight must be finite and not negative")]
+    fn nan_weight() {
+        let mut tree = WeightedTree::new();
+        tree.insert(1, 1.0);
+        tree.insert(2, f64::NAN);
+    }
+}